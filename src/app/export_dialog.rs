@@ -0,0 +1,268 @@
+//! The export dialog and the per-format export options on the Settings page.
+
+use eframe::egui;
+use std::path::PathBuf;
+
+use super::{DesktopApp, masking};
+use crate::data::TableData;
+use crate::export::{self, ExportProfile, ExportScope, OptionKind};
+
+pub(super) struct ExportDialog {
+    format_id: String,
+    profile: ExportProfile,
+    /// Target that already exists, awaiting an overwrite-or-rename decision.
+    existing_path: Option<PathBuf>,
+}
+
+impl DesktopApp {
+    pub(super) fn show_export_settings(&mut self, ui: &mut egui::Ui) {
+        ui.heading("Export Formats");
+        ui.add_space(10.0);
+
+        let Some(exporter) = self.export_registry.get(&self.settings_export_format) else {
+            self.settings_export_format = "xlsx".to_string();
+            return;
+        };
+        let info = exporter.info();
+
+        egui::ComboBox::from_id_source("settings_export_format")
+            .selected_text(info.name)
+            .show_ui(ui, |ui| {
+                for exporter in self.export_registry.iter() {
+                    let info = exporter.info();
+                    ui.selectable_value(&mut self.settings_export_format, info.id.to_string(), info.name);
+                }
+            });
+        ui.add_space(10.0);
+
+        let options = self.settings.export_options.entry(info.id.to_string()).or_default();
+        if info.options.is_empty() {
+            ui.label(format!("{} has no options.", info.name));
+        }
+        egui::Grid::new("export_options_grid")
+            .num_columns(2)
+            .spacing([40.0, 6.0])
+            .show(ui, |ui| {
+                for spec in &info.options {
+                    ui.label(format!("{}:", spec.label)).on_hover_text(spec.help);
+                    let mut value = options.get(spec).to_string();
+                    let changed = match spec.kind {
+                        OptionKind::Text => ui.text_edit_singleline(&mut value).changed(),
+                        OptionKind::Bool => {
+                            let mut checked = value == "true";
+                            let changed = ui.checkbox(&mut checked, "").changed();
+                            value = checked.to_string();
+                            changed
+                        }
+                        OptionKind::Choice(choices) => {
+                            let mut changed = false;
+                            egui::ComboBox::from_id_source(spec.key).selected_text(value.clone()).show_ui(ui, |ui| {
+                                for choice in choices {
+                                    changed |= ui.selectable_value(&mut value, choice.to_string(), *choice).changed();
+                                }
+                            });
+                            changed
+                        }
+                    };
+                    if changed {
+                        options.set(spec.key, value);
+                    }
+                    ui.end_row();
+                }
+            });
+
+        ui.add_space(10.0);
+        if ui.button("💾 Save Settings").clicked() {
+            self.save_settings();
+        }
+    }
+
+    pub(super) fn open_export_dialog(&mut self, format_id: &str) {
+        self.export_dialog = Some(ExportDialog {
+            format_id: format_id.to_string(),
+            profile: self.settings.export_profile.clone(),
+            existing_path: None,
+        });
+    }
+
+    fn rows_for_scope(&self, scope: ExportScope) -> Vec<TableData> {
+        match scope {
+            ExportScope::All => self.data_store.get_all_data().clone(),
+            ExportScope::Filtered => self.data_store.get_data_in_range(&self.date_filter).into_iter().cloned().collect(),
+            ExportScope::Selection => self
+                .data_store
+                .get_all_data()
+                .iter()
+                .filter(|item| self.selected_ids.contains(&item.id))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    pub(super) fn show_export_dialog(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = &mut self.export_dialog else {
+            return;
+        };
+        let Some(exporter) = self.export_registry.get(&dialog.format_id) else {
+            self.export_dialog = None;
+            return;
+        };
+        let info = exporter.info();
+        let appends = self.settings.export_options.get(info.id).is_some_and(|options| exporter.appends(options));
+
+        let counts = [
+            self.data_store.get_record_count(),
+            self.data_store.get_data_in_range(&self.date_filter).len(),
+            self.data_store.get_all_data().iter().filter(|item| self.selected_ids.contains(&item.id)).count(),
+        ];
+        let mut export_to = None;
+        let mut cancelled = false;
+
+        egui::Window::new("Export")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.set_min_width(450.0);
+                let profile = &mut dialog.profile;
+
+                egui::Grid::new("export_dialog_grid")
+                    .num_columns(2)
+                    .spacing([20.0, 6.0])
+                    .show(ui, |ui| {
+                        ui.label("Format:");
+                        egui::ComboBox::from_id_source("export_dialog_format")
+                            .selected_text(format!("{} (.{})", info.name, info.extension))
+                            .show_ui(ui, |ui| {
+                                for exporter in self.export_registry.iter() {
+                                    let option = exporter.info();
+                                    ui.selectable_value(
+                                        &mut dialog.format_id,
+                                        option.id.to_string(),
+                                        format!("{} (.{})", option.name, option.extension),
+                                    );
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("Folder:");
+                        let mut directory = profile.directory.to_string_lossy().to_string();
+                        if ui.add(egui::TextEdit::singleline(&mut directory).desired_width(300.0)).changed() {
+                            profile.directory = PathBuf::from(directory);
+                        }
+                        ui.end_row();
+
+                        ui.label("File name:");
+                        ui.add(egui::TextEdit::singleline(&mut profile.filename_template).desired_width(300.0))
+                            .on_hover_text("Placeholders: {date}, {time}, {format}, {count}");
+                        ui.end_row();
+
+                        ui.label("Rows:");
+                        ui.vertical(|ui| {
+                            for (scope, count) in ExportScope::ALL.into_iter().zip(counts) {
+                                ui.radio_value(&mut profile.scope, scope, format!("{} ({})", scope.label(), count));
+                            }
+                        });
+                        ui.end_row();
+                    });
+
+                ui.add_space(10.0);
+                ui.strong("Columns");
+                let mut move_up = None;
+                egui::Grid::new("export_dialog_columns")
+                    .num_columns(5)
+                    .spacing([10.0, 4.0])
+                    .show(ui, |ui| {
+                        let last = profile.columns.len().saturating_sub(1);
+                        for (index, choice) in profile.columns.iter_mut().enumerate() {
+                            ui.checkbox(&mut choice.enabled, choice.column.field.label());
+                            ui.label("Header:");
+                            ui.add_enabled(choice.enabled, egui::TextEdit::singleline(&mut choice.column.header));
+                            ui.horizontal(|ui| {
+                                if ui.add_enabled(index > 0, egui::Button::new("⬆")).clicked() {
+                                    move_up = Some(index);
+                                }
+                                if ui.add_enabled(index < last, egui::Button::new("⬇")).clicked() {
+                                    move_up = Some(index + 1);
+                                }
+                            });
+                            ui.add_enabled_ui(choice.enabled, |ui| masking::rule_picker(ui, index, choice));
+                            ui.end_row();
+                        }
+                    });
+                if let Some(index) = move_up {
+                    profile.columns.swap(index - 1, index);
+                }
+                masking::salt_field(ui, profile);
+
+                let count = counts[ExportScope::ALL.iter().position(|scope| *scope == profile.scope).unwrap_or(0)];
+                let path = profile.file_path(&info, count);
+                let appending = appends && path.exists();
+                ui.add_space(10.0);
+                match appending {
+                    true => ui.label(format!("Appends to: {}", path.display())),
+                    false => ui.label(format!("Saves to: {}", path.display())),
+                };
+
+                ui.add_space(15.0);
+                if let Some(existing) = dialog.existing_path.clone() {
+                    let renamed = export::next_free_path(&existing);
+                    ui.colored_label(egui::Color32::YELLOW, format!("⚠ {} already exists.", existing.display()));
+                    ui.horizontal(|ui| {
+                        if ui.button("Overwrite").clicked() {
+                            export_to = Some(existing.clone());
+                        }
+                        let renamed_name = renamed.file_name().unwrap_or_default().to_string_lossy().to_string();
+                        if ui.button(format!("Rename to {}", renamed_name)).clicked() {
+                            export_to = Some(renamed);
+                        }
+                        if ui.button("Back").clicked() {
+                            dialog.existing_path = None;
+                        }
+                    });
+                } else {
+                    ui.horizontal(|ui| {
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            if ui.button("❌ Cancel").clicked() {
+                                cancelled = true;
+                            }
+                            let can_export = profile.columns.iter().any(|choice| choice.enabled) && count > 0;
+                            if ui.add_enabled(can_export, egui::Button::new("📤 Export")).clicked() {
+                                if path.exists() && !appending {
+                                    dialog.existing_path = Some(path);
+                                } else {
+                                    export_to = Some(path);
+                                }
+                            }
+                        });
+                    });
+                }
+            });
+
+        if cancelled {
+            self.export_dialog = None;
+        } else if let Some(path) = export_to
+            && let Some(dialog) = self.export_dialog.take()
+        {
+            self.settings.export_profile = dialog.profile.clone();
+            if let Err(e) = self.settings.save() {
+                log::warn!("Failed to save export settings: {}", e);
+            }
+            self.export_data(ctx, &dialog.format_id, &dialog.profile, path);
+        }
+    }
+
+    fn export_data(&mut self, ctx: &egui::Context, format_id: &str, profile: &ExportProfile, path: PathBuf) {
+        let Some(exporter) = self.export_registry.shared(format_id) else {
+            self.update_status = format!("Unknown export format: {}", format_id);
+            return;
+        };
+        let data = self.rows_for_scope(profile.scope);
+        let filter = match profile.scope {
+            ExportScope::All => "All rows".to_string(),
+            ExportScope::Filtered => format!("Dates {}", self.date_filter.describe(self.settings.locale)),
+            ExportScope::Selection => format!("{} selected rows", data.len()),
+        };
+        self.start_export(ctx, exporter, data, profile, filter, path);
+    }
+}
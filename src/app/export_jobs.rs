@@ -0,0 +1,336 @@
+//! Exports running in the background, with progress, cancelling and notifications.
+
+use eframe::egui;
+use std::path::PathBuf;
+use std::sync::{Arc, mpsc};
+use std::thread;
+use std::time::Duration;
+
+use super::DesktopApp;
+use super::schedules::ScheduledRun;
+use crate::data::TableData;
+use crate::export::{ExportColumn, ExportContext, ExportOptions, ExportProfile, ExportProgress, Exporter, Masking};
+use crate::locale::Locale;
+use crate::schedule::{RunOutcome, ScheduleLogEntry};
+
+/// An export running on a worker thread, kept until its notification is dismissed.
+pub(super) struct ExportJob {
+    format_name: &'static str,
+    path: PathBuf,
+    progress: Arc<ExportProgress>,
+    receiver: mpsc::Receiver<Result<usize, String>>,
+    state: ExportJobState,
+    /// Set for runs of a scheduled preset, which are logged and removed once done.
+    pub(super) scheduled: Option<ScheduledRun>,
+}
+
+enum ExportJobState {
+    Running,
+    Finished(usize),
+    Failed(String),
+    Cancelled,
+}
+
+/// Everything an export job's worker needs besides the records.
+struct ExportWork {
+    exporter: Arc<dyn Exporter>,
+    columns: Vec<ExportColumn>,
+    masking: Masking,
+    options: ExportOptions,
+    filter: String,
+    locale: Locale,
+    path: PathBuf,
+}
+
+impl ExportWork {
+    /// Masks `data`, writes it and returns how many records were written.
+    fn run(self, data: Vec<TableData>, progress: &ExportProgress) -> Result<usize, String> {
+        let masked = self.masking.describe();
+        let filter = match &masked {
+            Some(masked) => format!("{}; masked: {}", self.filter, masked),
+            None => self.filter,
+        };
+        // A cancelled append is rolled back, and the file it appends to must stay.
+        let remove_on_cancel = !(self.exporter.appends(&self.options) && self.path.exists());
+
+        let data = self.masking.apply(data);
+        let context = ExportContext {
+            data: &data,
+            columns: &self.columns,
+            options: &self.options,
+            filter: &filter,
+            locale: self.locale,
+            masked: masked.is_some(),
+            progress,
+        };
+        let directory = self.path.parent().map(PathBuf::from).unwrap_or_default();
+        let result = std::fs::create_dir_all(&directory)
+            .map_err(anyhow::Error::from)
+            .and_then(|()| self.exporter.export(&context, &self.path));
+        if progress.is_cancelled() && remove_on_cancel {
+            // Don't leave a half-written file behind
+            let _ = std::fs::remove_file(&self.path);
+        }
+        result.map(|()| data.len()).map_err(|e| format!("{:#}", e))
+    }
+}
+
+impl DesktopApp {
+    /// Starts an export job on a worker thread; `check_export_results` picks up the outcome.
+    /// `data` is a copy of the stored records and is masked on the worker.
+    pub(super) fn start_export(
+        &mut self,
+        ctx: &egui::Context,
+        exporter: Arc<dyn Exporter>,
+        data: Vec<TableData>,
+        profile: &ExportProfile,
+        filter: String,
+        path: PathBuf,
+    ) -> &mut ExportJob {
+        let info = exporter.info();
+        let work = ExportWork {
+            options: self.settings.export_options.get(info.id).cloned().unwrap_or_default(),
+            exporter,
+            columns: profile.columns(),
+            masking: profile.masking(),
+            filter,
+            locale: self.settings.locale,
+            path: path.clone(),
+        };
+
+        let (tx, rx) = mpsc::channel();
+        let progress = Arc::new(ExportProgress::new(data.len()));
+        self.update_status = format!("Exporting {} rows...", data.len());
+
+        let ctx_clone = ctx.clone();
+        let job_progress = progress.clone();
+        thread::spawn(move || {
+            let _ = tx.send(work.run(data, &job_progress));
+            ctx_clone.request_repaint();
+        });
+
+        self.export_jobs.push(ExportJob {
+            format_name: info.name,
+            path,
+            progress,
+            receiver: rx,
+            state: ExportJobState::Running,
+            scheduled: None,
+        });
+        self.export_jobs.last_mut().expect("the job was just added")
+    }
+
+    pub(super) fn check_export_results(&mut self, ctx: &egui::Context) {
+        for job in &mut self.export_jobs {
+            if !matches!(job.state, ExportJobState::Running) {
+                continue;
+            }
+            match job.receiver.try_recv() {
+                Ok(_) if job.progress.is_cancelled() => {
+                    job.state = ExportJobState::Cancelled;
+                    self.update_status = format!("Export to {} cancelled", job.path.display());
+                }
+                Ok(Ok(count)) => {
+                    job.state = ExportJobState::Finished(count);
+                    self.update_status = format!("Exported {} rows to: {}", count, job.path.display());
+                }
+                Ok(Err(e)) => {
+                    self.update_status = format!("Export failed: {}", e);
+                    job.state = ExportJobState::Failed(e);
+                }
+                Err(mpsc::TryRecvError::Empty) => {}
+                Err(mpsc::TryRecvError::Disconnected) => {
+                    job.state = ExportJobState::Failed("The export thread stopped unexpectedly".to_string());
+                }
+            }
+        }
+
+        // Scheduled runs go to the log instead of waiting to be dismissed.
+        let (finished, jobs): (Vec<ExportJob>, Vec<ExportJob>) = std::mem::take(&mut self.export_jobs)
+            .into_iter()
+            .partition(|job| job.scheduled.is_some() && !matches!(job.state, ExportJobState::Running));
+        self.export_jobs = jobs;
+        for job in finished {
+            let Some(run) = job.scheduled else { continue };
+            let outcome = match job.state {
+                ExportJobState::Finished(records) => RunOutcome::Exported { path: job.path, records },
+                ExportJobState::Failed(error) => RunOutcome::Failed(error),
+                ExportJobState::Running | ExportJobState::Cancelled => RunOutcome::Failed("Cancelled".to_string()),
+            };
+            let entry = ScheduleLogEntry {
+                at: run.started,
+                preset_id: run.preset_id,
+                preset: run.preset,
+                due: run.due,
+                outcome,
+            };
+            if let Err(e) = self.schedule_log.record(entry) {
+                log::warn!("Failed to write export schedule log: {}", e);
+            }
+        }
+
+        if self.export_jobs.iter().any(|job| matches!(job.state, ExportJobState::Running)) {
+            ctx.request_repaint_after(Duration::from_millis(200));
+        }
+    }
+
+    /// Progress of running exports and notifications for finished ones.
+    pub(super) fn show_export_jobs(&mut self, ctx: &egui::Context) {
+        if self.export_jobs.is_empty() {
+            return;
+        }
+
+        let mut dismissed = None;
+        egui::Window::new("📤 Exports")
+            .anchor(egui::Align2::RIGHT_BOTTOM, [-10.0, -10.0])
+            .collapsible(false)
+            .resizable(false)
+            .show(ctx, |ui| {
+                for (index, job) in self.export_jobs.iter().enumerate() {
+                    if index > 0 {
+                        ui.separator();
+                    }
+                    let file_name = job
+                        .path
+                        .file_name()
+                        .map(|name| name.to_string_lossy().to_string())
+                        .unwrap_or_default();
+                    ui.strong(format!("{}: {}", job.format_name, file_name));
+
+                    match &job.state {
+                        ExportJobState::Running => {
+                            ui.horizontal(|ui| {
+                                ui.add(
+                                    egui::ProgressBar::new(job.progress.fraction())
+                                        .desired_width(220.0)
+                                        .show_percentage(),
+                                );
+                                let cancelling = job.progress.is_cancelled();
+                                if ui.add_enabled(!cancelling, egui::Button::new("Cancel")).clicked() {
+                                    job.progress.cancel();
+                                }
+                            });
+                        }
+                        ExportJobState::Finished(count) => {
+                            ui.label(format!("✅ Exported {} rows", count));
+                            ui.horizontal(|ui| {
+                                if ui.button("Open file").clicked() {
+                                    if let Err(e) = open_path(&job.path) {
+                                        self.update_status = format!("Failed to open file: {}", e);
+                                    }
+                                    dismissed = Some(index);
+                                }
+                                if ui.button("Open containing folder").clicked() {
+                                    if let Some(folder) = job.path.parent()
+                                        && let Err(e) = open_path(folder)
+                                    {
+                                        self.update_status = format!("Failed to open folder: {}", e);
+                                    }
+                                    dismissed = Some(index);
+                                }
+                                if ui.button("Dismiss").clicked() {
+                                    dismissed = Some(index);
+                                }
+                            });
+                        }
+                        ExportJobState::Failed(e) => {
+                            ui.colored_label(egui::Color32::RED, format!("❌ {}", e));
+                            if ui.button("Dismiss").clicked() {
+                                dismissed = Some(index);
+                            }
+                        }
+                        ExportJobState::Cancelled => {
+                            ui.label("Cancelled");
+                            if ui.button("Dismiss").clicked() {
+                                dismissed = Some(index);
+                            }
+                        }
+                    }
+                }
+            });
+
+        if let Some(index) = dismissed {
+            self.export_jobs.remove(index);
+        }
+    }
+}
+
+/// Opens a file or folder with the system's default application.
+fn open_path(path: &std::path::Path) -> std::io::Result<()> {
+    let program = if cfg!(target_os = "windows") {
+        "explorer"
+    } else if cfg!(target_os = "macos") {
+        "open"
+    } else {
+        "xdg-open"
+    };
+    std::process::Command::new(program).arg(path).spawn().map(|_| ())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::Field;
+    use crate::export::testing::{self, TempDir};
+    use crate::export::{ExportRegistry, MaskRule};
+
+    fn work(format_id: &str, path: PathBuf) -> ExportWork {
+        ExportWork {
+            exporter: ExportRegistry::with_builtin().shared(format_id).unwrap(),
+            columns: ExportColumn::all(),
+            masking: Masking::default(),
+            options: ExportOptions::default(),
+            filter: "All rows".to_string(),
+            locale: Locale::Standard,
+            path,
+        }
+    }
+
+    #[test]
+    fn finished_jobs_report_every_row_and_write_masked_records() {
+        let dir = TempDir::new();
+        let path = dir.join("exports").join("records.csv");
+        let mut job = work("csv", path.clone());
+        job.masking.rules = vec![(Field::Name, MaskRule::Redact)];
+        let progress = ExportProgress::new(3);
+
+        assert_eq!(job.run(testing::records(), &progress), Ok(3));
+
+        assert_eq!((progress.done(), progress.fraction()), (3, 1.0));
+        let csv = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(csv.matches("[redacted]").count(), 3, "{}", csv);
+        assert!(!csv.contains("Alpha"));
+    }
+
+    #[test]
+    fn cancelled_jobs_leave_no_file_behind() {
+        let dir = TempDir::new();
+        let progress = ExportProgress::new(3);
+        progress.cancel();
+
+        let result = work("csv", dir.join("records.csv")).run(testing::records(), &progress);
+
+        assert!(result.is_err());
+        assert!(dir.files().is_empty());
+    }
+
+    #[test]
+    fn cancelled_appends_keep_the_file_they_append_to() {
+        let dir = TempDir::new();
+        let path = dir.join("records.sqlite");
+        let appending = || {
+            let mut job = work("sqlite", path.clone());
+            job.options.set("append", "true");
+            job
+        };
+        assert_eq!(appending().run(testing::records(), &ExportProgress::new(3)), Ok(3));
+        let before = std::fs::read(&path).unwrap();
+
+        let progress = ExportProgress::new(3);
+        progress.cancel();
+        assert!(appending().run(testing::records(), &progress).is_err());
+
+        assert_eq!(std::fs::read(&path).unwrap(), before);
+        assert_eq!(dir.files(), ["records.sqlite"]);
+    }
+}
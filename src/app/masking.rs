@@ -0,0 +1,47 @@
+//! Widgets for choosing how exported columns are masked.
+
+use eframe::egui;
+
+use crate::export::{self, ColumnChoice, ExportProfile, MaskRule};
+
+/// Picks the rule for one column, with the percentage for random noise.
+pub(super) fn rule_picker(ui: &mut egui::Ui, index: usize, choice: &mut ColumnChoice) {
+    ui.horizontal(|ui| {
+        egui::ComboBox::from_id_source(("export_dialog_mask", index))
+            .selected_text(choice.mask.label())
+            .show_ui(ui, |ui| {
+                for rule in MaskRule::choices(choice.column.field) {
+                    let selected = std::mem::discriminant(rule) == std::mem::discriminant(&choice.mask);
+                    if ui.selectable_label(selected, rule.label()).clicked() && !selected {
+                        choice.mask = *rule;
+                    }
+                }
+            });
+        if let MaskRule::Noise(percent) = &mut choice.mask {
+            ui.add(egui::DragValue::new(percent).clamp_range(0.0..=100.0).suffix(" %"));
+        }
+    });
+}
+
+/// The salt of hashed and pseudonymised columns, shown while any are exported.
+pub(super) fn salt_field(ui: &mut egui::Ui, profile: &mut ExportProfile) {
+    if !profile.columns.iter().any(|choice| choice.enabled && choice.mask.uses_salt()) {
+        return;
+    }
+    ui.horizontal(|ui| {
+        ui.label("Masking salt:");
+        ui.add(egui::TextEdit::singleline(&mut profile.mask_salt).desired_width(260.0))
+            .on_hover_text("Keep the salt to mask the same names the same way in later exports");
+        if ui.button("🎲 New").clicked() {
+            profile.mask_salt = export::new_salt();
+        }
+    });
+}
+
+/// Column header with its masking rule, e.g. "Name (pseudonym)".
+pub(super) fn column_label(choice: &ColumnChoice) -> String {
+    match choice.mask {
+        MaskRule::Keep => choice.column.header.clone(),
+        mask => format!("{} ({})", choice.column.header, mask.describe()),
+    }
+}
@@ -0,0 +1,1108 @@
+mod export_dialog;
+mod export_jobs;
+mod masking;
+mod schedules;
+mod snapshots;
+mod sync;
+
+use eframe::egui;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+use std::sync::mpsc;
+use std::thread;
+use std::time::Instant;
+
+use crate::data::{DataStore, DateRange};
+use crate::export::{self, ExportRegistry};
+use crate::locale::Locale;
+use crate::schedule::ScheduleLog;
+use crate::settings::AppSettings;
+use crate::snapshot::{RecordDiff, SnapshotManager};
+use crate::storage;
+use crate::sync::{Conflict, SyncLog, SyncState};
+use crate::timeline::{self, Granularity};
+use crate::updater::{self, AppUpdater, UpdateChannel};
+
+use export_dialog::ExportDialog;
+use export_jobs::ExportJob;
+use snapshots::CompareSource;
+use sync::SyncResult;
+
+#[derive(Default)]
+pub enum AppPage {
+    #[default]
+    Home,
+    DataTable,
+    Timeline,
+    Snapshots,
+    Settings,
+    About,
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum FileDialogMode {
+    Open,
+    Save,
+    ChangePassword,
+    /// Reads the records back from an Excel export.
+    Import,
+}
+
+pub struct FileDialog {
+    mode: FileDialogMode,
+    path: String,
+    encrypt: bool,
+    password: String,
+    new_password: String,
+    confirm_password: String,
+    error: Option<String>,
+}
+
+#[derive(Default)]
+pub enum UpdateState {
+    #[default]
+    Idle,
+    Checking,
+    Available(String), // Contains the new version number
+    Downloading,
+    Downloaded,
+    Error(String),
+}
+
+pub struct DesktopApp {
+    current_page: AppPage,
+    data_store: DataStore,
+    date_filter: DateRange,
+    selected_ids: BTreeSet<u32>,
+    timeline_granularity: Granularity,
+    snapshot_manager: SnapshotManager,
+    snapshot_name: String,
+    compare_base: CompareSource,
+    compare_target: CompareSource,
+    compare_file_path: String,
+    compare_file_password: String,
+    snapshot_diff: Option<RecordDiff>,
+    file_dialog: Option<FileDialog>,
+    current_file: Option<std::path::PathBuf>,
+    current_password: Option<String>,
+    export_registry: ExportRegistry,
+    export_dialog: Option<ExportDialog>,
+    export_jobs: Vec<ExportJob>,
+    schedule_log: ScheduleLog,
+    /// When a preset was last edited, until the change is saved.
+    presets_changed_at: Option<Instant>,
+    preset_save_error: Option<String>,
+    settings_export_format: String,
+    settings: AppSettings,
+    editing: bool,
+    sync_receiver: Option<mpsc::Receiver<SyncResult>>,
+    sync_state: SyncState,
+    sync_log: SyncLog,
+    sync_conflicts: Vec<Conflict>,
+    synced_revision: u64,
+    last_sync: Option<Instant>,
+    offline: bool,
+    updater: AppUpdater,
+    update_status: String,
+    update_receiver: Option<mpsc::Receiver<UpdateResult>>,
+    
+    // Update dialog state
+    update_state: UpdateState,
+    show_update_dialog: bool,
+    available_version: String,
+}
+
+#[derive(Debug)]
+enum UpdateResult {
+    UpdateAvailable(String),
+    NoUpdate,
+    UpdateDownloaded,
+    Error(String),
+}
+
+impl DesktopApp {
+    pub fn new(_cc: &eframe::CreationContext<'_>) -> Self {
+        Self {
+            current_page: AppPage::default(),
+            data_store: DataStore::new(),
+            date_filter: DateRange::default(),
+            selected_ids: BTreeSet::new(),
+            timeline_granularity: Granularity::default(),
+            snapshot_manager: SnapshotManager::new(),
+            snapshot_name: String::new(),
+            compare_base: CompareSource::Snapshot(0),
+            compare_target: CompareSource::CurrentData,
+            compare_file_path: String::new(),
+            compare_file_password: String::new(),
+            snapshot_diff: None,
+            file_dialog: None,
+            current_file: None,
+            current_password: None,
+            export_registry: ExportRegistry::with_builtin(),
+            export_dialog: None,
+            export_jobs: Vec::new(),
+            schedule_log: ScheduleLog::load(),
+            presets_changed_at: None,
+            preset_save_error: None,
+            settings_export_format: "csv".to_string(),
+            settings: AppSettings::load(),
+            editing: false,
+            sync_receiver: None,
+            sync_state: SyncState::load(),
+            sync_log: SyncLog::load(),
+            sync_conflicts: Vec::new(),
+            synced_revision: 0,
+            last_sync: None,
+            offline: false,
+            updater: AppUpdater::new(),
+            update_status: "Ready".to_string(),
+            update_receiver: None,
+            update_state: UpdateState::default(),
+            show_update_dialog: false,
+            available_version: String::new(),
+        }
+    }
+
+    fn show_menubar(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
+        egui::TopBottomPanel::top("menubar").show(ctx, |ui| {
+            egui::menu::bar(ui, |ui| {
+                ui.menu_button("File", |ui| {
+                    if ui.button("📁 Open").clicked() {
+                        self.open_file_dialog(FileDialogMode::Open);
+                        ui.close_menu();
+                    }
+                    if ui.button("💾 Save").clicked() {
+                        self.open_file_dialog(FileDialogMode::Save);
+                        ui.close_menu();
+                    }
+                    if ui.button("🔑 Change Password").clicked() {
+                        self.open_file_dialog(FileDialogMode::ChangePassword);
+                        ui.close_menu();
+                    }
+                    ui.separator();
+                    if ui.button("📥 Import Excel Export").clicked() {
+                        self.open_file_dialog(FileDialogMode::Import);
+                        ui.close_menu();
+                    }
+                    ui.menu_button("📤 Export", |ui| {
+                        let mut selected = None;
+                        for exporter in self.export_registry.iter() {
+                            let info = exporter.info();
+                            if ui.button(format!("{} (.{})", info.name, info.extension)).clicked() {
+                                selected = Some(info.id);
+                            }
+                        }
+                        if let Some(format_id) = selected {
+                            self.open_export_dialog(format_id);
+                            ui.close_menu();
+                        }
+                    });
+                    ui.separator();
+                    if ui.button("❌ Exit").clicked() {
+                        std::process::exit(0);
+                    }
+                });
+
+                ui.menu_button("View", |ui| {
+                    if ui.button("🏠 Home").clicked() {
+                        self.current_page = AppPage::Home;
+                        ui.close_menu();
+                    }
+                    if ui.button("📊 Data Table").clicked() {
+                        self.current_page = AppPage::DataTable;
+                        ui.close_menu();
+                    }
+                    if ui.button("📅 Timeline").clicked() {
+                        self.current_page = AppPage::Timeline;
+                        ui.close_menu();
+                    }
+                    if ui.button("📸 Snapshots").clicked() {
+                        self.current_page = AppPage::Snapshots;
+                        ui.close_menu();
+                    }
+                    if ui.button("⚙️ Settings").clicked() {
+                        self.current_page = AppPage::Settings;
+                        ui.close_menu();
+                    }
+                });
+
+                ui.menu_button("Help", |ui| {
+                    if ui.button("🔄 Check for Updates").clicked() {
+                        self.check_for_updates(ctx);
+                        ui.close_menu();
+                    }
+                    if ui.button("ℹ️ About").clicked() {
+                        self.current_page = AppPage::About;
+                        ui.close_menu();
+                    }
+                });
+
+                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                    match &self.update_state {
+                        UpdateState::Checking => ui.label("🔄 Checking for updates..."),
+                        UpdateState::Available(version) => ui.label(format!("⬆️ Version {} available", version)),
+                        UpdateState::Downloading => ui.label("⬇️ Downloading update..."),
+                        UpdateState::Downloaded => ui.label("✅ Update ready! Restart app."),
+                        UpdateState::Error(_) => ui.label("❌ Update check failed"),
+                        _ => ui.label(&self.update_status),
+                    };
+                });
+            });
+        });
+    }
+
+    fn show_update_dialog(&mut self, ctx: &egui::Context) {
+        if !self.show_update_dialog {
+            return;
+        }
+
+        egui::Window::new("Update Available")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.set_min_width(400.0);
+                let switching = self.is_channel_switch();
+                let channel = self.settings.update_channel.label();
+                
+                // Header with icon and title
+                ui.horizontal(|ui| {
+                    if switching {
+                        ui.label("🔀");
+                        ui.heading(format!("Switch to the {} Channel", channel));
+                    } else {
+                        ui.label("🎉");
+                        ui.heading("New Version Available!");
+                    }
+                });
+                
+                ui.add_space(10.0);
+                ui.separator();
+                ui.add_space(10.0);
+
+                // Version information
+                ui.horizontal(|ui| {
+                    ui.label("Current version:");
+                    ui.strong(env!("CARGO_PKG_VERSION"));
+                });
+                
+                ui.horizontal(|ui| {
+                    ui.label(if switching { format!("{} version:", channel) } else { "New version:".to_string() });
+                    ui.strong(&self.available_version);
+                });
+
+                ui.add_space(15.0);
+
+                // Update description
+                if switching {
+                    ui.label(format!(
+                        "This pre-release is not on the {} channel. The latest {} release replaces it, even though its version number is lower.",
+                        channel.to_lowercase(),
+                        channel.to_lowercase()
+                    ));
+                    ui.label("Would you like to switch now?");
+                } else {
+                    ui.label("A new version of the application is available for download.");
+                    ui.label("Would you like to update now?");
+                }
+
+                ui.add_space(20.0);
+
+                // Show different content based on update state
+                match &self.update_state {
+                    UpdateState::Available(_) => {
+                        ui.horizontal(|ui| {
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                if ui.button("❌ Later").clicked() {
+                                    self.show_update_dialog = false;
+                                    self.update_state = UpdateState::Idle;
+                                }
+                                
+                                if ui.button("⬇️ Update Now").clicked() {
+                                    self.download_update(ctx);
+                                }
+                            });
+                        });
+                    }
+                    
+                    UpdateState::Downloading => {
+                        ui.horizontal(|ui| {
+                            ui.spinner();
+                            ui.label("Downloading update...");
+                        });
+                        ui.add_space(10.0);
+                        ui.label("Please wait while the update is being downloaded.");
+                    }
+                    
+                    UpdateState::Downloaded => {
+                        ui.colored_label(egui::Color32::GREEN, "✅ Update downloaded successfully!");
+                        ui.add_space(10.0);
+                        ui.label("Please restart the application to complete the update.");
+                        
+                        ui.add_space(15.0);
+                        ui.horizontal(|ui| {
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                if ui.button("🔄 Restart Now").clicked() {
+                                    // Restart the application
+                                    std::process::exit(0);
+                                }
+                                
+                                if ui.button("📋 Continue").clicked() {
+                                    self.show_update_dialog = false;
+                                    self.update_state = UpdateState::Idle;
+                                }
+                            });
+                        });
+                    }
+                    
+                    UpdateState::Error(error) => {
+                        ui.colored_label(egui::Color32::RED, "❌ Update failed!");
+                        ui.add_space(5.0);
+                        ui.label(format!("Error: {}", error));
+                        
+                        ui.add_space(15.0);
+                        ui.horizontal(|ui| {
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                if ui.button("❌ Close").clicked() {
+                                    self.show_update_dialog = false;
+                                    self.update_state = UpdateState::Idle;
+                                }
+                                
+                                if ui.button("🔄 Retry").clicked() {
+                                    self.download_update(ctx);
+                                }
+                            });
+                        });
+                    }
+                    
+                    _ => {}
+                }
+            });
+    }
+
+    fn open_file_dialog(&mut self, mode: FileDialogMode) {
+        let path = match (mode, self.current_file.clone()) {
+            (FileDialogMode::Import, _) => dirs::download_dir().unwrap_or_else(|| PathBuf::from(".")).join(""),
+            (_, Some(path)) => path,
+            (_, None) => {
+                let mut path = dirs::document_dir().unwrap_or_else(|| std::path::PathBuf::from("."));
+                path.push("data.json");
+                path
+            }
+        };
+        let password = match mode {
+            FileDialogMode::Save => self.current_password.clone().unwrap_or_default(),
+            _ => String::new(),
+        };
+        self.file_dialog = Some(FileDialog {
+            mode,
+            path: path.to_string_lossy().to_string(),
+            encrypt: mode == FileDialogMode::Save && self.current_password.is_some(),
+            confirm_password: password.clone(),
+            password,
+            new_password: String::new(),
+            error: None,
+        });
+    }
+
+    fn show_file_dialog(&mut self, ctx: &egui::Context) {
+        let Some(dialog) = &mut self.file_dialog else {
+            return;
+        };
+
+        let title = match dialog.mode {
+            FileDialogMode::Open => "Open Data File",
+            FileDialogMode::Save => "Save Data File",
+            FileDialogMode::ChangePassword => "Change File Password",
+            FileDialogMode::Import => "Import Excel Export",
+        };
+        let mut confirmed = false;
+        let mut cancelled = false;
+
+        egui::Window::new(title)
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.set_min_width(400.0);
+                egui::Grid::new("file_dialog_grid")
+                    .num_columns(2)
+                    .spacing([10.0, 6.0])
+                    .show(ui, |ui| {
+                        ui.label("Path:");
+                        ui.add(egui::TextEdit::singleline(&mut dialog.path).desired_width(320.0));
+                        ui.end_row();
+
+                        match dialog.mode {
+                            FileDialogMode::Open => {
+                                ui.label("Password:");
+                                ui.add(egui::TextEdit::singleline(&mut dialog.password).password(true))
+                                    .on_hover_text("Only needed for encrypted files");
+                                ui.end_row();
+                            }
+                            FileDialogMode::Save => {
+                                ui.label("");
+                                ui.checkbox(&mut dialog.encrypt, "🔒 Encrypt with password");
+                                ui.end_row();
+                                if dialog.encrypt {
+                                    ui.label("Password:");
+                                    ui.add(egui::TextEdit::singleline(&mut dialog.password).password(true));
+                                    ui.end_row();
+                                    ui.label("Confirm:");
+                                    ui.add(egui::TextEdit::singleline(&mut dialog.confirm_password).password(true));
+                                    ui.end_row();
+                                }
+                            }
+                            FileDialogMode::ChangePassword => {
+                                ui.label("Current password:");
+                                ui.add(egui::TextEdit::singleline(&mut dialog.password).password(true))
+                                    .on_hover_text("Leave empty if the file is not encrypted");
+                                ui.end_row();
+                                ui.label("New password:");
+                                ui.add(egui::TextEdit::singleline(&mut dialog.new_password).password(true))
+                                    .on_hover_text("Leave empty to remove encryption");
+                                ui.end_row();
+                                ui.label("Confirm:");
+                                ui.add(egui::TextEdit::singleline(&mut dialog.confirm_password).password(true));
+                                ui.end_row();
+                            }
+                            FileDialogMode::Import => {
+                                ui.label("");
+                                ui.label("Excel files exported with round-trip data are restored exactly.");
+                                ui.end_row();
+                            }
+                        }
+                    });
+
+                if let Some(error) = &dialog.error {
+                    ui.add_space(10.0);
+                    ui.colored_label(egui::Color32::RED, format!("❌ {}", error));
+                }
+
+                ui.add_space(15.0);
+                ui.horizontal(|ui| {
+                    ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                        if ui.button("❌ Cancel").clicked() {
+                            cancelled = true;
+                        }
+                        let label = match dialog.mode {
+                            FileDialogMode::Open => "📁 Open",
+                            FileDialogMode::Save => "💾 Save",
+                            FileDialogMode::ChangePassword => "🔑 Change",
+                            FileDialogMode::Import => "📥 Import",
+                        };
+                        if ui.button(label).clicked() {
+                            confirmed = true;
+                        }
+                    });
+                });
+            });
+
+        if cancelled {
+            self.file_dialog = None;
+        } else if confirmed {
+            let mode = dialog.mode;
+            let path = std::path::PathBuf::from(dialog.path.trim());
+            let password = Some(dialog.password.clone()).filter(|password| !password.is_empty());
+
+            let result = match mode {
+                FileDialogMode::Open => storage::load_file(&path, password.as_deref()).map(|(records, encrypted)| {
+                    self.data_store.replace_data(records);
+                    // A password typed for an unencrypted file is not kept, so
+                    // neither snapshots nor the next save get encrypted with it.
+                    self.current_password = password.filter(|_| encrypted);
+                    self.snapshot_manager.set_password(self.current_password.clone());
+                    format!("Opened {}", path.display())
+                }),
+                FileDialogMode::Save => {
+                    if !dialog.encrypt {
+                        storage::save_records(&path, self.data_store.get_all_data(), None).map(|()| {
+                            self.current_password = None;
+                            self.snapshot_manager.set_password(None);
+                            format!("Saved to {}", path.display())
+                        })
+                    } else if password.is_none() {
+                        Err(anyhow::anyhow!("Enter a password to encrypt the file"))
+                    } else if dialog.password != dialog.confirm_password {
+                        Err(anyhow::anyhow!("Passwords do not match"))
+                    } else {
+                        storage::save_records(&path, self.data_store.get_all_data(), password.as_deref()).map(|()| {
+                            self.current_password = password;
+                            self.snapshot_manager.set_password(self.current_password.clone());
+                            format!("Saved encrypted file to {}", path.display())
+                        })
+                    }
+                }
+                FileDialogMode::Import => export::import_excel(&path).map(|records| {
+                    let count = records.len();
+                    self.data_store.replace_data(records);
+                    format!("Imported {} records from {}", count, path.display())
+                }),
+                FileDialogMode::ChangePassword => {
+                    let new_password = Some(dialog.new_password.clone()).filter(|password| !password.is_empty());
+                    if dialog.new_password != dialog.confirm_password {
+                        Err(anyhow::anyhow!("Passwords do not match"))
+                    } else {
+                        let change_file = || storage::change_password(&path, password.as_deref(), new_password.as_deref());
+                        // The open file's snapshots change password together with it.
+                        let changed = match self.current_file.as_ref() == Some(&path) {
+                            true => self.snapshot_manager.change_password(new_password.clone(), change_file).map(|()| {
+                                self.current_password = new_password.clone();
+                            }),
+                            false => change_file(),
+                        };
+                        changed.map(|()| match new_password {
+                            Some(_) => format!("Password changed for {}", path.display()),
+                            None => format!("Encryption removed from {}", path.display()),
+                        })
+                    }
+                }
+            };
+            match result {
+                Ok(status) => {
+                    self.update_status = status;
+                    if matches!(mode, FileDialogMode::Open | FileDialogMode::Save) {
+                        self.current_file = Some(path);
+                    }
+                    self.file_dialog = None;
+                }
+                Err(e) => {
+                    let action = match mode {
+                        FileDialogMode::Open => "Open",
+                        FileDialogMode::Save => "Save",
+                        FileDialogMode::ChangePassword => "Password change",
+                        FileDialogMode::Import => "Import",
+                    };
+                    self.update_status = format!("{} failed", action);
+                    dialog.error = Some(e.to_string());
+                }
+            }
+        }
+    }
+
+    fn show_home_page(&mut self, ctx: &egui::Context) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Welcome to Desktop Application");
+            ui.add_space(20.0);
+
+            ui.horizontal(|ui| {
+                if ui.button("🎯 Action Button 1").clicked() {
+                    self.data_store.add_sample_data();
+                }
+                if ui.button("🚀 Action Button 2").clicked() {
+                    self.update_status = "Action 2 performed!".to_string();
+                }
+                if ui.button("💫 Action Button 3").clicked() {
+                    self.update_status = "Action 3 performed!".to_string();
+                }
+            });
+
+            ui.add_space(20.0);
+            ui.separator();
+            ui.add_space(20.0);
+
+            ui.heading("Quick Stats");
+            egui::Grid::new("stats_grid")
+                .num_columns(2)
+                .spacing([40.0, 4.0])
+                .show(ui, |ui| {
+                    ui.label("Total Records:");
+                    ui.label(self.settings.locale.format_count(self.data_store.get_record_count()));
+                    ui.end_row();
+
+                    ui.label("Last Updated:");
+                    ui.label(self.settings.locale.format_date_time(&chrono::Local::now()));
+                    ui.end_row();
+
+                    ui.label("Version:");
+                    ui.label(env!("CARGO_PKG_VERSION"));
+                    ui.end_row();
+                });
+        });
+    }
+
+    fn show_data_table_page(&mut self, ctx: &egui::Context) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Data Table");
+            ui.add_space(10.0);
+
+            ui.horizontal(|ui| {
+                if ui.button("➕ Add Sample Data").clicked() {
+                    self.data_store.add_sample_data();
+                }
+                if ui.button("🗑️ Clear Data").clicked() {
+                    self.data_store.clear_data();
+                    self.selected_ids.clear();
+                }
+                if ui.button("📤 Export...").clicked() {
+                    self.open_export_dialog("xlsx");
+                }
+                ui.toggle_value(&mut self.editing, "✏️ Edit");
+
+                if self.settings.rest.enabled {
+                    ui.separator();
+                    let syncing = self.sync_receiver.is_some();
+                    let pending = self.sync_state.queue_len();
+                    let label = if pending > 0 { format!("🔄 Sync ({} queued)", pending) } else { "🔄 Sync".to_string() };
+                    if ui.add_enabled(!syncing, egui::Button::new(label)).clicked() {
+                        self.start_sync(ctx);
+                    }
+                    if syncing {
+                        ui.spinner();
+                    } else if self.offline {
+                        ui.colored_label(egui::Color32::YELLOW, "⚠ Offline")
+                            .on_hover_text("Edits are queued and will be sent when the connection returns");
+                    }
+                    if !self.sync_conflicts.is_empty() {
+                        ui.colored_label(egui::Color32::YELLOW, format!("⚠ {} conflicts", self.sync_conflicts.len()));
+                    }
+                }
+            });
+
+            ui.add_space(10.0);
+            self.show_date_filter(ui);
+
+            ui.add_space(10.0);
+            ui.separator();
+            ui.add_space(10.0);
+
+            // Show table
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                egui::Grid::new("data_table")
+                    .num_columns(5)
+                    .spacing([10.0, 4.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        let visible = self.data_store.get_data_in_range(&self.date_filter);
+
+                        // Header
+                        let mut all_selected =
+                            !visible.is_empty() && visible.iter().all(|item| self.selected_ids.contains(&item.id));
+                        if ui.checkbox(&mut all_selected, "").on_hover_text("Select all shown rows").changed() {
+                            for item in &visible {
+                                if all_selected {
+                                    self.selected_ids.insert(item.id);
+                                } else {
+                                    self.selected_ids.remove(&item.id);
+                                }
+                            }
+                        }
+                        ui.strong("ID");
+                        ui.strong("Name");
+                        ui.strong("Value");
+                        ui.strong("Date");
+                        ui.end_row();
+
+                        // Data rows
+                        let mut edits = Vec::new();
+                        for item in visible {
+                            let mut selected = self.selected_ids.contains(&item.id);
+                            if ui.checkbox(&mut selected, "").changed() {
+                                if selected {
+                                    self.selected_ids.insert(item.id);
+                                } else {
+                                    self.selected_ids.remove(&item.id);
+                                }
+                            }
+
+                            if self.settings.rest.enabled && self.data_store.is_modified(item.id) {
+                                ui.label(format!("{} ✏", item.id)).on_hover_text("Not pushed yet");
+                            } else {
+                                ui.label(item.id.to_string());
+                            }
+
+                            if self.editing {
+                                let mut name = item.name.clone();
+                                let mut value = item.value;
+                                let name_changed = ui.text_edit_singleline(&mut name).changed();
+                                let value_changed = ui.add(egui::DragValue::new(&mut value).speed(1.0)).changed();
+                                if name_changed || value_changed {
+                                    edits.push((item.id, name, value));
+                                }
+                            } else {
+                                ui.label(&item.name);
+                                ui.label(self.settings.locale.format_number(item.value, 2));
+                            }
+                            ui.label(self.settings.locale.format_date(&item.date));
+                            ui.end_row();
+                        }
+
+                        for (id, name, value) in edits {
+                            self.data_store.update_record(id, name, value);
+                        }
+                    });
+            });
+        });
+    }
+
+    fn show_date_filter(&mut self, ui: &mut egui::Ui) {
+        let today = chrono::Local::now().date_naive();
+
+        ui.horizontal(|ui| {
+            let mut has_start = self.date_filter.start.is_some();
+            if ui.checkbox(&mut has_start, "From").changed() {
+                self.date_filter.start = has_start.then_some(self.date_filter.end.unwrap_or(today));
+            }
+            if let Some(start) = &mut self.date_filter.start {
+                ui.add(egui_extras::DatePickerButton::new(start).id_source("filter_from"));
+            }
+
+            let mut has_end = self.date_filter.end.is_some();
+            if ui.checkbox(&mut has_end, "To").changed() {
+                self.date_filter.end = has_end.then_some(self.date_filter.start.unwrap_or(today));
+            }
+            if let Some(end) = &mut self.date_filter.end {
+                ui.add(egui_extras::DatePickerButton::new(end).id_source("filter_to"));
+            }
+
+            if self.date_filter.is_active() && ui.button("✖ Clear Filter").clicked() {
+                self.date_filter = DateRange::default();
+            }
+
+            ui.label(format!(
+                "Showing {} of {} records",
+                self.data_store.get_data_in_range(&self.date_filter).len(),
+                self.data_store.get_record_count()
+            ));
+        });
+    }
+
+    fn show_timeline_page(&mut self, ctx: &egui::Context) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Timeline");
+            ui.add_space(10.0);
+
+            ui.horizontal(|ui| {
+                ui.label("Group by:");
+                for granularity in Granularity::ALL {
+                    ui.selectable_value(&mut self.timeline_granularity, granularity, granularity.label());
+                }
+            });
+
+            ui.add_space(10.0);
+            ui.separator();
+            ui.add_space(10.0);
+
+            let buckets = timeline::build_timeline(self.data_store.get_all_data(), self.timeline_granularity);
+            if buckets.is_empty() {
+                ui.label("No records to show. Add some data first.");
+                return;
+            }
+
+            let max_total = buckets.iter().map(|bucket| bucket.total.abs()).fold(0.0, f64::max);
+
+            egui::ScrollArea::vertical().show(ui, |ui| {
+                egui::Grid::new("timeline_grid")
+                    .num_columns(4)
+                    .spacing([20.0, 4.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("Period");
+                        ui.strong("Records");
+                        ui.strong("Total Value");
+                        ui.strong("");
+                        ui.end_row();
+
+                        for bucket in &buckets {
+                            if ui
+                                .button(bucket.label(self.timeline_granularity, self.settings.locale))
+                                .on_hover_text("Show these records in the Data Table")
+                                .clicked()
+                            {
+                                self.date_filter = bucket.range();
+                                self.current_page = AppPage::DataTable;
+                            }
+                            ui.label(self.settings.locale.format_count(bucket.count));
+                            ui.label(self.settings.locale.format_number(bucket.total, 2));
+
+                            let fraction = if max_total > 0.0 { bucket.total.abs() / max_total } else { 0.0 };
+                            ui.add(egui::ProgressBar::new(fraction as f32).desired_width(200.0));
+                            ui.end_row();
+                        }
+                    });
+            });
+        });
+    }
+
+    fn show_settings_page(&mut self, ctx: &egui::Context) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            egui::ScrollArea::vertical().auto_shrink([false; 2]).show(ui, |ui| {
+                ui.heading("Settings");
+                ui.add_space(20.0);
+
+                let locale = self.settings.locale;
+
+                egui::Grid::new("settings_grid")
+                    .num_columns(2)
+                    .spacing([40.0, 10.0])
+                    .show(ui, |ui| {
+                        ui.label("Auto-update:");
+                        ui.checkbox(&mut true, "Enable automatic updates");
+                        ui.end_row();
+
+                        ui.label("Theme:");
+                        egui::ComboBox::from_label("")
+                            .selected_text("Dark")
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut "Dark", "Dark", "Dark");
+                                ui.selectable_value(&mut "Light", "Light", "Light");
+                            });
+                        ui.end_row();
+
+                        ui.label("Regional format:");
+                        egui::ComboBox::from_id_source("settings_locale")
+                            .selected_text(locale.label())
+                            .show_ui(ui, |ui| {
+                                for option in Locale::ALL {
+                                    ui.selectable_value(&mut self.settings.locale, option, option.label());
+                                }
+                            });
+                        ui.end_row();
+
+                        ui.label("");
+                        ui.label(format!(
+                            "{}   {}",
+                            self.settings.locale.format_number(1234567.891, 2),
+                            self.settings.locale.format_date_time(&chrono::Local::now())
+                        ));
+                        ui.end_row();
+
+                        ui.label("Language:");
+                        egui::ComboBox::from_label("")
+                            .selected_text("English")
+                            .show_ui(ui, |ui| {
+                                ui.selectable_value(&mut "English", "English", "English");
+                                ui.selectable_value(&mut "Spanish", "Spanish", "Spanish");
+                            });
+                        ui.end_row();
+                    });
+
+                if self.settings.locale != locale
+                    && let Err(e) = self.settings.save()
+                {
+                    log::warn!("Failed to save regional format: {}", e);
+                }
+
+                ui.add_space(20.0);
+                ui.separator();
+                ui.add_space(20.0);
+
+                self.show_export_settings(ui);
+
+                ui.add_space(20.0);
+                ui.separator();
+                ui.add_space(20.0);
+
+                self.show_scheduled_exports(ui, ctx);
+
+                ui.add_space(20.0);
+                ui.separator();
+                ui.add_space(20.0);
+
+                self.show_data_source_settings(ui, ctx);
+
+                ui.add_space(20.0);
+                ui.separator();
+                ui.add_space(20.0);
+
+                ui.heading("Update Settings");
+                ui.add_space(10.0);
+
+                let channel = self.settings.update_channel;
+                ui.horizontal(|ui| {
+                    ui.label("Update channel:");
+                    egui::ComboBox::from_id_source("settings_update_channel")
+                        .selected_text(channel.label())
+                        .show_ui(ui, |ui| {
+                            for option in UpdateChannel::ALL {
+                                ui.selectable_value(&mut self.settings.update_channel, option, option.label())
+                                    .on_hover_text(option.description());
+                            }
+                        });
+                    ui.label(self.settings.update_channel.description());
+                });
+                if self.settings.update_channel != channel {
+                    self.save_settings();
+                }
+                if let Some(current) = self.updater.current_version() {
+                    let running = UpdateChannel::of(&current);
+                    if !self.settings.update_channel.includes(running) {
+                        ui.colored_label(
+                            egui::Color32::YELLOW,
+                            format!(
+                                "⚠ This is a {} build. Check for updates to return to the latest {} release.",
+                                running.label().to_lowercase(),
+                                self.settings.update_channel.label().to_lowercase()
+                            ),
+                        );
+                    }
+                }
+                ui.add_space(10.0);
+
+                if ui.button("🔄 Check for Updates Now").clicked() {
+                    self.check_for_updates(ctx);
+                }
+
+                ui.add_space(10.0);
+                ui.label(format!("Update Status: {}", self.update_status));
+            });
+        });
+    }
+
+    fn save_settings(&mut self) {
+        self.update_status = match self.settings.save() {
+            Ok(()) => "Settings saved".to_string(),
+            Err(e) => format!("Failed to save settings: {}", e),
+        };
+    }
+
+    fn show_about_page(&mut self, ctx: &egui::Context) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.vertical_centered(|ui| {
+                ui.heading("About Desktop Application");
+                ui.add_space(20.0);
+
+                ui.label(format!("Version: {} (Updated!)", env!("CARGO_PKG_VERSION")));
+                ui.label("Built with Rust, egui, love and pepsi");
+                ui.add_space(20.0);
+
+                ui.label("Features:");
+                ui.label("• Windows compatible desktop GUI");
+                ui.label("• Excel export functionality");
+                ui.label("• Automatic updates from GitHub");
+                ui.label("• Modern, responsive interface");
+
+                ui.add_space(30.0);
+                ui.hyperlink_to("🌐 Visit GitHub Repository", "https://github.com/mirekbohm/rust-desktop-app");
+            });
+        });
+    }
+
+    fn check_for_updates(&mut self, ctx: &egui::Context) {
+        self.update_state = UpdateState::Checking;
+        
+        let (tx, rx) = mpsc::channel();
+        self.update_receiver = Some(rx);
+        
+        let ctx_clone = ctx.clone();
+        let updater = self.updater.clone();
+        let channel = self.settings.update_channel;
+        
+        thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            rt.block_on(async {
+                match updater.check_for_updates(channel).await {
+                    Ok(Some(version)) => {
+                        let _ = tx.send(UpdateResult::UpdateAvailable(version));
+                    }
+                    Ok(None) => {
+                        let _ = tx.send(UpdateResult::NoUpdate);
+                    }
+                    Err(e) => {
+                        let _ = tx.send(UpdateResult::Error(e.to_string()));
+                    }
+                }
+                ctx_clone.request_repaint();
+            });
+        });
+    }
+
+    /// Whether the offered release is older than the running pre-release, as
+    /// after switching from beta back to stable.
+    fn is_channel_switch(&self) -> bool {
+        let available = updater::parse_version(&self.available_version);
+        available.zip(self.updater.current_version()).is_some_and(|(available, current)| available < current)
+    }
+
+    fn download_update(&mut self, ctx: &egui::Context) {
+        self.update_state = UpdateState::Downloading;
+        
+        let (tx, rx) = mpsc::channel();
+        self.update_receiver = Some(rx);
+        
+        let ctx_clone = ctx.clone();
+        let updater = self.updater.clone();
+        let version = self.available_version.clone();
+        
+        thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            rt.block_on(async {
+                match updater.update_app(&version).await {
+                    Ok(_) => {
+                        let _ = tx.send(UpdateResult::UpdateDownloaded);
+                    }
+                    Err(e) => {
+                        let _ = tx.send(UpdateResult::Error(e.to_string()));
+                    }
+                }
+                ctx_clone.request_repaint();
+            });
+        });
+    }
+
+    fn check_update_result(&mut self) {
+        if let Some(ref receiver) = self.update_receiver
+            && let Ok(result) = receiver.try_recv()
+        {
+            match result {
+                UpdateResult::UpdateAvailable(version) => {
+                    self.available_version = version.clone();
+                    self.update_state = UpdateState::Available(version);
+                    self.show_update_dialog = true;
+                    self.update_status = "Update available!".to_string();
+                }
+                UpdateResult::NoUpdate => {
+                    self.update_state = UpdateState::Idle;
+                    self.update_status = "No updates available.".to_string();
+                }
+                UpdateResult::UpdateDownloaded => {
+                    self.update_state = UpdateState::Downloaded;
+                    self.update_status = "Update downloaded!".to_string();
+                }
+                UpdateResult::Error(error) => {
+                    self.update_state = UpdateState::Error(error.clone());
+                    self.update_status = format!("Update failed: {}", error);
+                }
+            }
+            self.update_receiver = None;
+        }
+    }
+}
+
+impl eframe::App for DesktopApp {
+    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+        // Check for update results
+        self.check_update_result();
+        self.check_sync_result(ctx);
+        self.check_export_results(ctx);
+        self.run_scheduled_exports(ctx);
+        self.save_changed_presets(ctx);
+        
+        self.show_menubar(ctx, frame);
+
+        match self.current_page {
+            AppPage::Home => self.show_home_page(ctx),
+            AppPage::DataTable => self.show_data_table_page(ctx),
+            AppPage::Timeline => self.show_timeline_page(ctx),
+            AppPage::Snapshots => self.show_snapshots_page(ctx),
+            AppPage::Settings => self.show_settings_page(ctx),
+            AppPage::About => self.show_about_page(ctx),
+        }
+
+        self.show_file_dialog(ctx);
+        self.show_export_dialog(ctx);
+        self.show_conflict_dialog(ctx);
+        self.show_export_jobs(ctx);
+
+        // Show update dialog if needed
+        self.show_update_dialog(ctx);
+    }
+
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        if self.presets_changed_at.is_some()
+            && let Err(e) = self.settings.save()
+        {
+            log::warn!("Failed to save presets: {}", e);
+        }
+    }
+}
@@ -0,0 +1,327 @@
+//! Scheduled export presets: their settings, runs and run log.
+
+use chrono::Timelike;
+use eframe::egui;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+use super::{DesktopApp, masking};
+use crate::data::TableData;
+use crate::export::{self, ExportScope};
+use crate::schedule::{ExportPreset, RunOutcome, Schedule, ScheduleLogEntry};
+
+/// How long preset edits settle before they are saved, so typing in a preset
+/// field does not rewrite the settings file on every key.
+const PRESET_SAVE_DELAY: Duration = Duration::from_secs(1);
+
+pub(super) struct ScheduledRun {
+    pub(super) preset_id: u64,
+    pub(super) preset: String,
+    pub(super) due: chrono::DateTime<chrono::Local>,
+    pub(super) started: chrono::DateTime<chrono::Local>,
+}
+
+impl DesktopApp {
+    pub(super) fn show_scheduled_exports(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        ui.heading("Scheduled Exports");
+        ui.add_space(10.0);
+        ui.label("Presets run while the app is open. A run missed while it was closed happens at the next start.");
+        ui.add_space(10.0);
+
+        let today = chrono::Local::now().date_naive();
+        // Scheduled runs use the presets as they are, so edits are saved once they settle.
+        let presets_before = self.settings.export_presets.clone();
+        let mut removed = None;
+        let mut run_now = None;
+        for (index, preset) in self.settings.export_presets.iter_mut().enumerate() {
+            let id = preset.id;
+            egui::CollapsingHeader::new(format!("{} ({})", preset.name, preset.schedule.describe()))
+                .id_source(("export_preset", id))
+                .show(ui, |ui| {
+                    egui::Grid::new(("export_preset_grid", id))
+                        .num_columns(2)
+                        .spacing([40.0, 6.0])
+                        .show(ui, |ui| {
+                            ui.label("Name:");
+                            ui.text_edit_singleline(&mut preset.name);
+                            ui.end_row();
+
+                            ui.label("Enabled:");
+                            ui.checkbox(&mut preset.enabled, "Run on schedule");
+                            ui.end_row();
+
+                            ui.label("Format:");
+                            let format_name = self
+                                .export_registry
+                                .get(&preset.format_id)
+                                .map_or("Unknown", |exporter| exporter.info().name);
+                            egui::ComboBox::from_id_source(("export_preset_format", id))
+                                .selected_text(format_name)
+                                .show_ui(ui, |ui| {
+                                    for exporter in self.export_registry.iter() {
+                                        let info = exporter.info();
+                                        ui.selectable_value(&mut preset.format_id, info.id.to_string(), info.name);
+                                    }
+                                });
+                            ui.end_row();
+
+                            ui.label("Folder:");
+                            let mut directory = preset.profile.directory.to_string_lossy().to_string();
+                            if ui.add(egui::TextEdit::singleline(&mut directory).desired_width(300.0)).changed() {
+                                preset.profile.directory = PathBuf::from(directory);
+                            }
+                            ui.end_row();
+
+                            ui.label("File name:");
+                            ui.add(egui::TextEdit::singleline(&mut preset.profile.filename_template).desired_width(300.0))
+                                .on_hover_text("Placeholders: {date}, {time}, {format}, {count}");
+                            ui.end_row();
+
+                            ui.label("Schedule:");
+                            ui.vertical(|ui| {
+                                ui.horizontal(|ui| {
+                                    let hourly = matches!(preset.schedule, Schedule::EveryHours(_));
+                                    if ui.radio(hourly, "Every").clicked() && !hourly {
+                                        preset.schedule = Schedule::EveryHours(24);
+                                    }
+                                    if let Schedule::EveryHours(hours) = &mut preset.schedule {
+                                        ui.add(egui::DragValue::new(hours).clamp_range(1..=168).suffix(" h"));
+                                    }
+                                });
+                                ui.horizontal(|ui| {
+                                    let daily = matches!(preset.schedule, Schedule::DailyAt(_));
+                                    if ui.radio(daily, "Daily at").clicked() && !daily {
+                                        let time = chrono::NaiveTime::from_hms_opt(6, 0, 0).unwrap_or_default();
+                                        preset.schedule = Schedule::DailyAt(time);
+                                    }
+                                    if let Schedule::DailyAt(time) = &mut preset.schedule {
+                                        let (mut hour, mut minute) = (time.hour(), time.minute());
+                                        let mut changed = ui.add(egui::DragValue::new(&mut hour).clamp_range(0..=23)).changed();
+                                        ui.label(":");
+                                        changed |= ui.add(egui::DragValue::new(&mut minute).clamp_range(0..=59)).changed();
+                                        if changed && let Some(new_time) = chrono::NaiveTime::from_hms_opt(hour, minute, 0) {
+                                            *time = new_time;
+                                        }
+                                    }
+                                });
+                            });
+                            ui.end_row();
+
+                            ui.label("Rows:");
+                            ui.vertical(|ui| {
+                                let filtered = preset.profile.scope == ExportScope::Filtered;
+                                if ui.radio(!filtered, "All rows").clicked() {
+                                    preset.profile.scope = ExportScope::All;
+                                }
+                                ui.horizontal(|ui| {
+                                    if ui.radio(filtered, "Dates").clicked() {
+                                        preset.profile.scope = ExportScope::Filtered;
+                                    }
+                                    let filter = &mut preset.filter;
+                                    let mut has_start = filter.start.is_some();
+                                    if ui.add_enabled(filtered, egui::Checkbox::new(&mut has_start, "From")).changed() {
+                                        filter.start = has_start.then_some(filter.end.unwrap_or(today));
+                                    }
+                                    if let Some(start) = &mut filter.start {
+                                        ui.add_enabled(
+                                            filtered,
+                                            egui_extras::DatePickerButton::new(start).id_source(&format!("preset_from_{}", id)),
+                                        );
+                                    }
+                                    let mut has_end = filter.end.is_some();
+                                    if ui.add_enabled(filtered, egui::Checkbox::new(&mut has_end, "To")).changed() {
+                                        filter.end = has_end.then_some(filter.start.unwrap_or(today));
+                                    }
+                                    if let Some(end) = &mut filter.end {
+                                        ui.add_enabled(
+                                            filtered,
+                                            egui_extras::DatePickerButton::new(end).id_source(&format!("preset_to_{}", id)),
+                                        );
+                                    }
+                                });
+                            });
+                            ui.end_row();
+
+                            ui.label("Columns:");
+                            ui.horizontal(|ui| {
+                                for choice in &mut preset.profile.columns {
+                                    let label = masking::column_label(choice);
+                                    ui.checkbox(&mut choice.enabled, label);
+                                }
+                            });
+                            ui.end_row();
+
+                            let last_run = self.schedule_log.last_run(id);
+                            ui.label("Last run:");
+                            ui.label(last_run.map_or("Never".to_string(), |at| self.settings.locale.format_date_time(&at)));
+                            ui.end_row();
+
+                            ui.label("Next run:");
+                            if preset.enabled {
+                                ui.label(self.settings.locale.format_date_time(&preset.next_run(last_run)));
+                            } else {
+                                ui.label("Disabled");
+                            }
+                            ui.end_row();
+                        });
+
+                    ui.horizontal(|ui| {
+                        if ui.button("▶ Run Now").clicked() {
+                            run_now = Some(index);
+                        }
+                        if ui.button("🗑 Remove").clicked() {
+                            removed = Some(index);
+                        }
+                    });
+                });
+        }
+
+        if let Some(index) = run_now {
+            let preset = self.settings.export_presets[index].clone();
+            self.run_preset(ctx, &preset, chrono::Local::now());
+        }
+        if let Some(index) = removed {
+            self.settings.export_presets.remove(index);
+        }
+
+        ui.add_space(10.0);
+        ui.horizontal(|ui| {
+            if ui.button("➕ Add Preset").on_hover_text("Starts from the last export dialog choices").clicked() {
+                let mut profile = self.settings.export_profile.clone();
+                if profile.scope == ExportScope::Selection {
+                    profile.scope = ExportScope::All;
+                }
+                self.settings.export_presets.push(ExportPreset {
+                    name: format!("Scheduled export {}", self.settings.export_presets.len() + 1),
+                    format_id: self.settings_export_format.clone(),
+                    profile,
+                    ..ExportPreset::default()
+                });
+            }
+        });
+        if self.settings.export_presets != presets_before {
+            self.presets_changed_at = Some(Instant::now());
+        }
+        if let Some(error) = &self.preset_save_error {
+            ui.colored_label(egui::Color32::RED, error);
+        }
+
+        ui.add_space(10.0);
+        egui::CollapsingHeader::new(format!("Run log ({})", self.schedule_log.entries().len()))
+            .id_source("export_schedule_log")
+            .show(ui, |ui| {
+                egui::ScrollArea::vertical().id_source("export_schedule_log_scroll").max_height(200.0).show(ui, |ui| {
+                    for entry in self.schedule_log.entries().iter().rev() {
+                        let at = self.settings.locale.format_date_time(&entry.at);
+                        let missed = match entry.caught_up() {
+                            true => format!("  (missed run due {})", self.settings.locale.format_date_time(&entry.due)),
+                            false => String::new(),
+                        };
+                        match &entry.outcome {
+                            RunOutcome::Exported { path, records } => ui.label(format!(
+                                "✅ {}  {}  {} records to {}{}",
+                                at,
+                                entry.preset,
+                                records,
+                                path.display(),
+                                missed
+                            )),
+                            RunOutcome::Failed(error) => ui.colored_label(
+                                egui::Color32::RED,
+                                format!("❌ {}  {}  {}{}", at, entry.preset, error, missed),
+                            ),
+                        };
+                    }
+                });
+            });
+    }
+
+    /// Saves edited presets once they have not changed for `PRESET_SAVE_DELAY`.
+    pub(super) fn save_changed_presets(&mut self, ctx: &egui::Context) {
+        let Some(changed_at) = self.presets_changed_at else { return };
+        let elapsed = changed_at.elapsed();
+        if elapsed < PRESET_SAVE_DELAY {
+            ctx.request_repaint_after(PRESET_SAVE_DELAY - elapsed);
+            return;
+        }
+        self.presets_changed_at = None;
+        self.preset_save_error = self.settings.save().err().map(|e| format!("Failed to save presets: {}", e));
+    }
+
+    /// Starts presets that are due, including runs missed while the app was closed.
+    pub(super) fn run_scheduled_exports(&mut self, ctx: &egui::Context) {
+        let now = chrono::Local::now();
+        let mut next_check: Option<Duration> = None;
+
+        for preset in self.settings.export_presets.clone() {
+            let running = self
+                .export_jobs
+                .iter()
+                .any(|job| job.scheduled.as_ref().is_some_and(|run| run.preset_id == preset.id));
+            if !preset.enabled || running {
+                continue;
+            }
+            let due = preset.next_run(self.schedule_log.last_run(preset.id));
+            if due <= now {
+                self.run_preset(ctx, &preset, due);
+            } else {
+                let wait = (due - now).to_std().unwrap_or_default();
+                next_check = Some(next_check.map_or(wait, |next| next.min(wait)));
+            }
+        }
+
+        if let Some(wait) = next_check {
+            ctx.request_repaint_after(wait);
+        }
+    }
+
+    fn run_preset(&mut self, ctx: &egui::Context, preset: &ExportPreset, due: chrono::DateTime<chrono::Local>) {
+        let started = chrono::Local::now();
+        self.schedule_log.started(preset.id, started);
+
+        let (data, filter): (Vec<TableData>, String) = match preset.profile.scope {
+            ExportScope::Filtered => (
+                self.data_store.get_data_in_range(&preset.filter).into_iter().cloned().collect(),
+                format!("Dates {}", preset.filter.describe(self.settings.locale)),
+            ),
+            _ => (self.data_store.get_all_data().clone(), "All rows".to_string()),
+        };
+        let has_columns = preset.profile.columns.iter().any(|choice| choice.enabled);
+        let failure = match self.export_registry.shared(&preset.format_id) {
+            None => Some(format!("Unknown export format: {}", preset.format_id)),
+            Some(_) if data.is_empty() => Some("No records to export".to_string()),
+            Some(_) if !has_columns => Some("No columns selected".to_string()),
+            Some(exporter) => {
+                let path = preset.profile.file_path(&exporter.info(), data.len());
+                let appends = self
+                    .settings
+                    .export_options
+                    .get(&preset.format_id)
+                    .is_some_and(|options| exporter.appends(options));
+                let path = if path.exists() && !appends { export::next_free_path(&path) } else { path };
+                let job = self.start_export(ctx, exporter, data, &preset.profile, filter, path);
+                job.scheduled = Some(ScheduledRun {
+                    preset_id: preset.id,
+                    preset: preset.name.clone(),
+                    due,
+                    started,
+                });
+                None
+            }
+        };
+
+        if let Some(error) = failure {
+            self.update_status = format!("Scheduled export \"{}\" failed: {}", preset.name, error);
+            let entry = ScheduleLogEntry {
+                at: started,
+                preset_id: preset.id,
+                preset: preset.name.clone(),
+                due,
+                outcome: RunOutcome::Failed(error),
+            };
+            if let Err(e) = self.schedule_log.record(entry) {
+                log::warn!("Failed to write export schedule log: {}", e);
+            }
+        }
+    }
+}
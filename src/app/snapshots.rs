@@ -0,0 +1,234 @@
+//! Taking, restoring and deleting snapshots, and comparing snapshots, the
+//! current data and data files record by record.
+
+use eframe::egui;
+
+use super::DesktopApp;
+use crate::data::TableData;
+use crate::locale::Locale;
+use crate::snapshot::{self, RecordDiff, Snapshot};
+use crate::storage;
+
+#[derive(Clone, Copy, PartialEq)]
+pub(super) enum CompareSource {
+    Snapshot(usize),
+    CurrentData,
+    File,
+}
+
+fn compare_source_label(source: CompareSource, snapshots: &[Snapshot]) -> String {
+    match source {
+        CompareSource::Snapshot(index) => match snapshots.get(index) {
+            Some(snapshot) => format!("📸 {}", snapshot.name),
+            None => "(no snapshot)".to_string(),
+        },
+        CompareSource::CurrentData => "Current data".to_string(),
+        CompareSource::File => "File...".to_string(),
+    }
+}
+
+impl DesktopApp {
+    pub(super) fn show_snapshots_page(&mut self, ctx: &egui::Context) {
+        egui::CentralPanel::default().show(ctx, |ui| {
+            ui.heading("Snapshots");
+            ui.add_space(10.0);
+
+            ui.horizontal(|ui| {
+                ui.label("Name:");
+                ui.text_edit_singleline(&mut self.snapshot_name);
+                if ui.button("📸 Take Snapshot").clicked() {
+                    match self.snapshot_manager.take(&self.snapshot_name, self.data_store.get_all_data()) {
+                        Ok(()) => {
+                            self.update_status = format!("Snapshot \"{}\" saved", self.snapshot_name.trim());
+                            self.snapshot_name.clear();
+                        }
+                        Err(e) => self.update_status = format!("Snapshot failed: {}", e),
+                    }
+                }
+            });
+
+            ui.add_space(10.0);
+
+            let mut restore = None;
+            let mut delete = None;
+            egui::ScrollArea::vertical().id_source("snapshot_list").max_height(200.0).show(ui, |ui| {
+                egui::Grid::new("snapshot_grid")
+                    .num_columns(4)
+                    .spacing([20.0, 4.0])
+                    .striped(true)
+                    .show(ui, |ui| {
+                        ui.strong("Name");
+                        ui.strong("Taken");
+                        ui.strong("Records");
+                        ui.strong("");
+                        ui.end_row();
+
+                        for (index, snapshot) in self.snapshot_manager.snapshots().iter().enumerate() {
+                            ui.label(&snapshot.name);
+                            ui.label(self.settings.locale.format_date_time(&snapshot.taken_at));
+                            ui.label(snapshot.records.len().to_string());
+                            ui.horizontal(|ui| {
+                                if ui.button("↩ Restore").clicked() {
+                                    restore = Some(index);
+                                }
+                                if ui.button("🗑️ Delete").clicked() {
+                                    delete = Some(index);
+                                }
+                            });
+                            ui.end_row();
+                        }
+                    });
+            });
+
+            let locked = self.snapshot_manager.locked();
+            if locked > 0 {
+                ui.label(format!("🔒 {} encrypted snapshots are hidden. Open their data file to see them.", locked));
+            }
+
+            if let Some(index) = restore {
+                let snapshot = &self.snapshot_manager.snapshots()[index];
+                self.data_store.replace_data(snapshot.records.clone());
+                self.update_status = format!("Restored snapshot \"{}\"", snapshot.name);
+            }
+            if let Some(index) = delete {
+                if let Err(e) = self.snapshot_manager.delete(index) {
+                    self.update_status = format!("Failed to delete snapshot: {}", e);
+                }
+                self.snapshot_diff = None;
+            }
+
+            ui.add_space(10.0);
+            ui.separator();
+            ui.add_space(10.0);
+
+            ui.heading("Compare");
+            ui.add_space(10.0);
+
+            ui.horizontal(|ui| {
+                ui.label("Base:");
+                let snapshots = self.snapshot_manager.snapshots();
+                Self::compare_source_combo(ui, "compare_base", &mut self.compare_base, snapshots);
+                ui.label("Compare with:");
+                Self::compare_source_combo(ui, "compare_target", &mut self.compare_target, snapshots);
+            });
+
+            if self.compare_base == CompareSource::File || self.compare_target == CompareSource::File {
+                ui.horizontal(|ui| {
+                    ui.label("File:");
+                    ui.text_edit_singleline(&mut self.compare_file_path);
+                    ui.label("Password:");
+                    ui.add(egui::TextEdit::singleline(&mut self.compare_file_password).password(true));
+                });
+            }
+
+            if ui.button("🔍 Compare").clicked() {
+                let result = self
+                    .compare_records(self.compare_base)
+                    .and_then(|base| Ok(snapshot::diff_records(&base, &self.compare_records(self.compare_target)?, self.settings.locale)));
+                match result {
+                    Ok(diff) => self.snapshot_diff = Some(diff),
+                    Err(e) => {
+                        self.snapshot_diff = None;
+                        self.update_status = format!("Compare failed: {}", e);
+                    }
+                }
+            }
+
+            if let Some(diff) = &self.snapshot_diff {
+                ui.add_space(10.0);
+                Self::show_record_diff(ui, diff, self.settings.locale);
+            }
+        });
+    }
+
+    fn compare_source_combo(ui: &mut egui::Ui, id: &str, source: &mut CompareSource, snapshots: &[Snapshot]) {
+        egui::ComboBox::from_id_source(id)
+            .selected_text(compare_source_label(*source, snapshots))
+            .show_ui(ui, |ui| {
+                for index in 0..snapshots.len() {
+                    let option = CompareSource::Snapshot(index);
+                    ui.selectable_value(source, option, compare_source_label(option, snapshots));
+                }
+                ui.selectable_value(source, CompareSource::CurrentData, "Current data");
+                ui.selectable_value(source, CompareSource::File, "File...");
+            });
+    }
+
+    fn compare_records(&self, source: CompareSource) -> anyhow::Result<Vec<TableData>> {
+        match source {
+            CompareSource::Snapshot(index) => self
+                .snapshot_manager
+                .snapshots()
+                .get(index)
+                .map(|snapshot| snapshot.records.clone())
+                .ok_or_else(|| anyhow::anyhow!("Select a snapshot to compare")),
+            CompareSource::CurrentData => Ok(self.data_store.get_all_data().clone()),
+            CompareSource::File => {
+                let password = Some(self.compare_file_password.as_str()).filter(|password| !password.is_empty());
+                storage::load_records(std::path::Path::new(self.compare_file_path.trim()), password)
+            }
+        }
+    }
+
+    fn show_record_diff(ui: &mut egui::Ui, diff: &RecordDiff, locale: Locale) {
+        if diff.is_empty() {
+            ui.label("No differences found.");
+            return;
+        }
+
+        ui.label(format!(
+            "{} added, {} removed, {} changed",
+            diff.added.len(),
+            diff.removed.len(),
+            diff.changed.len()
+        ));
+
+        egui::ScrollArea::vertical().id_source("snapshot_diff").show(ui, |ui| {
+            for (title, records, color) in [
+                ("Added", &diff.added, egui::Color32::GREEN),
+                ("Removed", &diff.removed, egui::Color32::RED),
+            ] {
+                if records.is_empty() {
+                    continue;
+                }
+                egui::CollapsingHeader::new(format!("{} ({})", title, records.len()))
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        egui::Grid::new(format!("diff_{}", title)).num_columns(4).striped(true).show(ui, |ui| {
+                            for item in records {
+                                ui.colored_label(color, item.id.to_string());
+                                ui.colored_label(color, &item.name);
+                                ui.colored_label(color, locale.format_number(item.value, 2));
+                                ui.colored_label(color, locale.format_date_time(&item.date));
+                                ui.end_row();
+                            }
+                        });
+                    });
+            }
+
+            if !diff.changed.is_empty() {
+                egui::CollapsingHeader::new(format!("Changed ({})", diff.changed.len()))
+                    .default_open(true)
+                    .show(ui, |ui| {
+                        egui::Grid::new("diff_changed").num_columns(4).striped(true).show(ui, |ui| {
+                            ui.strong("ID");
+                            ui.strong("Field");
+                            ui.strong("Before");
+                            ui.strong("After");
+                            ui.end_row();
+
+                            for record in &diff.changed {
+                                for change in &record.changes {
+                                    ui.label(record.id.to_string());
+                                    ui.label(change.field);
+                                    ui.colored_label(egui::Color32::RED, &change.old);
+                                    ui.colored_label(egui::Color32::GREEN, &change.new);
+                                    ui.end_row();
+                                }
+                            }
+                        });
+                    });
+            }
+        });
+    }
+}
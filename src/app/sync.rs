@@ -0,0 +1,360 @@
+//! Syncing the records with the REST data source: settings, scheduled pulls,
+//! pushes of queued edits and conflict resolution.
+
+use eframe::egui;
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use super::DesktopApp;
+use crate::data::TableData;
+use crate::remote::{AuthMethod, RestClient};
+use crate::sync::Resolution;
+
+/// How often to retry syncing while queued edits are waiting for the connection.
+const OFFLINE_RETRY_INTERVAL: Duration = Duration::from_secs(30);
+
+const SECRET_HINT: &str = "Not saved to disk; enter it again after restarting the app";
+
+pub(super) enum SyncResult {
+    Pulled(Vec<TableData>),
+    Pushed(Vec<TableData>),
+    Error(String),
+}
+
+impl DesktopApp {
+    pub(super) fn show_data_source_settings(&mut self, ui: &mut egui::Ui, ctx: &egui::Context) {
+        ui.heading("REST Data Source");
+        ui.add_space(10.0);
+
+        let rest = &mut self.settings.rest;
+        egui::Grid::new("rest_settings_grid")
+            .num_columns(2)
+            .spacing([40.0, 6.0])
+            .show(ui, |ui| {
+                ui.label("Enabled:");
+                ui.checkbox(&mut rest.enabled, "Sync data with a REST endpoint");
+                ui.end_row();
+
+                ui.label("Base URL:");
+                ui.text_edit_singleline(&mut rest.base_url);
+                ui.end_row();
+
+                ui.label("Records path:");
+                ui.text_edit_singleline(&mut rest.records_path);
+                ui.end_row();
+
+                ui.label("Authentication:");
+                egui::ComboBox::from_id_source("rest_auth")
+                    .selected_text(rest.auth.label())
+                    .show_ui(ui, |ui| {
+                        for option in [
+                            AuthMethod::None,
+                            AuthMethod::Bearer { token: String::new() },
+                            AuthMethod::Basic { username: String::new(), password: String::new() },
+                            AuthMethod::Header { name: String::new(), value: String::new() },
+                        ] {
+                            let selected = std::mem::discriminant(&rest.auth) == std::mem::discriminant(&option);
+                            if ui.selectable_label(selected, option.label()).clicked() && !selected {
+                                rest.auth = option;
+                            }
+                        }
+                    });
+                ui.end_row();
+
+                match &mut rest.auth {
+                    AuthMethod::None => {}
+                    AuthMethod::Bearer { token } => {
+                        ui.label("Token:");
+                        ui.add(egui::TextEdit::singleline(token).password(true))
+                            .on_hover_text(SECRET_HINT);
+                        ui.end_row();
+                    }
+                    AuthMethod::Basic { username, password } => {
+                        ui.label("Username:");
+                        ui.text_edit_singleline(username);
+                        ui.end_row();
+                        ui.label("Password:");
+                        ui.add(egui::TextEdit::singleline(password).password(true))
+                            .on_hover_text(SECRET_HINT);
+                        ui.end_row();
+                    }
+                    AuthMethod::Header { name, value } => {
+                        ui.label("Header name:");
+                        ui.text_edit_singleline(name);
+                        ui.end_row();
+                        ui.label("Header value:");
+                        ui.add(egui::TextEdit::singleline(value).password(true))
+                            .on_hover_text(SECRET_HINT);
+                        ui.end_row();
+                    }
+                }
+
+                ui.label("Page size:");
+                ui.add(egui::DragValue::new(&mut rest.page_size).clamp_range(1..=10_000));
+                ui.end_row();
+
+                ui.label("Retries:");
+                ui.add(egui::DragValue::new(&mut rest.max_retries).clamp_range(0..=10));
+                ui.end_row();
+
+                ui.label("Initial backoff:");
+                ui.add(egui::DragValue::new(&mut rest.initial_backoff_ms).clamp_range(0..=60_000).suffix(" ms"));
+                ui.end_row();
+
+                ui.label("Pull every:");
+                ui.add(egui::DragValue::new(&mut rest.pull_interval_minutes).clamp_range(0..=1440).suffix(" min"))
+                    .on_hover_text("0 disables scheduled pulls");
+                ui.end_row();
+            });
+
+        ui.add_space(10.0);
+        ui.horizontal(|ui| {
+            if ui.button("💾 Save Settings").clicked() {
+                self.save_settings();
+            }
+            if ui
+                .add_enabled(self.settings.rest.enabled && self.sync_receiver.is_none(), egui::Button::new("🔄 Sync Now"))
+                .clicked()
+            {
+                self.start_sync(ctx);
+            }
+        });
+
+        ui.add_space(10.0);
+        egui::CollapsingHeader::new(format!("Conflict log ({})", self.sync_log.entries().len()))
+            .id_source("sync_log")
+            .show(ui, |ui| {
+                egui::ScrollArea::vertical().id_source("sync_log_scroll").max_height(200.0).show(ui, |ui| {
+                    for entry in self.sync_log.entries().iter().rev() {
+                        let fields: Vec<String> = entry
+                            .fields
+                            .iter()
+                            .map(|field| format!("{}: {} / {} → {}", field.field.label(), field.mine, field.theirs, field.chosen))
+                            .collect();
+                        ui.label(format!(
+                            "{}  #{}  {}  ({})",
+                            self.settings.locale.format_date_time(&entry.at),
+                            entry.record_id,
+                            entry.resolution.label(),
+                            fields.join("; ")
+                        ));
+                    }
+                });
+            });
+    }
+
+    pub(super) fn show_conflict_dialog(&mut self, ctx: &egui::Context) {
+        if self.sync_conflicts.is_empty() {
+            return;
+        }
+
+        let mut decision = None;
+        egui::Window::new("Resolve Sync Conflicts")
+            .collapsible(false)
+            .resizable(true)
+            .anchor(egui::Align2::CENTER_CENTER, [0.0, 0.0])
+            .show(ctx, |ui| {
+                ui.set_min_width(500.0);
+                ui.label("These records were changed both here and on the server.");
+                ui.add_space(10.0);
+
+                egui::ScrollArea::vertical().max_height(400.0).show(ui, |ui| {
+                    for (index, conflict) in self.sync_conflicts.iter_mut().enumerate() {
+                        ui.strong(format!("Record #{}", conflict.id()));
+                        egui::Grid::new(format!("conflict_{}", index))
+                            .num_columns(4)
+                            .spacing([20.0, 4.0])
+                            .striped(true)
+                            .show(ui, |ui| {
+                                ui.strong("Field");
+                                ui.strong("Mine");
+                                ui.strong("Theirs");
+                                ui.strong("Merge uses");
+                                ui.end_row();
+
+                                for (field, use_mine) in &mut conflict.fields {
+                                    ui.label(field.label());
+                                    ui.label(field.display(&conflict.local, self.settings.locale));
+                                    ui.label(field.display(&conflict.remote, self.settings.locale));
+                                    ui.horizontal(|ui| {
+                                        ui.radio_value(use_mine, true, "Mine");
+                                        ui.radio_value(use_mine, false, "Theirs");
+                                    });
+                                    ui.end_row();
+                                }
+                            });
+
+                        ui.horizontal(|ui| {
+                            for resolution in [Resolution::KeepMine, Resolution::KeepTheirs, Resolution::Merge] {
+                                if ui.button(resolution.label()).clicked() {
+                                    decision = Some((index, resolution));
+                                }
+                            }
+                        });
+                        ui.separator();
+                    }
+                });
+            });
+
+        if let Some((index, resolution)) = decision {
+            let conflict = self.sync_conflicts.remove(index);
+            let resolved = conflict.resolve(resolution);
+
+            if let Err(e) = self.sync_log.record(&conflict, resolution, &resolved, self.settings.locale) {
+                log::warn!("Failed to write sync log: {}", e);
+            }
+            self.sync_state.resolve(&conflict, &resolved);
+            let pending = resolution != Resolution::KeepTheirs;
+            self.data_store.put_record(resolved, pending);
+            self.synced_revision = self.data_store.revision();
+            self.save_sync_state();
+
+            if self.sync_conflicts.is_empty() && self.sync_state.queue_len() > 0 && self.sync_receiver.is_none() {
+                self.start_push(ctx);
+            }
+        }
+    }
+
+    fn save_sync_state(&mut self) {
+        if let Err(e) = self.sync_state.save() {
+            log::warn!("Failed to save sync state: {}", e);
+        }
+    }
+
+    /// Pulls remote records; the result is reconciled with queued edits in `check_sync_result`.
+    pub(super) fn start_sync(&mut self, ctx: &egui::Context) {
+        let (tx, rx) = mpsc::channel();
+        self.sync_receiver = Some(rx);
+        self.last_sync = Some(Instant::now());
+        self.update_status = "Syncing...".to_string();
+
+        let ctx_clone = ctx.clone();
+        let config = self.settings.rest.clone();
+
+        thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            rt.block_on(async {
+                let result = match RestClient::new(config) {
+                    Ok(client) => client.pull().await,
+                    Err(e) => Err(e),
+                };
+                let _ = match result {
+                    Ok(records) => tx.send(SyncResult::Pulled(records)),
+                    Err(e) => tx.send(SyncResult::Error(format!("{:#}", e))),
+                };
+                ctx_clone.request_repaint();
+            });
+        });
+    }
+
+    fn start_push(&mut self, ctx: &egui::Context) {
+        let (tx, rx) = mpsc::channel();
+        self.sync_receiver = Some(rx);
+        self.update_status = "Pushing edits...".to_string();
+
+        let ctx_clone = ctx.clone();
+        let config = self.settings.rest.clone();
+        let records = self.sync_state.queued();
+
+        thread::spawn(move || {
+            let rt = tokio::runtime::Runtime::new().unwrap();
+            rt.block_on(async {
+                let result = match RestClient::new(config) {
+                    Ok(client) => client.push(&records).await,
+                    Err(e) => Err(e),
+                };
+                let _ = match result {
+                    Ok(_) => tx.send(SyncResult::Pushed(records)),
+                    Err(e) => tx.send(SyncResult::Error(format!("{:#}", e))),
+                };
+                ctx_clone.request_repaint();
+            });
+        });
+    }
+
+    pub(super) fn check_sync_result(&mut self, ctx: &egui::Context) {
+        if !self.settings.rest.enabled {
+            return;
+        }
+
+        // Queue local edits so they survive going offline or restarting the app
+        if self.data_store.revision() != self.synced_revision {
+            self.synced_revision = self.data_store.revision();
+            let modified = self.data_store.get_modified_records();
+            if !modified.is_empty() {
+                self.sync_state.enqueue(modified);
+                self.save_sync_state();
+            }
+        }
+
+        if let Some(ref receiver) = self.sync_receiver
+            && let Ok(result) = receiver.try_recv()
+        {
+            self.sync_receiver = None;
+            match result {
+                SyncResult::Pulled(records) => {
+                    self.offline = false;
+                    let reconciliation = self.sync_state.reconcile(records);
+                    let pending = reconciliation
+                        .to_push
+                        .iter()
+                        .chain(reconciliation.conflicts.iter().map(|conflict| &conflict.local))
+                        .map(|item| item.id)
+                        .collect::<Vec<_>>();
+                    self.data_store.replace_synced(reconciliation.records, pending);
+                    self.synced_revision = self.data_store.revision();
+                    self.sync_conflicts = reconciliation.conflicts;
+                    self.save_sync_state();
+
+                    self.update_status = match self.sync_conflicts.len() {
+                        0 => format!("Synced {} records", self.data_store.get_record_count()),
+                        n => format!("Synced with {} conflicts to resolve", n),
+                    };
+                    if !reconciliation.deleted.is_empty() {
+                        self.update_status.push_str(&format!(
+                            "; {} edited records were deleted on the server",
+                            reconciliation.deleted.len()
+                        ));
+                    }
+                    if !reconciliation.to_push.is_empty() {
+                        self.start_push(ctx);
+                    }
+                }
+                SyncResult::Pushed(records) => {
+                    self.offline = false;
+                    self.update_status = format!("Pushed {} records", records.len());
+                    let synced = self.sync_state.mark_pushed(&records);
+                    self.data_store.mark_synced(&synced);
+                    self.save_sync_state();
+                }
+                SyncResult::Error(error) => {
+                    self.offline = true;
+                    self.update_status = format!(
+                        "Offline, {} edits queued: {}",
+                        self.sync_state.queue_len(),
+                        error
+                    );
+                }
+            }
+        }
+
+        // Scheduled syncs, and retries while edits wait for the connection to return
+        let rest = &self.settings.rest;
+        let interval = if self.offline && self.sync_state.queue_len() > 0 {
+            Some(OFFLINE_RETRY_INTERVAL)
+        } else if rest.pull_interval_minutes > 0 {
+            Some(Duration::from_secs(rest.pull_interval_minutes as u64 * 60))
+        } else {
+            None
+        };
+        if let Some(interval) = interval {
+            let due = self.last_sync.is_none_or(|last| last.elapsed() >= interval);
+            if due && self.sync_receiver.is_none() && self.sync_conflicts.is_empty() {
+                self.start_sync(ctx);
+            }
+            let next = self.last_sync.map_or(interval, |last| interval.saturating_sub(last.elapsed()));
+            ctx.request_repaint_after(next);
+        }
+    }
+}
@@ -0,0 +1,170 @@
+use crate::export::{self, ExportContext, ExportProgress, ExportRegistry};
+use crate::settings::AppSettings;
+use crate::storage;
+use anyhow::{Context, Result, anyhow, bail};
+use std::io::{BufRead, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+const USAGE: &str = "\
+Usage:
+  desktop-app export --list-formats
+  desktop-app export --format <id> --input <data file> [--output <path>]
+                     [--no-mask] [--option <key>=<value>]...
+
+Options default to the values saved on the Settings page, and the columns,
+headers and masking to those last used in the export dialog. --no-mask
+exports the columns unmasked.
+
+Encrypted data files are opened with the password in the
+DESKTOP_APP_PASSWORD environment variable, or one read from standard input.";
+
+/// Environment variable holding the password of an encrypted data file.
+const PASSWORD_VARIABLE: &str = "DESKTOP_APP_PASSWORD";
+
+/// Runs a headless command if the arguments ask for one. Returns the process
+/// exit code, or `None` to start the GUI.
+pub fn run(args: &[String]) -> Option<i32> {
+    let (command, rest) = args.split_first()?;
+    let result = match command.as_str() {
+        "export" => export_command(rest),
+        "--help" | "-h" | "help" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => return None,
+    };
+
+    match result {
+        Ok(()) => Some(0),
+        Err(e) => {
+            eprintln!("Error: {:#}\n\n{}", e, USAGE);
+            Some(1)
+        }
+    }
+}
+
+fn export_command(args: &[String]) -> Result<()> {
+    let registry = ExportRegistry::with_builtin();
+    let settings = AppSettings::load();
+
+    let mut format = None;
+    let mut input = None;
+    let mut output = None;
+    let mut mask = true;
+    let mut overrides = Vec::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or_else(|| anyhow!("{} needs a value", arg));
+        match arg.as_str() {
+            "--list-formats" => {
+                list_formats(&registry);
+                return Ok(());
+            }
+            "--format" => format = Some(value()?.clone()),
+            "--input" => input = Some(PathBuf::from(value()?)),
+            "--output" => output = Some(PathBuf::from(value()?)),
+            "--no-mask" => mask = false,
+            "--option" => {
+                let option = value()?;
+                let (key, value) = option
+                    .split_once('=')
+                    .ok_or_else(|| anyhow!("--option expects key=value, got \"{}\"", option))?;
+                overrides.push((key.to_string(), value.to_string()));
+            }
+            other => bail!("Unknown argument \"{}\"", other),
+        }
+    }
+
+    let format = format.ok_or_else(|| anyhow!("--format is required"))?;
+    let input = input.ok_or_else(|| anyhow!("--input is required"))?;
+    let exporter = registry
+        .get(&format)
+        .ok_or_else(|| anyhow!("Unknown format \"{}\"; run with --list-formats", format))?;
+    let info = exporter.info();
+
+    let mut options = settings.export_options.get(info.id).cloned().unwrap_or_default();
+    for (key, value) in overrides {
+        if !info.options.iter().any(|spec| spec.key == key) {
+            bail!("{} has no option \"{}\"", info.name, key);
+        }
+        options.set(&key, value);
+    }
+
+    let profile = &settings.export_profile;
+    let columns = profile.columns();
+    if columns.is_empty() {
+        bail!("No columns are selected in the export dialog");
+    }
+    let mut filter = "All rows".to_string();
+    let masking = profile.masking();
+    if mask && let Some(masked) = masking.describe() {
+        filter = format!("{}; masked: {}", filter, masked);
+    }
+
+    let password = read_password(&input)?;
+    let data = storage::load_records(&input, password.as_deref())?;
+    let data = if mask { masking.apply(data) } else { data };
+    let output = output.unwrap_or_else(|| export::default_export_path(info.extension));
+    let progress = ExportProgress::new(data.len());
+    let context = ExportContext {
+        data: &data,
+        columns: &columns,
+        options: &options,
+        filter: &filter,
+        locale: settings.locale,
+        masked: mask && masking.describe().is_some(),
+        progress: &progress,
+    };
+    let result = thread::scope(|scope| {
+        let job = scope.spawn(|| exporter.export(&context, &output));
+        while !job.is_finished() {
+            if std::io::stderr().is_terminal() {
+                eprint!("\rExporting... {:>3.0}%", progress.fraction() * 100.0);
+            }
+            thread::sleep(Duration::from_millis(200));
+        }
+        job.join().unwrap_or_else(|_| Err(anyhow!("export thread panicked")))
+    });
+    if std::io::stderr().is_terminal() {
+        eprint!("\r");
+    }
+    result.with_context(|| format!("{} export failed", info.name))?;
+
+    println!("Exported {} records to {}", data.len(), output.display());
+    Ok(())
+}
+
+/// Password for `input` when it is encrypted, from the environment or standard input.
+fn read_password(input: &Path) -> Result<Option<String>> {
+    let bytes = std::fs::read(input).with_context(|| format!("Failed to read {}", input.display()))?;
+    if !storage::is_encrypted(&bytes) {
+        return Ok(None);
+    }
+    if let Ok(password) = std::env::var(PASSWORD_VARIABLE) {
+        return Ok(Some(password));
+    }
+
+    let stdin = std::io::stdin();
+    if stdin.is_terminal() {
+        eprint!("Password for {}: ", input.display());
+        std::io::stderr().flush()?;
+    }
+    let mut password = String::new();
+    if stdin.lock().read_line(&mut password)? == 0 {
+        bail!("{} is encrypted; set {} or pass the password on standard input", input.display(), PASSWORD_VARIABLE);
+    }
+    Ok(Some(password.trim_end_matches(['\r', '\n']).to_string()))
+}
+
+fn list_formats(registry: &ExportRegistry) {
+    for exporter in registry.iter() {
+        let info = exporter.info();
+        println!("{:<8} {} (.{})", info.id, info.name, info.extension);
+        for spec in &info.options {
+            println!("    {:<14} default \"{}\"  {}", spec.key, spec.default.escape_default(), spec.help);
+        }
+    }
+}
@@ -1,5 +1,7 @@
-use chrono::{DateTime, Local};
+use crate::locale::Locale;
+use chrono::{DateTime, Local, NaiveDate};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TableData {
@@ -9,9 +11,65 @@ pub struct TableData {
     pub date: DateTime<Local>,
 }
 
+/// A column of `TableData`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Field {
+    Id,
+    Name,
+    Value,
+    Date,
+}
+
+impl Field {
+    pub const ALL: [Field; 4] = [Field::Id, Field::Name, Field::Value, Field::Date];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Field::Id => "ID",
+            Field::Name => "Name",
+            Field::Value => "Value",
+            Field::Date => "Date",
+        }
+    }
+}
+
+/// Inclusive date range used to filter records. An unset bound is open-ended.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct DateRange {
+    pub start: Option<NaiveDate>,
+    pub end: Option<NaiveDate>,
+}
+
+impl DateRange {
+    pub fn new(start: Option<NaiveDate>, end: Option<NaiveDate>) -> Self {
+        Self { start, end }
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.start.is_some() || self.end.is_some()
+    }
+
+    pub fn contains(&self, date: &DateTime<Local>) -> bool {
+        let day = date.date_naive();
+        self.start.is_none_or(|start| day >= start) && self.end.is_none_or(|end| day <= end)
+    }
+
+    pub fn describe(&self, locale: Locale) -> String {
+        match (self.start, self.end) {
+            (Some(start), Some(end)) => format!("{} to {}", locale.format_day(start), locale.format_day(end)),
+            (Some(start), None) => format!("from {}", locale.format_day(start)),
+            (None, Some(end)) => format!("until {}", locale.format_day(end)),
+            (None, None) => "all dates".to_string(),
+        }
+    }
+}
+
 pub struct DataStore {
     data: Vec<TableData>,
     next_id: u32,
+    /// IDs of records added or edited locally since the last push.
+    modified: BTreeSet<u32>,
+    revision: u64,
 }
 
 impl DataStore {
@@ -19,6 +77,8 @@ impl DataStore {
         Self {
             data: Vec::new(),
             next_id: 1,
+            modified: BTreeSet::new(),
+            revision: 0,
         }
     }
 
@@ -35,13 +95,81 @@ impl DataStore {
                 value: fastrand::f64() * 1000.0,
                 date: Local::now(),
             });
+            self.modified.insert(self.next_id);
             self.next_id += 1;
         }
+        self.revision += 1;
     }
 
     pub fn clear_data(&mut self) {
         self.data.clear();
         self.next_id = 1;
+        self.modified.clear();
+        self.revision += 1;
+    }
+
+    /// Replaces all records, e.g. when restoring a snapshot or opening a file.
+    pub fn replace_data(&mut self, data: Vec<TableData>) {
+        self.next_id = data.iter().map(|item| item.id).max().unwrap_or(0) + 1;
+        self.data = data;
+        self.modified.clear();
+        self.revision += 1;
+    }
+
+    pub fn update_record(&mut self, id: u32, name: String, value: f64) {
+        if let Some(item) = self.data.iter_mut().find(|item| item.id == id) {
+            item.name = name;
+            item.value = value;
+            self.modified.insert(id);
+            self.revision += 1;
+        }
+    }
+
+    /// Inserts or replaces a whole record, e.g. after resolving a sync conflict.
+    pub fn put_record(&mut self, record: TableData, modified: bool) {
+        let id = record.id;
+        match self.data.iter_mut().find(|item| item.id == id) {
+            Some(item) => *item = record,
+            None => {
+                self.data.push(record);
+                self.data.sort_by_key(|item| item.id);
+            }
+        }
+        if modified {
+            self.modified.insert(id);
+        } else {
+            self.modified.remove(&id);
+        }
+        self.next_id = self.next_id.max(id + 1);
+        self.revision += 1;
+    }
+
+    pub fn is_modified(&self, id: u32) -> bool {
+        self.modified.contains(&id)
+    }
+
+    pub fn get_modified_records(&self) -> Vec<TableData> {
+        self.data.iter().filter(|item| self.modified.contains(&item.id)).cloned().collect()
+    }
+
+    /// Clears the modified flag for records that were pushed successfully.
+    pub fn mark_synced(&mut self, ids: &[u32]) {
+        for id in ids {
+            self.modified.remove(id);
+        }
+    }
+
+    /// Takes the result of a sync. `pending` lists records that still have to be pushed.
+    pub fn replace_synced(&mut self, data: Vec<TableData>, pending: impl IntoIterator<Item = u32>) {
+        self.next_id = self.next_id.max(data.iter().map(|item| item.id).max().unwrap_or(0) + 1);
+        self.data = data;
+        self.modified = pending.into_iter().collect();
+        self.revision += 1;
+    }
+
+    /// Counter bumped on every change, so callers can cheaply detect edits.
+    pub fn revision(&self) -> u64 {
+        self.revision
     }
 
     pub fn get_all_data(&self) -> &Vec<TableData> {
@@ -51,4 +179,8 @@ impl DataStore {
     pub fn get_record_count(&self) -> usize {
         self.data.len()
     }
+
+    pub fn get_data_in_range(&self, range: &DateRange) -> Vec<&TableData> {
+        self.data.iter().filter(|item| range.contains(&item.date)).collect()
+    }
 }
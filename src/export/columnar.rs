@@ -0,0 +1,222 @@
+use super::{ExportContext, Exporter, ExporterInfo, OptionKind, OptionSpec};
+use crate::data::{Field, TableData};
+use crate::storage;
+use anyhow::Result;
+use arrow::array::{ArrayRef, Float64Array, StringArray, TimestampMicrosecondArray, UInt32Array};
+use arrow::datatypes::{DataType, Schema, SchemaRef, TimeUnit};
+use arrow::ipc::writer::FileWriter;
+use arrow::record_batch::RecordBatch;
+use parquet::arrow::ArrowWriter;
+use parquet::basic::{BrotliLevel, Compression, GzipLevel, ZstdLevel};
+use parquet::file::properties::WriterProperties;
+use std::fs::File;
+use std::path::Path;
+use std::sync::Arc;
+
+const COMPRESSION_OPTION: OptionSpec = OptionSpec {
+    key: "compression",
+    label: "Compression",
+    kind: OptionKind::Choice(&["snappy", "zstd", "gzip", "lz4", "brotli", "none"]),
+    default: "snappy",
+    help: "Snappy is fast and widely supported; zstd gives smaller files",
+};
+
+/// Rows per record batch, which bounds memory use and sets how often progress is reported.
+const BATCH_ROWS: usize = 65_536;
+
+/// Timestamps are stored as UTC instants; readers convert them to local time.
+const TIMEZONE: &str = "UTC";
+
+/// Writes typed columns as an Apache Parquet or Arrow IPC file.
+pub struct ColumnarExporter {
+    parquet: bool,
+}
+
+impl ColumnarExporter {
+    pub fn parquet() -> Self {
+        Self { parquet: true }
+    }
+
+    pub fn arrow_ipc() -> Self {
+        Self { parquet: false }
+    }
+
+    fn write(&self, context: &ExportContext, path: &Path) -> Result<()> {
+        let schema = schema(context);
+        let file = File::create(path)?;
+
+        if self.parquet {
+            let compression = match context.options.value(&COMPRESSION_OPTION)? {
+                "zstd" => Compression::ZSTD(ZstdLevel::default()),
+                "gzip" => Compression::GZIP(GzipLevel::default()),
+                "lz4" => Compression::LZ4_RAW,
+                "brotli" => Compression::BROTLI(BrotliLevel::default()),
+                "none" => Compression::UNCOMPRESSED,
+                _ => Compression::SNAPPY,
+            };
+            let properties = WriterProperties::builder().set_compression(compression).build();
+            let mut writer = ArrowWriter::try_new(file, schema.clone(), Some(properties))?;
+            for (index, rows) in context.data.chunks(BATCH_ROWS).enumerate() {
+                writer.write(&batch(&schema, context, rows)?)?;
+                context.progress.set(index * BATCH_ROWS + rows.len())?;
+            }
+            writer.close()?;
+        } else {
+            let mut writer = FileWriter::try_new(file, &schema)?;
+            for (index, rows) in context.data.chunks(BATCH_ROWS).enumerate() {
+                writer.write(&batch(&schema, context, rows)?)?;
+                context.progress.set(index * BATCH_ROWS + rows.len())?;
+            }
+            writer.finish()?;
+        }
+        Ok(())
+    }
+}
+
+impl Exporter for ColumnarExporter {
+    fn info(&self) -> ExporterInfo {
+        if self.parquet {
+            ExporterInfo {
+                id: "parquet",
+                name: "Parquet",
+                extension: "parquet",
+                options: vec![COMPRESSION_OPTION],
+            }
+        } else {
+            ExporterInfo {
+                id: "arrow",
+                name: "Arrow IPC",
+                extension: "arrow",
+                options: Vec::new(),
+            }
+        }
+    }
+
+    fn export(&self, context: &ExportContext, path: &Path) -> Result<()> {
+        // Written next to the destination and renamed once complete, so a
+        // failed or cancelled export leaves no truncated file behind.
+        storage::replace_file(path, |temp_path| self.write(context, temp_path))
+    }
+}
+
+fn data_type(field: Field) -> DataType {
+    match field {
+        Field::Id => DataType::UInt32,
+        Field::Name => DataType::Utf8,
+        Field::Value => DataType::Float64,
+        Field::Date => DataType::Timestamp(TimeUnit::Microsecond, Some(TIMEZONE.into())),
+    }
+}
+
+fn schema(context: &ExportContext) -> SchemaRef {
+    let fields: Vec<arrow::datatypes::Field> = context
+        .columns
+        .iter()
+        .map(|column| arrow::datatypes::Field::new(&column.header, data_type(column.field), false))
+        .collect();
+    Arc::new(Schema::new(fields))
+}
+
+fn batch(schema: &SchemaRef, context: &ExportContext, rows: &[TableData]) -> Result<RecordBatch> {
+    let arrays: Vec<ArrayRef> = context
+        .columns
+        .iter()
+        .map(|column| -> ArrayRef {
+            match column.field {
+                Field::Id => Arc::new(UInt32Array::from_iter_values(rows.iter().map(|item| item.id))),
+                Field::Name => Arc::new(StringArray::from_iter_values(rows.iter().map(|item| &item.name))),
+                Field::Value => Arc::new(Float64Array::from_iter_values(rows.iter().map(|item| item.value))),
+                Field::Date => Arc::new(
+                    TimestampMicrosecondArray::from_iter_values(rows.iter().map(|item| item.date.timestamp_micros()))
+                        .with_timezone(TIMEZONE),
+                ),
+            }
+        })
+        .collect();
+    Ok(RecordBatch::try_new(schema.clone(), arrays)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::{self, TempDir};
+    use super::super::{ExportOptions, ExportProgress};
+    use super::*;
+    use arrow::array::{Array, AsArray};
+    use arrow::datatypes::{Float64Type, TimestampMicrosecondType, UInt32Type};
+    use arrow::ipc::reader::FileReader;
+    use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+
+    fn read_back(parquet: bool, path: &Path) -> (SchemaRef, Vec<RecordBatch>) {
+        let file = File::open(path).unwrap();
+        if parquet {
+            let builder = ParquetRecordBatchReaderBuilder::try_new(file).unwrap();
+            let schema = builder.schema().clone();
+            (schema, builder.build().unwrap().map(Result::unwrap).collect())
+        } else {
+            let reader = FileReader::try_new(file, None).unwrap();
+            (reader.schema(), reader.map(Result::unwrap).collect())
+        }
+    }
+
+    #[test]
+    fn written_files_have_typed_columns_and_every_value() {
+        let dir = TempDir::new();
+        let data = testing::records();
+        for (exporter, parquet) in [(ColumnarExporter::parquet(), true), (ColumnarExporter::arrow_ipc(), false)] {
+            let path = dir.join(&format!("records.{}", exporter.info().extension));
+            testing::export(&exporter, &data, &ExportOptions::default(), &path).unwrap();
+
+            let (schema, batches) = read_back(parquet, &path);
+            let columns: Vec<(&str, &DataType, bool)> = schema
+                .fields()
+                .iter()
+                .map(|field| (field.name().as_str(), field.data_type(), field.is_nullable()))
+                .collect();
+            assert_eq!(
+                columns,
+                [
+                    ("ID", &DataType::UInt32, false),
+                    ("Name", &DataType::Utf8, false),
+                    ("Value", &DataType::Float64, false),
+                    ("Date", &DataType::Timestamp(TimeUnit::Microsecond, Some(TIMEZONE.into())), false),
+                ]
+            );
+
+            assert_eq!(batches.len(), 1);
+            let batch = &batches[0];
+            assert_eq!(batch.num_rows(), data.len());
+            let ids = batch.column(0).as_primitive::<UInt32Type>();
+            let names = batch.column(1).as_string::<i32>();
+            let values = batch.column(2).as_primitive::<Float64Type>();
+            let dates = batch.column(3).as_primitive::<TimestampMicrosecondType>();
+            for (row, item) in data.iter().enumerate() {
+                assert_eq!(ids.value(row), item.id);
+                assert_eq!(names.value(row), item.name);
+                assert_eq!(values.value(row), item.value);
+                assert_eq!(dates.value(row), item.date.timestamp_micros());
+            }
+            assert_eq!(dates.null_count(), 0);
+        }
+    }
+
+    #[test]
+    fn a_cancelled_export_keeps_the_previous_file() {
+        let dir = TempDir::new();
+        let path = dir.join("records.parquet");
+        std::fs::write(&path, b"previous export").unwrap();
+        let progress = ExportProgress::new(3);
+        progress.cancel();
+
+        let result = testing::export_with_progress(
+            &ColumnarExporter::parquet(),
+            &testing::records(),
+            &ExportOptions::default(),
+            &progress,
+            &path,
+        );
+
+        assert!(result.is_err());
+        assert_eq!(std::fs::read(&path).unwrap(), b"previous export");
+        assert_eq!(dir.files(), ["records.parquet"]);
+    }
+}
@@ -0,0 +1,134 @@
+use super::{
+    DATE_FORMAT_OPTION, ExportColumn, ExportContext, ExportProgress, Exporter, ExporterInfo, LINE_ENDING_OPTION, OptionKind,
+    OptionSpec, QUOTING_OPTION, Quoting, TextExportOptions,
+};
+use crate::data::{Field, TableData};
+use anyhow::{Result, bail};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+const DELIMITER_OPTION: OptionSpec = OptionSpec {
+    key: "delimiter",
+    label: "Delimiter",
+    kind: OptionKind::Text,
+    default: ",",
+    help: "A single character separating fields, e.g. , or ;",
+};
+
+/// Writes CSV, TSV or any other single-character delimited text.
+pub struct DelimitedExporter {
+    /// Fixed delimiter, or `None` to make it configurable.
+    delimiter: Option<char>,
+}
+
+impl DelimitedExporter {
+    pub fn csv() -> Self {
+        Self { delimiter: None }
+    }
+
+    pub fn tsv() -> Self {
+        Self { delimiter: Some('\t') }
+    }
+
+    fn write_data(
+        &self,
+        data: &[TableData],
+        columns: &[ExportColumn],
+        options: &TextExportOptions,
+        progress: &ExportProgress,
+        path: &Path,
+    ) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+
+        let header: Vec<(String, bool)> = columns.iter().map(|column| (column.header.clone(), false)).collect();
+        write_row(&mut writer, options, &header)?;
+
+        for (index, item) in data.iter().enumerate() {
+            let row: Vec<(String, bool)> = columns
+                .iter()
+                .map(|column| match column.field {
+                    Field::Id => (item.id.to_string(), true),
+                    Field::Name => (item.name.clone(), false),
+                    Field::Value => (options.locale.format_decimal(item.value), true),
+                    Field::Date => (item.date.format(&options.date_format).to_string(), false),
+                })
+                .collect();
+            write_row(&mut writer, options, &row)?;
+            progress.set(index + 1)?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+impl Exporter for DelimitedExporter {
+    fn info(&self) -> ExporterInfo {
+        let mut options = Vec::new();
+        if self.delimiter.is_none() {
+            options.push(DELIMITER_OPTION);
+        }
+        options.extend([QUOTING_OPTION, LINE_ENDING_OPTION, DATE_FORMAT_OPTION]);
+
+        match self.delimiter {
+            Some('\t') => ExporterInfo {
+                id: "tsv",
+                name: "TSV",
+                extension: "tsv",
+                options,
+            },
+            _ => ExporterInfo {
+                id: "csv",
+                name: "CSV",
+                extension: "csv",
+                options,
+            },
+        }
+    }
+
+    fn export(&self, context: &ExportContext, path: &Path) -> Result<()> {
+        let options = context.options;
+        let delimiter = match self.delimiter {
+            Some(delimiter) => delimiter,
+            None => {
+                let value = options.value(&DELIMITER_OPTION)?;
+                let mut chars = value.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) if c != '"' && c != '\n' && c != '\r' => c,
+                    _ => bail!("Delimiter must be a single character other than a quote or line break"),
+                }
+            }
+        };
+
+        let options = TextExportOptions::from_options(options, delimiter, context.locale)?;
+        self.write_data(context.data, context.columns, &options, context.progress, path)
+    }
+}
+
+/// Writes one row. Each field is paired with whether it is numeric.
+fn write_row(writer: &mut impl Write, options: &TextExportOptions, fields: &[(String, bool)]) -> Result<()> {
+    let mut delimiter = [0u8; 4];
+    let delimiter = options.delimiter.encode_utf8(&mut delimiter);
+
+    for (index, (field, numeric)) in fields.iter().enumerate() {
+        if index > 0 {
+            writer.write_all(delimiter.as_bytes())?;
+        }
+        if needs_quotes(options, field, *numeric) {
+            write!(writer, "\"{}\"", field.replace('"', "\"\""))?;
+        } else {
+            writer.write_all(field.as_bytes())?;
+        }
+    }
+    writer.write_all(options.line_ending.as_str().as_bytes())?;
+    Ok(())
+}
+
+fn needs_quotes(options: &TextExportOptions, field: &str, numeric: bool) -> bool {
+    match options.quoting {
+        Quoting::All => true,
+        Quoting::NonNumeric if !numeric => true,
+        _ => field.contains([options.delimiter, '"', '\n', '\r']),
+    }
+}
@@ -0,0 +1,1041 @@
+use super::excel_import::{
+    FORMAT, FORMAT_PROPERTY, FORMAT_VERSION, FORMAT_VERSION_PROPERTY, METADATA_SHEET, RECORD_COLUMNS,
+    RECORD_COUNT_PROPERTY, RECORD_SHEETS_PROPERTY, RECORDS_SHEET,
+};
+use super::template::{self, TemplateTarget};
+use super::{ExportColumn, ExportContext, ExportOptions, Exporter, ExporterInfo, OptionKind, OptionSpec};
+use crate::data::{Field, TableData};
+use anyhow::{Result, anyhow, bail};
+use rust_xlsxwriter::*;
+use std::collections::BTreeSet;
+use std::path::Path;
+
+const DATE_FORMAT_OPTION: OptionSpec = OptionSpec {
+    key: "date_format",
+    label: "Date format",
+    kind: OptionKind::Text,
+    default: "",
+    help: "Excel number format for date cells, e.g. dd.mm.yyyy or yyyy-mm-dd; empty uses the regional format from Settings",
+};
+
+const VALUE_FORMAT_OPTION: OptionSpec = OptionSpec {
+    key: "value_format",
+    label: "Value format",
+    kind: OptionKind::Text,
+    default: "#,##0.00",
+    help: "Excel number format for values, e.g. 0.00, #,##0.00 or currency such as [$€-x-euro2] #,##0.00. Excel shows the separators of the computer opening the file",
+};
+
+const REPORT_OPTION: OptionSpec = OptionSpec {
+    key: "report",
+    label: "Report sheets",
+    kind: OptionKind::Bool,
+    default: "true",
+    help: "Add Summary, By Name and Metadata sheets next to the data",
+};
+
+const ROUNDTRIP_OPTION: OptionSpec = OptionSpec {
+    key: "roundtrip",
+    label: "Round-trip data",
+    kind: OptionKind::Bool,
+    default: "true",
+    help: "Embed hidden sheets with the exact records and export settings, so the file can be imported back without loss; left out of masked exports",
+};
+
+const TABLE_OPTION: OptionSpec = OptionSpec {
+    key: "table",
+    label: "Excel Table",
+    kind: OptionKind::Bool,
+    default: "true",
+    help: "Format the data as an Excel Table with filter buttons and banded rows",
+};
+
+const FREEZE_HEADER_OPTION: OptionSpec = OptionSpec {
+    key: "freeze_header",
+    label: "Freeze header",
+    kind: OptionKind::Bool,
+    default: "true",
+    help: "Keep the header row visible while scrolling",
+};
+
+const DATA_BARS_OPTION: OptionSpec = OptionSpec {
+    key: "value_data_bars",
+    label: "Value data bars",
+    kind: OptionKind::Bool,
+    default: "false",
+    help: "Draw a data bar in each value cell",
+};
+
+const COLOR_SCALE_OPTION: OptionSpec = OptionSpec {
+    key: "value_color_scale",
+    label: "Value color scale",
+    kind: OptionKind::Bool,
+    default: "false",
+    help: "Shade values from red (lowest) through yellow to green (highest)",
+};
+
+const HIGHLIGHT_BELOW_OPTION: OptionSpec = OptionSpec {
+    key: "highlight_below",
+    label: "Highlight below",
+    kind: OptionKind::Text,
+    default: "",
+    help: "Highlight values below this number in red. Leave empty to disable",
+};
+
+const HIGHLIGHT_ABOVE_OPTION: OptionSpec = OptionSpec {
+    key: "highlight_above",
+    label: "Highlight above",
+    kind: OptionKind::Text,
+    default: "",
+    help: "Highlight values above this number in green. Leave empty to disable",
+};
+
+const LINE_CHART_OPTION: OptionSpec = OptionSpec {
+    key: "line_chart",
+    label: "Line chart",
+    kind: OptionKind::Bool,
+    default: "false",
+    help: "Embed a line chart of value over date",
+};
+
+const LINE_CHART_TITLE_OPTION: OptionSpec = OptionSpec {
+    key: "line_chart_title",
+    label: "Line chart title",
+    kind: OptionKind::Text,
+    default: "Value over time",
+    help: "Leave empty for no title",
+};
+
+const LINE_CHART_X_AXIS_OPTION: OptionSpec = OptionSpec {
+    key: "line_chart_x_axis",
+    label: "Line chart X axis",
+    kind: OptionKind::Text,
+    default: "Date",
+    help: "Label of the date axis",
+};
+
+const LINE_CHART_Y_AXIS_OPTION: OptionSpec = OptionSpec {
+    key: "line_chart_y_axis",
+    label: "Line chart Y axis",
+    kind: OptionKind::Text,
+    default: "Value",
+    help: "Label of the value axis",
+};
+
+const LINE_CHART_POSITION_OPTION: OptionSpec = OptionSpec {
+    key: "line_chart_position",
+    label: "Line chart position",
+    kind: OptionKind::Text,
+    default: "Charts!B2",
+    help: "Sheet and top-left cell, e.g. Charts!B2 or Data!G2. Unknown sheets are created",
+};
+
+const BAR_CHART_OPTION: OptionSpec = OptionSpec {
+    key: "bar_chart",
+    label: "Bar chart",
+    kind: OptionKind::Bool,
+    default: "false",
+    help: "Embed a bar chart of totals per name, taken from the By Name sheet",
+};
+
+const BAR_CHART_TITLE_OPTION: OptionSpec = OptionSpec {
+    key: "bar_chart_title",
+    label: "Bar chart title",
+    kind: OptionKind::Text,
+    default: "Total per name",
+    help: "Leave empty for no title",
+};
+
+const BAR_CHART_X_AXIS_OPTION: OptionSpec = OptionSpec {
+    key: "bar_chart_x_axis",
+    label: "Bar chart X axis",
+    kind: OptionKind::Text,
+    default: "Name",
+    help: "Label of the category axis",
+};
+
+const BAR_CHART_Y_AXIS_OPTION: OptionSpec = OptionSpec {
+    key: "bar_chart_y_axis",
+    label: "Bar chart Y axis",
+    kind: OptionKind::Text,
+    default: "Total",
+    help: "Label of the value axis",
+};
+
+const BAR_CHART_POSITION_OPTION: OptionSpec = OptionSpec {
+    key: "bar_chart_position",
+    label: "Bar chart position",
+    kind: OptionKind::Text,
+    default: "Charts!B18",
+    help: "Sheet and top-left cell, e.g. Charts!B18 or By Name!F2. Unknown sheets are created",
+};
+
+const TEMPLATE_OPTION: OptionSpec = OptionSpec {
+    key: "template",
+    label: "Template",
+    kind: OptionKind::Text,
+    default: "",
+    help: "Path of an .xlsx or .xltx workbook to fill instead of creating a new one. Leave empty to disable",
+};
+
+const TEMPLATE_START_OPTION: OptionSpec = OptionSpec {
+    key: "template_start",
+    label: "Template start cell",
+    kind: OptionKind::Text,
+    default: "Sheet1!A2",
+    help: "Sheet and cell of the first data row, e.g. Data!B5. The template's formatting of that row is used for every row",
+};
+
+const TEMPLATE_COLUMNS_OPTION: OptionSpec = OptionSpec {
+    key: "template_columns",
+    label: "Template columns",
+    kind: OptionKind::Text,
+    default: "",
+    help: "Fields for the columns from the start cell, e.g. Date,Name,,Value; an empty entry skips a column. Leave empty for the exported columns",
+};
+
+/// Estimated widths, in characters, of formatted cells.
+const DATE_COLUMN_WIDTH: usize = 19;
+const VALUE_COLUMN_WIDTH: usize = 14;
+const MAX_COLUMN_WIDTH: usize = 60;
+
+/// Data rows per sheet: Excel's 1,048,576 row limit minus the header row.
+const ROWS_PER_SHEET: usize = 1_048_575;
+
+/// Rows between progress updates.
+const PROGRESS_INTERVAL: usize = 1000;
+
+const DATA_SHEET: &str = "Data";
+const BREAKDOWN_SHEET: &str = "By Name";
+
+/// Title, axis labels and placement of one embedded chart.
+struct ChartSettings {
+    title: String,
+    x_axis: String,
+    y_axis: String,
+    sheet: String,
+    row: u32,
+    col: u16,
+}
+
+impl ChartSettings {
+    fn from_options(
+        context: &ExportContext,
+        [title, x_axis, y_axis, position]: [&OptionSpec; 4],
+    ) -> Result<Self> {
+        let options = context.options;
+        let (sheet, row, col) = parse_position(options.value(position)?)
+            .ok_or_else(|| anyhow!("Invalid {} \"{}\", expected e.g. Charts!B2", position.label, options.get(position)))?;
+        check_sheet_name(&sheet)?;
+
+        Ok(Self {
+            title: options.value(title)?.to_string(),
+            x_axis: options.value(x_axis)?.to_string(),
+            y_axis: options.value(y_axis)?.to_string(),
+            sheet,
+            row,
+            col,
+        })
+    }
+
+    fn apply(&self, chart: &mut Chart) {
+        if self.title.is_empty() {
+            chart.title().set_hidden();
+        } else {
+            chart.title().set_name(&self.title);
+        }
+        chart.x_axis().set_name(&self.x_axis);
+        chart.y_axis().set_name(&self.y_axis);
+        chart.legend().set_hidden();
+    }
+}
+
+/// Sheet name as written in a formula, quoted when it contains anything but letters and digits.
+fn quote_sheet_name(name: &str) -> String {
+    if name.chars().all(|c| c.is_ascii_alphanumeric()) {
+        name.to_string()
+    } else {
+        format!("'{}'", name.replace('\'', "''"))
+    }
+}
+
+/// Splits `Sheet!B2` into the sheet name and zero-based row and column.
+fn parse_position(position: &str) -> Option<(String, u32, u16)> {
+    let (sheet, cell) = position.rsplit_once('!')?;
+    let sheet = sheet.trim().trim_matches('\'');
+    let cell = cell.trim().to_ascii_uppercase();
+    let split = cell.find(|c: char| c.is_ascii_digit())?;
+    let (letters, digits) = cell.split_at(split);
+    if sheet.is_empty() || letters.is_empty() || !letters.chars().all(|c| c.is_ascii_uppercase()) {
+        return None;
+    }
+    let row: u32 = digits.parse().ok().filter(|row| *row > 0)?;
+    Some((sheet.to_string(), row - 1, column_name_to_number(letters)))
+}
+
+/// One sheet's share of the exported rows.
+struct DataSheet {
+    name: String,
+    /// Index of the sheet's first row in the exported data.
+    first: usize,
+    len: usize,
+}
+
+impl DataSheet {
+    /// Last row covered by tables and ranges; an empty sheet still covers one row.
+    fn last_row(&self) -> u32 {
+        self.len.max(1) as u32
+    }
+}
+
+/// Formats shared by all sheets of one workbook.
+struct Formats {
+    header: Format,
+    id: Format,
+    value: Format,
+    date: Format,
+}
+
+pub struct ExcelExporter;
+
+impl ExcelExporter {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Splits the rows across as many data sheets as Excel's row limit requires.
+    fn data_sheets(context: &ExportContext) -> Vec<DataSheet> {
+        let total = context.data.len();
+        (0..total.div_ceil(ROWS_PER_SHEET).max(1))
+            .map(|index| {
+                let first = index * ROWS_PER_SHEET;
+                DataSheet {
+                    name: match index {
+                        0 => DATA_SHEET.to_string(),
+                        _ => format!("{} {}", DATA_SHEET, index + 1),
+                    },
+                    first,
+                    len: (total - first).min(ROWS_PER_SHEET),
+                }
+            })
+            .collect()
+    }
+
+    /// Writes one data sheet in constant memory mode, so everything above a
+    /// row has to be set up before the row is written.
+    fn write_data_sheet(
+        worksheet: &mut Worksheet,
+        context: &ExportContext,
+        sheet: &DataSheet,
+        formats: &Formats,
+    ) -> Result<()> {
+        worksheet.set_name(&sheet.name)?;
+        let options = context.options;
+        let last_row = sheet.last_row();
+        let last_col = context.columns.len().saturating_sub(1) as u16;
+        let table = options.flag(&TABLE_OPTION)? && !context.columns.is_empty();
+
+        for (col, column) in context.columns.iter().enumerate() {
+            let width = Self::column_width(context, column, table);
+            worksheet.set_column_width(col as u16, width)?;
+        }
+        if options.flag(&FREEZE_HEADER_OPTION)? {
+            worksheet.set_freeze_panes(1, 0)?;
+        }
+        if let Some(col) = Self::column_index(context, Field::Value) {
+            Self::add_value_highlights(worksheet, options, col, last_row)?;
+        }
+
+        if table {
+            let columns: Vec<TableColumn> = context
+                .columns
+                .iter()
+                .map(|column| TableColumn::new().set_header(&column.header))
+                .collect();
+            let table = Table::new().set_columns(&columns).set_banded_rows(true).set_autofilter(true);
+            worksheet.add_table(0, 0, last_row, last_col, &table)?;
+        } else {
+            for (col, column) in context.columns.iter().enumerate() {
+                worksheet.write_with_format(0, col as u16, &column.header, &formats.header)?;
+            }
+        }
+
+        let rows = &context.data[sheet.first..sheet.first + sheet.len];
+        for (row, item) in rows.iter().enumerate() {
+            let excel_row = (row + 1) as u32;
+            for (col, column) in context.columns.iter().enumerate() {
+                let col = col as u16;
+                match column.field {
+                    Field::Id => worksheet.write_number_with_format(excel_row, col, item.id, &formats.id)?,
+                    Field::Name => worksheet.write(excel_row, col, &item.name)?,
+                    Field::Value => worksheet.write_number_with_format(excel_row, col, item.value, &formats.value)?,
+                    Field::Date => {
+                        worksheet.write_datetime_with_format(excel_row, col, item.date.naive_local(), &formats.date)?
+                    }
+                };
+            }
+            if row % PROGRESS_INTERVAL == 0 {
+                context.progress.set(sheet.first + row)?;
+            }
+        }
+        context.progress.set(sheet.first + sheet.len)?;
+        Ok(())
+    }
+
+    /// Autofit needs the cells in memory, so widths are estimated from the data instead.
+    fn column_width(context: &ExportContext, column: &ExportColumn, table: bool) -> f64 {
+        let header = column.header.chars().count() + if table { 3 } else { 0 };
+        let content = match column.field {
+            Field::Id => context.data.iter().map(|item| item.id).max().unwrap_or(0).to_string().len(),
+            Field::Name => context.data.iter().map(|item| item.name.chars().count()).max().unwrap_or(0),
+            Field::Value => VALUE_COLUMN_WIDTH,
+            Field::Date => DATE_COLUMN_WIDTH,
+        };
+        (header.max(content) + 1).min(MAX_COLUMN_WIDTH) as f64
+    }
+
+    fn add_value_highlights(worksheet: &mut Worksheet, options: &ExportOptions, col: u16, last_row: u32) -> Result<()> {
+        if options.flag(&DATA_BARS_OPTION)? {
+            worksheet.add_conditional_format(1, col, last_row, col, &ConditionalFormatDataBar::new())?;
+        }
+        if options.flag(&COLOR_SCALE_OPTION)? {
+            let scale = ConditionalFormat3ColorScale::new()
+                .set_minimum_color(Color::RGB(0xF8696B))
+                .set_midpoint_color(Color::RGB(0xFFEB84))
+                .set_maximum_color(Color::RGB(0x63BE7B));
+            worksheet.add_conditional_format(1, col, last_row, col, &scale)?;
+        }
+
+        let thresholds = [
+            (&HIGHLIGHT_BELOW_OPTION, Color::RGB(0xFFC7CE), Color::RGB(0x9C0006)),
+            (&HIGHLIGHT_ABOVE_OPTION, Color::RGB(0xC6EFCE), Color::RGB(0x006100)),
+        ];
+        for (spec, fill, font) in thresholds {
+            let value = options.value(spec)?.trim();
+            if value.is_empty() {
+                continue;
+            }
+            let threshold: f64 = value
+                .parse()
+                .map_err(|_| anyhow!("Invalid {} \"{}\", expected a number", spec.label, value))?;
+            let rule = if spec.key == HIGHLIGHT_BELOW_OPTION.key {
+                ConditionalFormatCellRule::LessThan(threshold)
+            } else {
+                ConditionalFormatCellRule::GreaterThan(threshold)
+            };
+            let highlight = ConditionalFormatCell::new()
+                .set_rule(rule)
+                .set_format(Format::new().set_background_color(fill).set_font_color(font));
+            worksheet.add_conditional_format(1, col, last_row, col, &highlight)?;
+        }
+        Ok(())
+    }
+
+    fn column_index(context: &ExportContext, field: Field) -> Option<u16> {
+        context.columns.iter().position(|column| column.field == field).map(|col| col as u16)
+    }
+
+    /// Absolute references to the data cells of a column, one per data sheet,
+    /// e.g. `Data!$C$2:$C$11`.
+    fn data_ranges(context: &ExportContext, sheets: &[DataSheet], field: Field) -> Option<Vec<String>> {
+        let col = Self::column_index(context, field)?;
+        let ranges = sheets
+            .iter()
+            .map(|sheet| {
+                let range = cell_range_absolute(1, col, sheet.last_row(), col);
+                format!("{}!{}", quote_sheet_name(&sheet.name), range)
+            })
+            .collect();
+        Some(ranges)
+    }
+
+    fn write_summary_sheet(
+        worksheet: &mut Worksheet,
+        context: &ExportContext,
+        sheets: &[DataSheet],
+        formats: &Formats,
+    ) -> Result<()> {
+        worksheet.set_name("Summary")?;
+        worksheet.write_with_format(0, 0, "Metric", &formats.header)?;
+        worksheet.write_with_format(0, 1, "Value", &formats.header)?;
+
+        let Some(values) = Self::data_ranges(context, sheets, Field::Value) else {
+            worksheet.write(1, 0, "The Value column was not exported.")?;
+            worksheet.autofit();
+            return Ok(());
+        };
+
+        let values = values.join(",");
+        let metrics = [
+            ("Count", format!("=COUNT({})", values), &formats.id),
+            ("Sum", format!("=SUM({})", values), &formats.value),
+            ("Average", format!("=IFERROR(AVERAGE({}),\"\")", values), &formats.value),
+            ("Min", format!("=MIN({})", values), &formats.value),
+            ("Max", format!("=MAX({})", values), &formats.value),
+        ];
+        for (row, (label, formula, format)) in metrics.into_iter().enumerate() {
+            let row = (row + 1) as u32;
+            worksheet.write(row, 0, label)?;
+            worksheet.write_formula_with_format(row, 1, formula.as_str(), format)?;
+        }
+
+        worksheet.set_column_width(0, 12)?;
+        worksheet.set_column_width(1, 16)?;
+        Ok(())
+    }
+
+    fn write_breakdown_sheet(
+        worksheet: &mut Worksheet,
+        context: &ExportContext,
+        sheets: &[DataSheet],
+        formats: &Formats,
+    ) -> Result<()> {
+        worksheet.set_name(BREAKDOWN_SHEET)?;
+
+        let (Some(names), Some(values)) = (
+            Self::data_ranges(context, sheets, Field::Name),
+            Self::data_ranges(context, sheets, Field::Value),
+        ) else {
+            worksheet.write(0, 0, "The breakdown needs both the Name and Value columns.")?;
+            worksheet.autofit();
+            return Ok(());
+        };
+
+        for (col, header) in ["Name", "Count", "Total", "Average"].into_iter().enumerate() {
+            worksheet.write_with_format(0, col as u16, header, &formats.header)?;
+        }
+
+        let unique: BTreeSet<&str> = context.data.iter().map(|item| item.name.as_str()).collect();
+        for (row, name) in unique.into_iter().enumerate() {
+            let row = (row + 1) as u32;
+            let name_cell = row_col_to_cell(row, 0);
+            // An equality test rather than COUNTIF/SUMIF criteria, which would treat
+            // `*`, `?` and a leading `<`, `>` or `=` in names as patterns. Each range
+            // is on one sheet, so continuation sheets are added up.
+            let count = names
+                .iter()
+                .map(|names| format!("SUMPRODUCT(--({}={}))", names, name_cell))
+                .collect::<Vec<_>>()
+                .join("+");
+            let total = names
+                .iter()
+                .zip(&values)
+                .map(|(names, values)| format!("SUMPRODUCT(--({}={}),{})", names, name_cell, values))
+                .collect::<Vec<_>>()
+                .join("+");
+            let average = format!("=IFERROR({}/{},\"\")", row_col_to_cell(row, 2), row_col_to_cell(row, 1));
+
+            worksheet.write(row, 0, name)?;
+            worksheet.write_formula_with_format(row, 1, format!("={}", count).as_str(), &formats.id)?;
+            worksheet.write_formula_with_format(row, 2, format!("={}", total).as_str(), &formats.value)?;
+            worksheet.write_formula_with_format(row, 3, average.as_str(), &formats.value)?;
+        }
+
+        worksheet.autofit();
+        worksheet.set_column_width(2, 16)?;
+        worksheet.set_column_width(3, 16)?;
+        Ok(())
+    }
+
+    fn line_chart(context: &ExportContext, sheets: &[DataSheet], settings: &ChartSettings) -> Result<Chart> {
+        let (Some(date_col), Some(value_col)) =
+            (Self::column_index(context, Field::Date), Self::column_index(context, Field::Value))
+        else {
+            bail!("The line chart needs both the Date and Value columns");
+        };
+
+        let mut chart = Chart::new(ChartType::Line);
+        for sheet in sheets {
+            let last_row = sheet.last_row();
+            chart
+                .add_series()
+                .set_categories((sheet.name.as_str(), 1, date_col, last_row, date_col))
+                .set_values((sheet.name.as_str(), 1, value_col, last_row, value_col));
+        }
+        // A date axis plots points by date, so unsorted rows still draw a proper timeline.
+        chart.x_axis().set_date_axis(true).set_num_format(context.locale.excel_date_format());
+        settings.apply(&mut chart);
+        Ok(chart)
+    }
+
+    fn bar_chart(context: &ExportContext, settings: &ChartSettings) -> Result<Chart> {
+        if Self::column_index(context, Field::Name).is_none() || Self::column_index(context, Field::Value).is_none() {
+            bail!("The bar chart needs both the Name and Value columns");
+        }
+        let names = context.data.iter().map(|item| item.name.as_str()).collect::<BTreeSet<_>>().len();
+        let last_row = names.max(1) as u32;
+
+        let mut chart = Chart::new(ChartType::Column);
+        chart
+            .add_series()
+            .set_categories((BREAKDOWN_SHEET, 1, 0, last_row, 0))
+            .set_values((BREAKDOWN_SHEET, 1, 2, last_row, 2));
+        settings.apply(&mut chart);
+        Ok(chart)
+    }
+
+    fn insert_chart(workbook: &mut Workbook, chart: &Chart, settings: &ChartSettings) -> Result<()> {
+        let worksheet = match workbook.worksheet_from_name(&settings.sheet) {
+            Ok(worksheet) => worksheet,
+            Err(_) => workbook.add_worksheet().set_name(&settings.sheet)?,
+        };
+        worksheet.insert_chart(settings.row, settings.col, chart)?;
+        Ok(())
+    }
+
+    /// Adds the hidden sheets `import_excel` reads: a metadata sheet describing
+    /// the export and record sheets with every field in a lossless form.
+    fn write_roundtrip_sheets(workbook: &mut Workbook, context: &ExportContext) -> Result<()> {
+        let chunks: Vec<&[TableData]> = match context.data.is_empty() {
+            true => vec![&[]],
+            false => context.data.chunks(ROWS_PER_SHEET).collect(),
+        };
+        let names: Vec<String> = (0..chunks.len())
+            .map(|index| match index {
+                0 => RECORDS_SHEET.to_string(),
+                _ => format!("{} {}", RECORDS_SHEET, index + 1),
+            })
+            .collect();
+
+        let schema = serde_json::json!({
+            "ID": "integer",
+            "Name": "text",
+            "Value": "number, or text for NaN and infinity",
+            "Date": "RFC 3339 timestamp",
+        });
+        let rows = [
+            (FORMAT_PROPERTY, FORMAT.to_string()),
+            (FORMAT_VERSION_PROPERTY, FORMAT_VERSION.to_string()),
+            ("Application", env!("CARGO_PKG_NAME").to_string()),
+            ("App version", env!("CARGO_PKG_VERSION").to_string()),
+            ("Exported at", chrono::Local::now().to_rfc3339()),
+            (RECORD_COUNT_PROPERTY, context.data.len().to_string()),
+            (RECORD_SHEETS_PROPERTY, serde_json::to_string(&names)?),
+            ("Record schema", schema.to_string()),
+            ("Exported columns", serde_json::to_string(context.columns)?),
+            ("Filter", context.filter.to_string()),
+            ("Export settings", serde_json::to_string(context.options)?),
+        ];
+        let worksheet = workbook.add_worksheet();
+        worksheet.set_name(METADATA_SHEET)?.set_hidden(true);
+        for (row, (key, value)) in rows.into_iter().enumerate() {
+            worksheet.write(row as u32, 0, key)?;
+            worksheet.write(row as u32, 1, value)?;
+        }
+
+        for (name, records) in names.iter().zip(chunks) {
+            let worksheet = workbook.add_worksheet_with_constant_memory();
+            worksheet.set_name(name)?.set_hidden(true);
+            for (col, header) in RECORD_COLUMNS.into_iter().enumerate() {
+                worksheet.write(0, col as u16, header)?;
+            }
+            for (index, item) in records.iter().enumerate() {
+                let row = (index + 1) as u32;
+                worksheet.write(row, 0, item.id)?;
+                // Constant-memory sheets store strings inline, where the writer
+                // encodes control characters as `_xHHHH_` and escapes literal
+                // `_xHHHH_` text as `_x005F_xHHHH_`, so every name reads back as is.
+                worksheet.write(row, 1, &item.name)?;
+                // Excel numbers cannot hold NaN or infinity; their text parses back exactly.
+                match item.value.is_finite() {
+                    true => worksheet.write(row, 2, item.value)?,
+                    false => worksheet.write(row, 2, item.value.to_string())?,
+                };
+                worksheet.write(row, 3, item.date.to_rfc3339())?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Fills the configured template instead of building a workbook.
+    fn fill_template(context: &ExportContext, template: &str, path: &Path) -> Result<()> {
+        let options = context.options;
+        let (sheet, row, col) = parse_position(options.value(&TEMPLATE_START_OPTION)?).ok_or_else(|| {
+            anyhow!(
+                "Invalid {} \"{}\", expected e.g. Sheet1!A2",
+                TEMPLATE_START_OPTION.label,
+                options.get(&TEMPLATE_START_OPTION)
+            )
+        })?;
+
+        let columns = options.value(&TEMPLATE_COLUMNS_OPTION)?.trim();
+        let fields = if columns.is_empty() {
+            context.columns.iter().map(|column| Some(column.field)).collect()
+        } else {
+            columns
+                .split(',')
+                .map(|name| {
+                    let name = name.trim();
+                    if name.is_empty() {
+                        return Ok(None);
+                    }
+                    Field::ALL
+                        .into_iter()
+                        .find(|field| field.label().eq_ignore_ascii_case(name))
+                        .map(Some)
+                        .ok_or_else(|| anyhow!("Unknown field \"{}\" in {}, expected ID, Name, Value or Date", name, TEMPLATE_COLUMNS_OPTION.label))
+                })
+                .collect::<Result<Vec<_>>>()?
+        };
+
+        let target = TemplateTarget { sheet, row, col, fields };
+        template::fill(context, Path::new(template), &target, path)
+    }
+
+    fn write_metadata_sheet(worksheet: &mut Worksheet, context: &ExportContext, formats: &Formats) -> Result<()> {
+        worksheet.set_name("Metadata")?;
+        worksheet.write_with_format(0, 0, "Property", &formats.header)?;
+        worksheet.write_with_format(0, 1, "Value", &formats.header)?;
+
+        let rows = [
+            ("Application", env!("CARGO_PKG_NAME").to_string()),
+            ("App version", env!("CARGO_PKG_VERSION").to_string()),
+            ("Exported at", chrono::Local::now().format("%Y-%m-%d %H:%M:%S %:z").to_string()),
+            ("Row count", context.data.len().to_string()),
+            ("Filter", context.filter.to_string()),
+        ];
+        for (row, (key, value)) in rows.into_iter().enumerate() {
+            let row = (row + 1) as u32;
+            worksheet.write(row, 0, key)?;
+            worksheet.write(row, 1, value)?;
+        }
+
+        worksheet.autofit();
+        Ok(())
+    }
+}
+
+impl Exporter for ExcelExporter {
+    fn info(&self) -> ExporterInfo {
+        ExporterInfo {
+            id: "xlsx",
+            name: "Excel",
+            extension: "xlsx",
+            options: vec![
+                DATE_FORMAT_OPTION,
+                VALUE_FORMAT_OPTION,
+                TABLE_OPTION,
+                FREEZE_HEADER_OPTION,
+                DATA_BARS_OPTION,
+                COLOR_SCALE_OPTION,
+                HIGHLIGHT_BELOW_OPTION,
+                HIGHLIGHT_ABOVE_OPTION,
+                REPORT_OPTION,
+                ROUNDTRIP_OPTION,
+                LINE_CHART_OPTION,
+                LINE_CHART_TITLE_OPTION,
+                LINE_CHART_X_AXIS_OPTION,
+                LINE_CHART_Y_AXIS_OPTION,
+                LINE_CHART_POSITION_OPTION,
+                BAR_CHART_OPTION,
+                BAR_CHART_TITLE_OPTION,
+                BAR_CHART_X_AXIS_OPTION,
+                BAR_CHART_Y_AXIS_OPTION,
+                BAR_CHART_POSITION_OPTION,
+                TEMPLATE_OPTION,
+                TEMPLATE_START_OPTION,
+                TEMPLATE_COLUMNS_OPTION,
+            ],
+        }
+    }
+
+    fn export(&self, context: &ExportContext, path: &Path) -> Result<()> {
+        let template = context.options.value(&TEMPLATE_OPTION)?.trim();
+        if !template.is_empty() {
+            return Self::fill_template(context, template, path);
+        }
+
+        let formats = Formats {
+            header: Format::new().set_bold().set_background_color(Color::RGB(0xD3D3D3)),
+            id: Format::new().set_num_format("0"),
+            value: Format::new().set_num_format(context.options.value(&VALUE_FORMAT_OPTION)?),
+            date: Format::new().set_num_format(match context.options.value(&DATE_FORMAT_OPTION)?.trim() {
+                "" => context.locale.excel_date_time_format(),
+                format => format,
+            }),
+        };
+
+        let report = context.options.flag(&REPORT_OPTION)?;
+        let line_chart = match context.options.flag(&LINE_CHART_OPTION)? {
+            true => Some(ChartSettings::from_options(
+                context,
+                [&LINE_CHART_TITLE_OPTION, &LINE_CHART_X_AXIS_OPTION, &LINE_CHART_Y_AXIS_OPTION, &LINE_CHART_POSITION_OPTION],
+            )?),
+            false => None,
+        };
+        let bar_chart = match context.options.flag(&BAR_CHART_OPTION)? {
+            true => Some(ChartSettings::from_options(
+                context,
+                [&BAR_CHART_TITLE_OPTION, &BAR_CHART_X_AXIS_OPTION, &BAR_CHART_Y_AXIS_OPTION, &BAR_CHART_POSITION_OPTION],
+            )?),
+            false => None,
+        };
+
+        let mut workbook = Workbook::new();
+        let sheets = Self::data_sheets(context);
+        for sheet in &sheets {
+            Self::write_data_sheet(workbook.add_worksheet_with_constant_memory(), context, sheet, &formats)?;
+        }
+
+        if report {
+            Self::write_summary_sheet(workbook.add_worksheet(), context, &sheets, &formats)?;
+        }
+        // The bar chart reads its totals from the By Name sheet, so it is added for the chart too.
+        if report || bar_chart.is_some() {
+            Self::write_breakdown_sheet(workbook.add_worksheet(), context, &sheets, &formats)?;
+        }
+        if report {
+            Self::write_metadata_sheet(workbook.add_worksheet(), context, &formats)?;
+        }
+        // Masked records would import as if they were the real ones.
+        if context.options.flag(&ROUNDTRIP_OPTION)? && !context.masked {
+            Self::write_roundtrip_sheets(&mut workbook, context)?;
+        }
+
+        if let Some(settings) = &line_chart {
+            let chart = Self::line_chart(context, &sheets, settings)?;
+            Self::insert_chart(&mut workbook, &chart, settings)?;
+        }
+        if let Some(settings) = &bar_chart {
+            let chart = Self::bar_chart(context, settings)?;
+            Self::insert_chart(&mut workbook, &chart, settings)?;
+        }
+
+        workbook.save(path)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::{self, TempDir};
+    use super::super::xlsx::{self, Package, Row, WORKBOOK_PART, WORKBOOK_RELS_PART};
+    use super::super::ExportProgress;
+    use super::*;
+    use crate::locale::Locale;
+    use std::collections::BTreeMap;
+    use std::io::Cursor;
+    use zip::ZipArchive;
+
+    fn open(path: &Path) -> Package {
+        ZipArchive::new(Cursor::new(std::fs::read(path).unwrap())).unwrap()
+    }
+
+    fn sheet_xml(package: &mut Package, name: &str) -> String {
+        let workbook = xlsx::read_part(package, WORKBOOK_PART).unwrap();
+        let relationships = xlsx::read_part(package, WORKBOOK_RELS_PART).unwrap();
+        let part = xlsx::sheet_part(&workbook, &relationships, name).unwrap();
+        xlsx::read_part(package, &part).unwrap()
+    }
+
+    /// Cell text of a sheet's rows by zero-based row number.
+    fn rows(package: &mut Package, name: &str) -> BTreeMap<u32, Row> {
+        let sheet = sheet_xml(package, name);
+        let mut rows = BTreeMap::new();
+        xlsx::read_rows(&sheet, &xlsx::shared_strings(package).unwrap(), |row, cells| {
+            rows.insert(row, cells.clone());
+            Ok(())
+        })
+        .unwrap();
+        rows
+    }
+
+    #[test]
+    fn dates_and_values_are_typed_cells_with_the_chosen_formats() {
+        let dir = TempDir::new();
+        let path = dir.join("typed.xlsx");
+        let mut options = ExportOptions::default();
+        options.set(DATE_FORMAT_OPTION.key, "dd.mm.yyyy hh:mm");
+        options.set(VALUE_FORMAT_OPTION.key, "0.000");
+
+        testing::export(&ExcelExporter::new(), &testing::records(), &options, &path).unwrap();
+
+        let mut package = open(&path);
+        let data = rows(&mut package, DATA_SHEET);
+        assert_eq!(data[&0].values().collect::<Vec<_>>(), ["ID", "Name", "Value", "Date"]);
+        assert_eq!(data[&1][&0], "1");
+        assert_eq!(data[&1][&2].parse::<f64>().unwrap(), 10.5);
+        // 2024-01-15 09:30 as an Excel serial date.
+        let date: f64 = data[&1][&3].parse().unwrap();
+        assert!((date - (45306.0 + 9.5 / 24.0)).abs() < 1e-6, "{}", date);
+        assert_eq!(data[&2][&2].parse::<f64>().unwrap(), -3.25);
+
+        let sheet = sheet_xml(&mut package, DATA_SHEET);
+        let date_cell = &sheet[sheet.find(r#"<c r="D2""#).unwrap()..];
+        assert!(!date_cell[..date_cell.find('>').unwrap()].contains(" t="), "{}", date_cell);
+        let styles = xlsx::read_part(&mut package, "xl/styles.xml").unwrap();
+        assert!(styles.contains(r#"formatCode="dd.mm.yyyy hh:mm""#), "{}", styles);
+        assert!(styles.contains(r#"formatCode="0.000""#), "{}", styles);
+    }
+
+    #[test]
+    fn report_sheets_summarise_the_data_sheet() {
+        let dir = TempDir::new();
+        let (report, plain) = (dir.join("report.xlsx"), dir.join("plain.xlsx"));
+        let mut options = ExportOptions::default();
+
+        testing::export(&ExcelExporter::new(), &testing::records(), &options, &report).unwrap();
+        options.set(REPORT_OPTION.key, "false");
+        testing::export(&ExcelExporter::new(), &testing::records(), &options, &plain).unwrap();
+
+        let mut package = open(&report);
+        let workbook = xlsx::read_part(&mut package, WORKBOOK_PART).unwrap();
+        let sheets = xlsx::sheet_names(&workbook).unwrap();
+        assert_eq!(sheets[..4], [DATA_SHEET, "Summary", BREAKDOWN_SHEET, "Metadata"]);
+
+        let summary = sheet_xml(&mut package, "Summary");
+        for formula in ["COUNT", "SUM", "MIN", "MAX"] {
+            assert!(summary.contains(&format!("<f>{}(Data!$C$2:$C$4)</f>", formula)), "{}", summary);
+        }
+        assert!(summary.contains("AVERAGE(Data!$C$2:$C$4)"), "{}", summary);
+
+        let breakdown = rows(&mut package, BREAKDOWN_SHEET);
+        let names: Vec<&str> = breakdown.values().map(|row| row[&0].as_str()).collect();
+        assert_eq!(names, ["Name", "Alpha", "Beta"]);
+        assert!(sheet_xml(&mut package, BREAKDOWN_SHEET).contains("SUMPRODUCT(--(Data!$B$2:$B$4=A2),Data!$C$2:$C$4)"));
+
+        let metadata: BTreeMap<String, String> = rows(&mut package, "Metadata")
+            .into_values()
+            .map(|row| (row[&0].clone(), row[&1].clone()))
+            .collect();
+        assert_eq!(metadata["App version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(metadata["Row count"], "3");
+        assert_eq!(metadata["Filter"], "All rows");
+        assert!(!metadata["Exported at"].is_empty());
+
+        let workbook = xlsx::read_part(&mut open(&plain), WORKBOOK_PART).unwrap();
+        assert!(!xlsx::has_sheet(&workbook, "Summary").unwrap());
+        assert!(!xlsx::has_sheet(&workbook, BREAKDOWN_SHEET).unwrap());
+    }
+
+    #[test]
+    fn charts_are_embedded_with_their_titles_and_ranges() {
+        let dir = TempDir::new();
+        let path = dir.join("charts.xlsx");
+        let mut options = ExportOptions::default();
+        options.set(REPORT_OPTION.key, "false");
+        options.set(ROUNDTRIP_OPTION.key, "false");
+        options.set(LINE_CHART_OPTION.key, "true");
+        options.set(LINE_CHART_TITLE_OPTION.key, "Sales over time");
+        options.set(LINE_CHART_POSITION_OPTION.key, "Trend!B2");
+        options.set(BAR_CHART_OPTION.key, "true");
+        options.set(BAR_CHART_Y_AXIS_OPTION.key, "Sum of values");
+
+        testing::export(&ExcelExporter::new(), &testing::records(), &options, &path).unwrap();
+
+        let mut package = open(&path);
+        let workbook = xlsx::read_part(&mut package, WORKBOOK_PART).unwrap();
+        assert_eq!(xlsx::sheet_names(&workbook).unwrap(), [DATA_SHEET, BREAKDOWN_SHEET, "Trend", "Charts"]);
+        assert!(sheet_xml(&mut package, "Trend").contains("<drawing "));
+        assert!(sheet_xml(&mut package, "Charts").contains("<drawing "));
+
+        let line = xlsx::read_part(&mut package, "xl/charts/chart1.xml").unwrap();
+        assert!(line.contains("<c:lineChart>") && line.contains("Sales over time"), "{}", line);
+        assert!(line.contains("Data!$D$2:$D$4") && line.contains("Data!$C$2:$C$4"), "{}", line);
+        let bar = xlsx::read_part(&mut package, "xl/charts/chart2.xml").unwrap();
+        assert!(bar.contains("<c:barChart>") && bar.contains("Sum of values"), "{}", bar);
+        assert!(bar.contains("'By Name'!$A$2:$A$3") && bar.contains("'By Name'!$C$2:$C$3"), "{}", bar);
+    }
+
+    #[test]
+    fn charts_need_a_valid_position_and_their_columns() {
+        let dir = TempDir::new();
+        let path = dir.join("charts.xlsx");
+        let mut options = ExportOptions::default();
+        options.set(LINE_CHART_OPTION.key, "true");
+        options.set(LINE_CHART_POSITION_OPTION.key, "B2");
+        let error = testing::export(&ExcelExporter::new(), &testing::records(), &options, &path).unwrap_err();
+        assert!(error.to_string().contains("Invalid Line chart position"), "{}", error);
+
+        options.set(LINE_CHART_POSITION_OPTION.key, "Charts!B2");
+        let columns = [ExportColumn::new(Field::Name), ExportColumn::new(Field::Value)];
+        let error =
+            testing::export_columns(&ExcelExporter::new(), &testing::records(), &columns, &options, &path).unwrap_err();
+        assert!(error.to_string().contains("needs both the Date and Value columns"), "{}", error);
+    }
+
+    #[test]
+    fn data_is_a_table_with_a_frozen_header_and_value_highlights() {
+        let dir = TempDir::new();
+        let (table, plain) = (dir.join("table.xlsx"), dir.join("plain.xlsx"));
+        let mut options = ExportOptions::default();
+        options.set(DATA_BARS_OPTION.key, "true");
+        options.set(HIGHLIGHT_BELOW_OPTION.key, "0");
+        options.set(HIGHLIGHT_ABOVE_OPTION.key, "1000");
+
+        testing::export(&ExcelExporter::new(), &testing::records(), &options, &table).unwrap();
+
+        let mut package = open(&table);
+        let part = xlsx::read_part(&mut package, "xl/tables/table1.xml").unwrap();
+        assert!(part.contains(r#"ref="A1:D4""#) && part.contains(r#"<autoFilter ref="A1:D4"/>"#), "{}", part);
+        assert!(part.contains(r#"showRowStripes="1""#), "{}", part);
+        let sheet = sheet_xml(&mut package, DATA_SHEET);
+        assert!(sheet.contains(r#"<pane ySplit="1" topLeftCell="A2" activePane="bottomLeft" state="frozen"/>"#));
+        assert!(sheet.contains(r#"<conditionalFormatting sqref="C2:C4">"#), "{}", sheet);
+        assert_eq!(sheet.matches("<cfRule ").count(), 3, "{}", sheet);
+        assert!(sheet.contains("<dataBar>"), "{}", sheet);
+        assert!(sheet.contains(r#"operator="lessThan""#) && sheet.contains("<formula>0</formula>"), "{}", sheet);
+        assert!(sheet.contains(r#"operator="greaterThan""#) && sheet.contains("<formula>1000</formula>"));
+
+        options.set(TABLE_OPTION.key, "false");
+        options.set(FREEZE_HEADER_OPTION.key, "false");
+        options.set(HIGHLIGHT_BELOW_OPTION.key, "low");
+        let error = testing::export(&ExcelExporter::new(), &testing::records(), &options, &plain).unwrap_err();
+        assert!(error.to_string().contains("expected a number"), "{}", error);
+
+        options.set(HIGHLIGHT_BELOW_OPTION.key, "");
+        testing::export(&ExcelExporter::new(), &testing::records(), &options, &plain).unwrap();
+        let mut package = open(&plain);
+        assert!(package.by_name("xl/tables/table1.xml").is_err());
+        let sheet = sheet_xml(&mut package, DATA_SHEET);
+        assert!(!sheet.contains("<pane "), "{}", sheet);
+        assert_eq!(rows(&mut package, DATA_SHEET)[&0][&1], "Name");
+    }
+
+    #[test]
+    fn rows_past_the_sheet_limit_go_to_continuation_sheets() {
+        let data = vec![testing::record(1, "Alpha", 1.0, (2024, 1, 15)); ROWS_PER_SHEET + 2];
+        let columns = ExportColumn::all();
+        let progress = ExportProgress::new(data.len());
+        let context = ExportContext {
+            data: &data,
+            columns: &columns,
+            options: &ExportOptions::default(),
+            filter: "All rows",
+            locale: Locale::Standard,
+            masked: false,
+            progress: &progress,
+        };
+
+        let sheets = ExcelExporter::data_sheets(&context);
+        let split: Vec<(&str, usize, usize)> =
+            sheets.iter().map(|sheet| (sheet.name.as_str(), sheet.first, sheet.len)).collect();
+        assert_eq!(split, [("Data", 0, ROWS_PER_SHEET), ("Data 2", ROWS_PER_SHEET, 2)]);
+        assert_eq!(
+            ExcelExporter::data_ranges(&context, &sheets, Field::Value).unwrap(),
+            ["Data!$C$2:$C$1048576", "'Data 2'!$C$2:$C$3"]
+        );
+    }
+
+    #[test]
+    fn progress_reaches_every_row_and_cancelling_stops_the_export() {
+        let dir = TempDir::new();
+        let path = dir.join("progress.xlsx");
+        let data: Vec<TableData> =
+            (0..2500).map(|id| testing::record(id, "Alpha", f64::from(id), (2024, 1, 15))).collect();
+
+        let progress = ExportProgress::new(data.len());
+        testing::export_with_progress(&ExcelExporter::new(), &data, &ExportOptions::default(), &progress, &path)
+            .unwrap();
+        assert_eq!(progress.done(), data.len());
+        assert_eq!(progress.fraction(), 1.0);
+        assert_eq!(rows(&mut open(&path), DATA_SHEET).len(), data.len() + 1);
+
+        let progress = ExportProgress::new(data.len());
+        progress.cancel();
+        let result =
+            testing::export_with_progress(&ExcelExporter::new(), &data, &ExportOptions::default(), &progress, &path);
+        assert!(result.is_err());
+        assert!(progress.done() < data.len());
+    }
+}
@@ -0,0 +1,164 @@
+//! Reads the records back from an Excel export that embeds round-trip sheets.
+//!
+//! The exporter adds a hidden metadata sheet describing the export and hidden
+//! record sheets holding every field of every record in a lossless form: IDs
+//! and values as numbers, names as text and dates as RFC 3339 timestamps with
+//! their UTC offset. The visible sheets are ignored, so edits made to them in
+//! Excel do not affect what is imported.
+
+use super::xlsx::{self, Row, WORKBOOK_PART, WORKBOOK_RELS_PART};
+use crate::data::TableData;
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{DateTime, Local};
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Cursor;
+use std::path::Path;
+use zip::ZipArchive;
+
+pub const METADATA_SHEET: &str = "_metadata";
+pub const RECORDS_SHEET: &str = "_records";
+pub const FORMAT: &str = "desktop-app records";
+pub const FORMAT_VERSION: u32 = 1;
+
+/// Metadata properties the importer relies on.
+pub const FORMAT_PROPERTY: &str = "Format";
+pub const FORMAT_VERSION_PROPERTY: &str = "Format version";
+pub const RECORD_COUNT_PROPERTY: &str = "Record count";
+pub const RECORD_SHEETS_PROPERTY: &str = "Record sheets";
+
+/// Columns of the record sheets.
+pub const RECORD_COLUMNS: [&str; 4] = ["ID", "Name", "Value", "Date"];
+
+/// Loads the records of an Excel file written by `ExcelExporter` with round-trip sheets.
+pub fn import_excel(path: &Path) -> Result<Vec<TableData>> {
+    let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let mut package = ZipArchive::new(Cursor::new(bytes))
+        .with_context(|| format!("{} is not an .xlsx workbook", path.display()))?;
+
+    let workbook = xlsx::read_part(&mut package, WORKBOOK_PART)?;
+    let relationships = xlsx::read_part(&mut package, WORKBOOK_RELS_PART)?;
+    if !xlsx::has_sheet(&workbook, METADATA_SHEET)? {
+        bail!(
+            "{} has no round-trip data; export it from this app with the Round-trip data option on",
+            path.display()
+        );
+    }
+    let shared_strings = xlsx::shared_strings(&mut package)?;
+    let mut read_sheet = |name: &str, on_row: &mut dyn FnMut(u32, &Row) -> Result<()>| {
+        let part = xlsx::sheet_part(&workbook, &relationships, name)?;
+        let sheet = xlsx::read_part(&mut package, &part)?;
+        xlsx::read_rows(&sheet, &shared_strings, on_row).with_context(|| format!("Failed to read sheet \"{}\"", name))
+    };
+
+    let mut metadata = BTreeMap::new();
+    read_sheet(METADATA_SHEET, &mut |_, cells| {
+        if let (Some(key), Some(value)) = (cells.get(&0), cells.get(&1)) {
+            metadata.insert(key.clone(), value.clone());
+        }
+        Ok(())
+    })?;
+    let property = |key: &str| {
+        metadata
+            .get(key)
+            .map(String::as_str)
+            .ok_or_else(|| anyhow!("The round-trip metadata has no \"{}\"", key))
+    };
+
+    if property(FORMAT_PROPERTY)? != FORMAT {
+        bail!("{} was not exported by this app", path.display());
+    }
+    let version: u32 = property(FORMAT_VERSION_PROPERTY)?.parse().context("Invalid format version")?;
+    if version > FORMAT_VERSION {
+        bail!("{} was exported by a newer version of the application", path.display());
+    }
+    let count: usize = property(RECORD_COUNT_PROPERTY)?.parse().context("Invalid record count")?;
+    let sheets: Vec<String> =
+        serde_json::from_str(property(RECORD_SHEETS_PROPERTY)?).context("Invalid record sheet list")?;
+
+    let mut records = Vec::with_capacity(count);
+    for sheet in &sheets {
+        let mut columns = None;
+        read_sheet(sheet, &mut |row, cells| {
+            let Some(columns) = &columns else {
+                columns = Some(record_columns(cells)?);
+                return Ok(());
+            };
+            records.push(parse_record(cells, columns).with_context(|| format!("Invalid record in row {}", row + 1))?);
+            Ok(())
+        })?;
+    }
+
+    if records.len() != count {
+        bail!("{} holds {} records, but its metadata says {}", path.display(), records.len(), count);
+    }
+    Ok(records)
+}
+
+/// Column index of each `RECORD_COLUMNS` entry, from a record sheet's header row.
+fn record_columns(header: &Row) -> Result<[u16; 4]> {
+    let mut columns = [0; 4];
+    for (index, name) in RECORD_COLUMNS.iter().enumerate() {
+        columns[index] = header
+            .iter()
+            .find(|(_, text)| text == name)
+            .map(|(col, _)| *col)
+            .ok_or_else(|| anyhow!("A record sheet has no {} column", name))?;
+    }
+    Ok(columns)
+}
+
+fn parse_record(cells: &Row, [id, name, value, date]: &[u16; 4]) -> Result<TableData> {
+    let cell = |col: &u16| cells.get(col).map(String::as_str).unwrap_or_default();
+    Ok(TableData {
+        id: cell(id).parse().context("Invalid ID")?,
+        name: cell(name).to_string(),
+        value: cell(value).parse().context("Invalid value")?,
+        date: DateTime::parse_from_rfc3339(cell(date)).context("Invalid date")?.with_timezone(&Local),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::{self, TempDir};
+    use super::super::{ExcelExporter, ExportOptions};
+    use super::*;
+
+    #[test]
+    fn exported_records_import_unchanged() {
+        let dir = TempDir::new();
+        let path = dir.join("records.xlsx");
+        let mut data = testing::records();
+        data.push(testing::record(4, "Überschuss – 東京 ✓", f64::NAN, (2024, 3, 1)));
+        data.push(testing::record(5, "_x000D_ literal", f64::INFINITY, (2024, 3, 2)));
+        data.push(testing::record(6, "line\r\nbreak\u{1}", f64::NEG_INFINITY, (2024, 3, 3)));
+
+        testing::export(&ExcelExporter::new(), &data, &ExportOptions::default(), &path).unwrap();
+        let imported = import_excel(&path).unwrap();
+
+        let mut package = ZipArchive::new(Cursor::new(fs::read(&path).unwrap())).unwrap();
+        let workbook = xlsx::read_part(&mut package, WORKBOOK_PART).unwrap();
+        let relationships = xlsx::read_part(&mut package, WORKBOOK_RELS_PART).unwrap();
+        let part = xlsx::sheet_part(&workbook, &relationships, RECORDS_SHEET).unwrap();
+        assert!(xlsx::read_part(&mut package, &part).unwrap().contains("_x005F_x000D_ literal"));
+
+        assert_eq!(imported.len(), data.len());
+        for (imported, item) in imported.iter().zip(&data) {
+            assert_eq!(imported.id, item.id);
+            assert_eq!(imported.name, item.name);
+            assert_eq!(imported.value.to_bits(), item.value.to_bits(), "{}", item.name);
+            assert_eq!(imported.date, item.date);
+        }
+    }
+
+    #[test]
+    fn masked_exports_have_no_round_trip_data() {
+        let dir = TempDir::new();
+        let path = dir.join("masked.xlsx");
+
+        testing::export_masked(&ExcelExporter::new(), &testing::records(), &ExportOptions::default(), &path).unwrap();
+
+        let error = import_excel(&path).unwrap_err().to_string();
+        assert!(error.contains("has no round-trip data"), "{}", error);
+    }
+}
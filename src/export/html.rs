@@ -0,0 +1,384 @@
+use super::{DATE_FORMAT_OPTION, ExportContext, Exporter, ExporterInfo, OptionKind, OptionSpec, date_format, report};
+use crate::data::{Field, TableData};
+use crate::locale::Locale;
+use anyhow::Result;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+const TITLE_OPTION: OptionSpec = OptionSpec {
+    key: "title",
+    label: "Title",
+    kind: OptionKind::Text,
+    default: "Data Report",
+    help: "Page title and heading",
+};
+
+const SCRIPT_OPTION: OptionSpec = OptionSpec {
+    key: "interactive",
+    label: "Sorting and filtering",
+    kind: OptionKind::Bool,
+    default: "true",
+    help: "Include a small inline script to sort and filter the table. Without it the file has no JavaScript",
+};
+
+const CHARTS_OPTION: OptionSpec = OptionSpec {
+    key: "charts",
+    label: "Charts",
+    kind: OptionKind::Bool,
+    default: "true",
+    help: "Add SVG charts of value over date and totals per name",
+};
+
+const CHART_WIDTH: f32 = 640.0;
+const CHART_HEIGHT: f32 = 240.0;
+/// Room for value labels on the left and date or name labels below.
+const CHART_LEFT: f32 = 64.0;
+const CHART_BOTTOM: f32 = 24.0;
+const CHART_TOP: f32 = 8.0;
+const MAX_LABEL_CHARS: usize = 12;
+
+const STYLE: &str = r#"
+body { font-family: system-ui, -apple-system, "Segoe UI", Roboto, sans-serif; margin: 2rem; color: #222; }
+h1 { margin-bottom: 0.2rem; }
+.meta { color: #666; margin-top: 0; }
+.stats { display: grid; grid-template-columns: repeat(auto-fill, minmax(9rem, 1fr)); gap: 0.6rem; margin: 0; }
+.stats div { background: #f3f5f8; border-radius: 6px; padding: 0.5rem 0.8rem; }
+.stats dt { font-size: 0.8rem; color: #666; }
+.stats dd { margin: 0; font-size: 1.2rem; font-weight: 600; }
+.charts { display: flex; flex-wrap: wrap; gap: 1.5rem; }
+.charts figure { margin: 0; }
+.charts svg { width: 100%; max-width: 640px; height: auto; }
+.axis { stroke: #888; stroke-width: 1; fill: none; }
+.line { stroke: #3366cc; stroke-width: 1.5; fill: none; }
+.bar { fill: #4d9966; }
+svg text { font-size: 11px; fill: #555; }
+#filter { margin: 0.5rem 0; padding: 0.3rem 0.5rem; width: 20rem; max-width: 100%; }
+table { border-collapse: collapse; width: 100%; }
+th, td { padding: 0.3rem 0.6rem; border-bottom: 1px solid #ddd; text-align: left; }
+th { background: #e4e4e4; position: sticky; top: 0; }
+th.number, td.number { text-align: right; }
+tbody tr:nth-child(even) { background: #f7f7f7; }
+.sortable th { cursor: pointer; user-select: none; }
+.sortable th[aria-sort=ascending]::after { content: " \25B2"; }
+.sortable th[aria-sort=descending]::after { content: " \25BC"; }
+"#;
+
+const SCRIPT: &str = r#"
+(function () {
+  var table = document.getElementById('data');
+  var body = table.tBodies[0];
+  var rows = Array.prototype.slice.call(body.rows);
+  var filter = document.getElementById('filter');
+  var count = document.getElementById('count');
+  table.classList.add('sortable');
+  filter.hidden = false;
+
+  filter.addEventListener('input', function () {
+    var needle = filter.value.toLowerCase();
+    var shown = 0;
+    rows.forEach(function (row) {
+      var match = row.textContent.toLowerCase().indexOf(needle) !== -1;
+      row.hidden = !match;
+      if (match) shown++;
+    });
+    count.textContent = shown + ' of ' + rows.length + ' rows';
+  });
+
+  Array.prototype.forEach.call(table.tHead.rows[0].cells, function (th, index) {
+    th.addEventListener('click', function () {
+      var numeric = th.classList.contains('number');
+      var ascending = th.getAttribute('aria-sort') !== 'ascending';
+      Array.prototype.forEach.call(th.parentNode.cells, function (cell) { cell.removeAttribute('aria-sort'); });
+      th.setAttribute('aria-sort', ascending ? 'ascending' : 'descending');
+      // data-sort holds a number: the raw value, or a date in epoch milliseconds.
+      var key = function (row) {
+        var cell = row.cells[index];
+        var sort = cell.getAttribute('data-sort');
+        if (sort !== null) return parseFloat(sort);
+        return numeric ? parseFloat(cell.textContent) : cell.textContent.toLowerCase();
+      };
+      rows.sort(function (a, b) {
+        var x = key(a), y = key(b);
+        return (x < y ? -1 : x > y ? 1 : 0) * (ascending ? 1 : -1);
+      });
+      rows.forEach(function (row) { body.appendChild(row); });
+    });
+  });
+})();
+"#;
+
+/// Writes a single HTML file with everything inline, so it opens offline in any browser.
+pub struct HtmlExporter;
+
+impl HtmlExporter {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Exporter for HtmlExporter {
+    fn info(&self) -> ExporterInfo {
+        ExporterInfo {
+            id: "html",
+            name: "HTML report",
+            extension: "html",
+            options: vec![TITLE_OPTION, SCRIPT_OPTION, CHARTS_OPTION, DATE_FORMAT_OPTION],
+        }
+    }
+
+    fn export(&self, context: &ExportContext, path: &Path) -> Result<()> {
+        let options = context.options;
+        let title = escape(options.value(&TITLE_OPTION)?);
+        let interactive = options.flag(&SCRIPT_OPTION)?;
+        let date_format = date_format(options, context.locale)?;
+        let mut out = BufWriter::new(File::create(path)?);
+
+        writeln!(out, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>")?;
+        writeln!(out, "<meta charset=\"utf-8\">")?;
+        writeln!(out, "<meta name=\"viewport\" content=\"width=device-width, initial-scale=1\">")?;
+        writeln!(out, "<meta name=\"generator\" content=\"{} {}\">", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION"))?;
+        writeln!(out, "<title>{}</title>\n<style>{}</style>\n</head>\n<body>", title, STYLE)?;
+        writeln!(out, "<h1>{}</h1>", title)?;
+        writeln!(
+            out,
+            "<p class=\"meta\">Generated {} &middot; {}</p>",
+            context.locale.format_date_time(&chrono::Local::now()),
+            escape(context.filter)
+        )?;
+
+        writeln!(out, "<h2>Summary</h2>\n<dl class=\"stats\">")?;
+        for (label, value) in report::summary_figures(context, &date_format) {
+            writeln!(out, "<div><dt>{}</dt><dd>{}</dd></div>", label, escape(&value))?;
+        }
+        writeln!(out, "</dl>")?;
+
+        if options.flag(&CHARTS_OPTION)? && !context.data.is_empty() {
+            let has = |field| report::has_column(context, field);
+            let line = has(Field::Date) && has(Field::Value);
+            let bar = has(Field::Name) && has(Field::Value);
+            if line || bar {
+                writeln!(out, "<h2>Charts</h2>\n<div class=\"charts\">")?;
+                if line {
+                    write_line_chart(&mut out, context.data, context.locale)?;
+                }
+                if bar {
+                    write_bar_chart(&mut out, context.data, context.locale)?;
+                }
+                writeln!(out, "</div>")?;
+            }
+        }
+
+        write_table(&mut out, context, &date_format, interactive)?;
+        if interactive {
+            writeln!(out, "<script>{}</script>", SCRIPT)?;
+        }
+        writeln!(out, "</body>\n</html>")?;
+        out.flush()?;
+        Ok(())
+    }
+}
+
+fn write_table(out: &mut impl Write, context: &ExportContext, date_format: &str, interactive: bool) -> Result<()> {
+    writeln!(out, "<h2>Data</h2>")?;
+    if interactive {
+        writeln!(out, "<input id=\"filter\" type=\"search\" placeholder=\"Filter rows...\" hidden>")?;
+    }
+    writeln!(out, "<p id=\"count\" class=\"meta\">{} rows</p>", context.data.len())?;
+
+    writeln!(out, "<table id=\"data\">\n<thead><tr>")?;
+    for column in context.columns {
+        writeln!(out, "<th{}>{}</th>", numeric_class(column.field), escape(&column.header))?;
+    }
+    writeln!(out, "</tr></thead>\n<tbody>")?;
+
+    for (index, item) in context.data.iter().enumerate() {
+        write!(out, "<tr>")?;
+        for column in context.columns {
+            match column.field {
+                Field::Id => write!(out, "<td class=\"number\">{}</td>", item.id)?,
+                Field::Name => write!(out, "<td>{}</td>", escape(&item.name))?,
+                Field::Value => write!(
+                    out,
+                    "<td class=\"number\" data-sort=\"{}\">{}</td>",
+                    item.value,
+                    context.locale.format_number(item.value, 2)
+                )?,
+                Field::Date => write!(
+                    out,
+                    "<td data-sort=\"{}\">{}</td>",
+                    item.date.timestamp_millis(),
+                    escape(&item.date.format(date_format).to_string())
+                )?,
+            }
+        }
+        writeln!(out, "</tr>")?;
+        context.progress.set(index + 1)?;
+    }
+    writeln!(out, "</tbody>\n</table>")?;
+    Ok(())
+}
+
+fn numeric_class(field: Field) -> &'static str {
+    match field {
+        Field::Id | Field::Value => " class=\"number\"",
+        Field::Name | Field::Date => "",
+    }
+}
+
+/// Plot area of a chart as left, top, width and height in SVG units.
+fn plot_area() -> (f32, f32, f32, f32) {
+    (CHART_LEFT, CHART_TOP, CHART_WIDTH - CHART_LEFT - 8.0, CHART_HEIGHT - CHART_TOP - CHART_BOTTOM)
+}
+
+fn write_chart_start(out: &mut impl Write, title: &str, min: f64, max: f64, locale: Locale) -> Result<()> {
+    let (left, top, width, height) = plot_area();
+    writeln!(out, "<figure>\n<figcaption><strong>{}</strong></figcaption>", escape(title))?;
+    writeln!(
+        out,
+        "<svg viewBox=\"0 0 {} {}\" role=\"img\" aria-label=\"{}\">",
+        CHART_WIDTH,
+        CHART_HEIGHT,
+        escape(title)
+    )?;
+    writeln!(
+        out,
+        "<polyline class=\"axis\" points=\"{l},{t} {l},{b} {r},{b}\"/>",
+        l = left,
+        t = top,
+        b = top + height,
+        r = left + width
+    )?;
+    writeln!(out, "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>", left - 4.0, top + 8.0, locale.format_number(max, 2))?;
+    writeln!(out, "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>", left - 4.0, top + height, locale.format_number(min, 2))?;
+    Ok(())
+}
+
+fn write_line_chart(out: &mut impl Write, data: &[TableData], locale: Locale) -> Result<()> {
+    let points = report::value_over_time(data);
+    let (first, last) = (points[0].0, points[points.len() - 1].0);
+    let (min, max) = report::value_bounds(points.iter().map(|&(_, value)| value));
+    let (left, top, width, height) = plot_area();
+    let y = |value: f64| top + height * (1.0 - ((value - min) / (max - min)) as f32);
+
+    write_chart_start(out, "Value over time", min, max, locale)?;
+    write!(out, "<polyline class=\"line\" points=\"")?;
+    for (date, value) in &points {
+        write!(out, "{:.1},{:.1} ", left + width * report::time_fraction(*date, first, last), y(*value))?;
+    }
+    writeln!(out, "\"/>")?;
+
+    let label_y = top + height + 16.0;
+    writeln!(out, "<text x=\"{}\" y=\"{}\">{}</text>", left, label_y, locale.format_date(&first))?;
+    writeln!(
+        out,
+        "<text x=\"{}\" y=\"{}\" text-anchor=\"end\">{}</text>",
+        left + width,
+        label_y,
+        locale.format_date(&last)
+    )?;
+    writeln!(out, "</svg>\n</figure>")?;
+    Ok(())
+}
+
+fn write_bar_chart(out: &mut impl Write, data: &[TableData], locale: Locale) -> Result<()> {
+    let (title, totals) = report::totals_per_name(data);
+    let (min, max) = report::value_bounds(totals.iter().map(|&(_, total)| total).chain([0.0]));
+    let (left, top, width, height) = plot_area();
+    let y = |value: f64| top + height * (1.0 - ((value - min) / (max - min)) as f32);
+    let slot = width / totals.len() as f32;
+
+    write_chart_start(out, &title, min, max, locale)?;
+    for (index, (name, total)) in totals.iter().enumerate() {
+        let x = left + slot * index as f32 + slot * 0.15;
+        let (bar_top, base) = (y(*total), y(0.0));
+        writeln!(
+            out,
+            "<rect class=\"bar\" x=\"{:.1}\" y=\"{:.1}\" width=\"{:.1}\" height=\"{:.1}\"><title>{}: {}</title></rect>",
+            x,
+            bar_top.min(base),
+            slot * 0.7,
+            (bar_top - base).abs(),
+            escape(name),
+            locale.format_number(*total, 2)
+        )?;
+        let label: String = if name.chars().count() > MAX_LABEL_CHARS {
+            format!("{}...", name.chars().take(MAX_LABEL_CHARS - 3).collect::<String>())
+        } else {
+            name.to_string()
+        };
+        writeln!(
+            out,
+            "<text x=\"{:.1}\" y=\"{}\" text-anchor=\"middle\">{}</text>",
+            x + slot * 0.35,
+            top + height + 16.0,
+            escape(&label)
+        )?;
+    }
+    writeln!(out, "</svg>\n</figure>")?;
+    Ok(())
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::{self, TempDir};
+    use super::super::ExportOptions;
+    use super::*;
+
+    fn write(data: &[TableData], options: &ExportOptions, path: &Path) -> String {
+        testing::export(&HtmlExporter::new(), data, options, path).unwrap();
+        std::fs::read_to_string(path).unwrap()
+    }
+
+    #[test]
+    fn reports_hold_every_row_with_sort_keys_and_escaped_text() {
+        let dir = TempDir::new();
+        let mut data = testing::records();
+        data.push(testing::record(4, "<script>alert(\"x\")</script> & Co", 7.0, (2024, 3, 1)));
+
+        let html = write(&data, &ExportOptions::default(), &dir.join("report.html"));
+
+        assert!(html.contains("<title>Data Report</title>"));
+        assert!(html.contains("<p id=\"count\" class=\"meta\">4 rows</p>"));
+        assert_eq!(html.matches("<tr><td class=\"number\">").count(), 4);
+        assert!(html.contains("<td class=\"number\" data-sort=\"-3.25\">-3.25</td>"), "{}", html);
+        let millis = data[0].date.timestamp_millis();
+        assert!(html.contains(&format!("<td data-sort=\"{}\">", millis)));
+        assert!(html.contains("<td>&lt;script&gt;alert(&quot;x&quot;)&lt;/script&gt; &amp; Co</td>"));
+        assert!(!html.contains("<script>alert"));
+
+        assert_eq!(html.matches("<svg ").count(), 2, "line and bar chart");
+        assert_eq!(html.matches("<script>").count(), 1);
+        // Nothing is loaded from elsewhere.
+        assert!(!html.contains(" src=") && !html.contains("<link"));
+    }
+
+    #[test]
+    fn script_and_charts_can_be_left_out() {
+        let dir = TempDir::new();
+        let mut options = ExportOptions::default();
+        options.set(SCRIPT_OPTION.key, "false");
+        options.set(CHARTS_OPTION.key, "false");
+
+        let html = write(&testing::records(), &options, &dir.join("static.html"));
+
+        assert!(!html.contains("<script>") && !html.contains("id=\"filter\""));
+        assert!(!html.contains("<svg "));
+        assert!(html.contains("<h2>Summary</h2>") && html.contains("</table>"));
+    }
+}
@@ -0,0 +1,162 @@
+use super::{
+    DATE_FORMAT_OPTION, ExportColumn, ExportContext, Exporter, ExporterInfo, LINE_ENDING_OPTION, LineEnding, OptionKind,
+    OptionSpec,
+};
+use crate::data::{Field, TableData};
+use anyhow::{Result, bail};
+use serde_json::{Map, Value, json};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+const PRETTY_OPTION: OptionSpec = OptionSpec {
+    key: "pretty",
+    label: "Pretty print",
+    kind: OptionKind::Bool,
+    default: "true",
+    help: "Indent the JSON array for readability",
+};
+
+/// JSON is read by programs, so dates are RFC 3339 whatever the regional format.
+const JSON_DATE_FORMAT_OPTION: OptionSpec = OptionSpec {
+    help: "chrono format string, e.g. %Y-%m-%d %H:%M; empty writes RFC 3339 (ISO 8601) with the UTC offset",
+    ..DATE_FORMAT_OPTION
+};
+
+/// Writes records as a JSON array, or as JSON Lines with one object per line.
+pub struct JsonExporter {
+    lines: bool,
+}
+
+impl JsonExporter {
+    pub fn json() -> Self {
+        Self { lines: false }
+    }
+
+    pub fn json_lines() -> Self {
+        Self { lines: true }
+    }
+}
+
+impl Exporter for JsonExporter {
+    fn info(&self) -> ExporterInfo {
+        if self.lines {
+            ExporterInfo {
+                id: "jsonl",
+                name: "JSON Lines",
+                extension: "jsonl",
+                options: vec![LINE_ENDING_OPTION, JSON_DATE_FORMAT_OPTION],
+            }
+        } else {
+            ExporterInfo {
+                id: "json",
+                name: "JSON",
+                extension: "json",
+                options: vec![PRETTY_OPTION, LINE_ENDING_OPTION, JSON_DATE_FORMAT_OPTION],
+            }
+        }
+    }
+
+    fn export(&self, context: &ExportContext, path: &Path) -> Result<()> {
+        // Each header becomes a key of every object, so a repeated one would
+        // silently replace the other column's value.
+        for (index, column) in context.columns.iter().enumerate() {
+            if context.columns[..index].iter().any(|other| other.header == column.header) {
+                bail!("The header \"{}\" is used for more than one column; JSON keys must be unique", column.header);
+            }
+        }
+        let pretty = context.options.flag(&PRETTY_OPTION)?;
+        let line_ending = LineEnding::from_options(context.options)?;
+        let date_format = match context.options.value(&JSON_DATE_FORMAT_OPTION)?.trim() {
+            "" => None,
+            format if chrono::format::StrftimeItems::new(format).any(|item| item == chrono::format::Item::Error) => {
+                bail!("Invalid date format \"{}\"", format)
+            }
+            format => Some(format),
+        };
+        let mut writer = BufWriter::new(File::create(path)?);
+        let records = context.data.iter().enumerate().map(|(index, item)| {
+            context.progress.set(index + 1)?;
+            Ok(to_json(item, context.columns, date_format))
+        });
+
+        if self.lines {
+            for record in records {
+                serde_json::to_writer(&mut writer, &record?)?;
+                writer.write_all(line_ending.as_str().as_bytes())?;
+            }
+        } else {
+            let records = records.collect::<Result<Vec<_>>>()?;
+            if pretty {
+                serde_json::to_writer_pretty(&mut writer, &records)?;
+            } else {
+                serde_json::to_writer(&mut writer, &records)?;
+            }
+            writer.write_all(line_ending.as_str().as_bytes())?;
+        }
+
+        writer.flush()?;
+        Ok(())
+    }
+}
+
+/// Builds one object keyed by the column headers, in column order. Dates are
+/// RFC 3339 unless a `chrono` format is given.
+fn to_json(item: &TableData, columns: &[ExportColumn], date_format: Option<&str>) -> Value {
+    let mut object = Map::new();
+    for column in columns {
+        let value = match column.field {
+            Field::Id => json!(item.id),
+            Field::Name => json!(item.name),
+            Field::Value => json!(item.value),
+            Field::Date => json!(match date_format {
+                Some(format) => item.date.format(format).to_string(),
+                None => item.date.to_rfc3339(),
+            }),
+        };
+        object.insert(column.header.clone(), value);
+    }
+    Value::Object(object)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::{self, TempDir};
+    use super::super::{ExportColumn, ExportOptions};
+    use super::*;
+
+    #[test]
+    fn json_and_json_lines_hold_every_record() {
+        let dir = TempDir::new();
+        let data = testing::records();
+        let (json_path, lines_path) = (dir.join("records.json"), dir.join("records.jsonl"));
+        let mut options = ExportOptions::default();
+        options.set("line_ending", "lf");
+        testing::export(&JsonExporter::json(), &data, &options, &json_path).unwrap();
+        testing::export(&JsonExporter::json_lines(), &data, &options, &lines_path).unwrap();
+
+        let array: Vec<Value> = serde_json::from_str(&std::fs::read_to_string(&json_path).unwrap()).unwrap();
+        let lines = std::fs::read_to_string(&lines_path).unwrap();
+        let objects: Vec<Value> = lines.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert!(!lines.contains('\r'));
+        assert_eq!(array, objects);
+        for (object, item) in array.iter().zip(&data) {
+            assert_eq!(object["ID"], json!(item.id));
+            assert_eq!(object["Name"], json!(item.name));
+            assert_eq!(object["Value"], json!(item.value));
+            assert_eq!(object["Date"], json!(item.date.to_rfc3339()));
+        }
+    }
+
+    #[test]
+    fn duplicate_headers_are_rejected() {
+        let dir = TempDir::new();
+        let mut columns = ExportColumn::all();
+        columns[1].header = "Value".to_string();
+
+        let path = dir.join("records.json");
+        let error = testing::export_columns(&JsonExporter::json(), &testing::records(), &columns, &ExportOptions::default(), &path)
+            .unwrap_err();
+        assert!(error.to_string().contains("\"Value\" is used for more than one column"), "{}", error);
+    }
+}
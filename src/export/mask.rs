@@ -0,0 +1,263 @@
+//! Masking of exported records, for sharing datasets without revealing them.
+//!
+//! Rules are applied to the copy of the records handed to an exporter, never
+//! to the `DataStore`.
+
+use crate::data::{Field, TableData};
+use chrono::{Datelike, Local, TimeZone};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, BTreeSet};
+
+const REDACTED: &str = "[redacted]";
+
+const ADJECTIVES: [&str; 32] = [
+    "Amber", "Bold", "Brave", "Bright", "Calm", "Clever", "Coral", "Crimson", "Eager", "Gentle", "Golden", "Happy",
+    "Hidden", "Honest", "Jolly", "Kind", "Lively", "Lucky", "Mellow", "Misty", "Noble", "Olive", "Quiet", "Rapid",
+    "Silver", "Sly", "Steady", "Sunny", "Swift", "Tidy", "Vivid", "Witty",
+];
+
+const ANIMALS: [&str; 32] = [
+    "Badger", "Bear", "Beaver", "Bison", "Crane", "Deer", "Dolphin", "Eagle", "Falcon", "Ferret", "Fox", "Gecko",
+    "Hare", "Heron", "Ibis", "Jaguar", "Koala", "Lynx", "Marten", "Moose", "Otter", "Owl", "Panda", "Puffin", "Raven",
+    "Robin", "Seal", "Stork", "Swan", "Tiger", "Walrus", "Wolf",
+];
+
+/// How one column is masked.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum MaskRule {
+    #[default]
+    Keep,
+    /// Salted SHA-256 as hex; IDs get the first four bytes as a number.
+    Hash,
+    /// Readable stand-in such as "Swift Otter 4821", the same for the same
+    /// name and salt.
+    Pseudonym,
+    /// Replaced with a fixed placeholder.
+    Redact,
+    /// Randomly changed by up to the given percentage either way.
+    Noise(f64),
+    /// Moved to midnight on the first day of its month.
+    Month,
+}
+
+impl MaskRule {
+    /// Rules that make sense for a field, `Keep` first.
+    pub fn choices(field: Field) -> &'static [MaskRule] {
+        match field {
+            Field::Id => &[MaskRule::Keep, MaskRule::Hash],
+            Field::Name => &[MaskRule::Keep, MaskRule::Hash, MaskRule::Pseudonym, MaskRule::Redact],
+            Field::Value => &[MaskRule::Keep, MaskRule::Noise(10.0)],
+            Field::Date => &[MaskRule::Keep, MaskRule::Month],
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            MaskRule::Keep => "Unmasked",
+            MaskRule::Hash => "Salted hash",
+            MaskRule::Pseudonym => "Pseudonym",
+            MaskRule::Redact => "Redacted",
+            MaskRule::Noise(_) => "Random noise",
+            MaskRule::Month => "Month only",
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            MaskRule::Noise(percent) => format!("±{}% noise", percent),
+            other => other.label().to_lowercase(),
+        }
+    }
+
+    /// Whether the result depends on the salt.
+    pub fn uses_salt(&self) -> bool {
+        matches!(self, MaskRule::Hash | MaskRule::Pseudonym)
+    }
+}
+
+/// Masking rules for the exported columns.
+#[derive(Debug, Clone, Default)]
+pub struct Masking {
+    pub salt: String,
+    pub rules: Vec<(Field, MaskRule)>,
+}
+
+impl Masking {
+    fn active_rules(&self) -> impl Iterator<Item = &(Field, MaskRule)> {
+        self.rules.iter().filter(|(_, rule)| *rule != MaskRule::Keep)
+    }
+
+    /// Summary for export metadata, e.g. "Name pseudonym, Value ±5% noise";
+    /// `None` when nothing is masked.
+    pub fn describe(&self) -> Option<String> {
+        let rules: Vec<String> = self
+            .active_rules()
+            .map(|(field, rule)| format!("{} {}", field.label(), rule.describe()))
+            .collect();
+        (!rules.is_empty()).then(|| rules.join(", "))
+    }
+
+    /// Masks the given records, which must be a copy of the stored ones.
+    pub fn apply(&self, mut data: Vec<TableData>) -> Vec<TableData> {
+        for &(field, rule) in self.active_rules() {
+            match (field, rule) {
+                (Field::Id, MaskRule::Hash) => {
+                    for item in &mut data {
+                        let digest = self.digest(&item.id.to_string());
+                        item.id = u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]);
+                    }
+                }
+                (Field::Name, MaskRule::Hash) => {
+                    for item in &mut data {
+                        item.name = hex(&self.digest(&item.name));
+                    }
+                }
+                (Field::Name, MaskRule::Pseudonym) => {
+                    let pseudonyms = self.pseudonyms(&data);
+                    for item in &mut data {
+                        item.name = pseudonyms[&item.name].clone();
+                    }
+                }
+                (Field::Name, MaskRule::Redact) => {
+                    for item in &mut data {
+                        item.name = REDACTED.to_string();
+                    }
+                }
+                (Field::Value, MaskRule::Noise(percent)) => {
+                    let spread = percent.abs() / 100.0;
+                    for item in &mut data {
+                        item.value *= 1.0 + spread * (fastrand::f64() * 2.0 - 1.0);
+                    }
+                }
+                (Field::Date, MaskRule::Month) => {
+                    for item in &mut data {
+                        let month = item.date.date_naive().with_day(1).and_then(|day| day.and_hms_opt(0, 0, 0));
+                        if let Some(start) = month.and_then(|month| Local.from_local_datetime(&month).earliest()) {
+                            item.date = start;
+                        }
+                    }
+                }
+                _ => {}
+            }
+        }
+        data
+    }
+
+    fn digest(&self, text: &str) -> [u8; 32] {
+        let mut hasher = Sha256::new();
+        hasher.update(self.salt.as_bytes());
+        hasher.update([0]);
+        hasher.update(text.as_bytes());
+        hasher.finalize().into()
+    }
+
+    /// Pseudonym for every distinct name. Names are taken in sorted order so a
+    /// rare clash is numbered the same way in every export of the same names.
+    fn pseudonyms(&self, data: &[TableData]) -> BTreeMap<String, String> {
+        let names: BTreeSet<&str> = data.iter().map(|item| item.name.as_str()).collect();
+        let mut taken = BTreeSet::new();
+        let mut pseudonyms = BTreeMap::new();
+        for name in names {
+            let digest = self.digest(name);
+            let number = u16::from_be_bytes([digest[2], digest[3]]) % 10_000;
+            let base = format!(
+                "{} {} {:04}",
+                ADJECTIVES[usize::from(digest[0]) % ADJECTIVES.len()],
+                ANIMALS[usize::from(digest[1]) % ANIMALS.len()],
+                number
+            );
+            let pseudonym = (1..)
+                .map(|n| if n == 1 { base.clone() } else { format!("{} ({})", base, n) })
+                .find(|candidate| !taken.contains(candidate))
+                .expect("unbounded range always yields a free name");
+            taken.insert(pseudonym.clone());
+            pseudonyms.insert(name.to_string(), pseudonym);
+        }
+        pseudonyms
+    }
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// Random salt for new export profiles.
+pub fn new_salt() -> String {
+    format!("{:016x}{:016x}", fastrand::u64(..), fastrand::u64(..))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing;
+    use super::*;
+    use crate::data::DataStore;
+    use chrono::Timelike;
+
+    fn masking(salt: &str, rules: &[(Field, MaskRule)]) -> Masking {
+        Masking {
+            salt: salt.to_string(),
+            rules: rules.to_vec(),
+        }
+    }
+
+    #[test]
+    fn the_same_salt_gives_the_same_hashes_and_pseudonyms() {
+        let hashed = [(Field::Id, MaskRule::Hash), (Field::Name, MaskRule::Hash)];
+        let pseudonymised = [(Field::Name, MaskRule::Pseudonym)];
+        let names = |data: Vec<TableData>| data.into_iter().map(|item| (item.id, item.name)).collect::<Vec<_>>();
+
+        for rules in [&hashed[..], &pseudonymised[..]] {
+            let first = names(masking("salt", rules).apply(testing::records()));
+            let second = names(masking("salt", rules).apply(testing::records()));
+            let other = names(masking("pepper", rules).apply(testing::records()));
+            assert_eq!(first, second);
+            assert_ne!(first, other);
+            // Records with the same name keep sharing it.
+            assert_eq!(first[0].1, first[2].1);
+            assert_ne!(first[0].1, first[1].1);
+            assert!(first.iter().all(|(_, name)| name != "Alpha" && name != "Beta"));
+        }
+    }
+
+    #[test]
+    fn noise_stays_within_the_percentage() {
+        let data: Vec<TableData> = (0..1000).map(|id| testing::record(id, "Alpha", 200.0, (2024, 1, 15))).collect();
+        let masked = masking("", &[(Field::Value, MaskRule::Noise(5.0))]).apply(data);
+
+        assert!(masked.iter().all(|item| (190.0..=210.0).contains(&item.value)));
+        assert!(masked.iter().any(|item| item.value != 200.0));
+    }
+
+    #[test]
+    fn month_moves_dates_to_midnight_on_the_first() {
+        let masked = masking("", &[(Field::Date, MaskRule::Month)]).apply(testing::records());
+
+        for (item, original) in masked.iter().zip(testing::records()) {
+            assert_eq!((item.date.year(), item.date.month()), (original.date.year(), original.date.month()));
+            assert_eq!((item.date.day(), item.date.hour(), item.date.minute()), (1, 0, 0));
+        }
+    }
+
+    #[test]
+    fn applying_leaves_the_store_untouched() {
+        let mut store = DataStore::new();
+        store.replace_data(testing::records());
+        let rules = [
+            (Field::Id, MaskRule::Hash),
+            (Field::Name, MaskRule::Redact),
+            (Field::Value, MaskRule::Noise(50.0)),
+            (Field::Date, MaskRule::Month),
+        ];
+
+        let masked = masking("salt", &rules).apply(store.get_all_data().clone());
+
+        assert!(masked.iter().all(|item| item.name == REDACTED));
+        for (stored, original) in store.get_all_data().iter().zip(testing::records()) {
+            assert_eq!(stored.id, original.id);
+            assert_eq!(stored.name, original.name);
+            assert_eq!(stored.value, original.value);
+            assert_eq!(stored.date, original.date);
+        }
+    }
+}
@@ -0,0 +1,572 @@
+mod columnar;
+mod delimited;
+mod excel;
+mod excel_import;
+mod html;
+mod json;
+mod mask;
+mod pdf;
+mod report;
+mod sqlite;
+mod template;
+mod xlsx;
+
+pub use columnar::ColumnarExporter;
+pub use delimited::DelimitedExporter;
+pub use excel::ExcelExporter;
+pub use excel_import::import_excel;
+pub use html::HtmlExporter;
+pub use json::JsonExporter;
+pub use mask::{MaskRule, Masking, new_salt};
+pub use pdf::PdfExporter;
+pub use sqlite::SqliteExporter;
+
+use crate::data::{Field, TableData};
+use crate::locale::Locale;
+use anyhow::{Result, bail};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+#[derive(Debug, Clone)]
+pub enum OptionKind {
+    Text,
+    Bool,
+    Choice(&'static [&'static str]),
+}
+
+/// Describes one configurable option of an exporter.
+#[derive(Debug, Clone)]
+pub struct OptionSpec {
+    pub key: &'static str,
+    pub label: &'static str,
+    pub kind: OptionKind,
+    pub default: &'static str,
+    pub help: &'static str,
+}
+
+#[derive(Debug, Clone)]
+pub struct ExporterInfo {
+    /// Stable identifier used in settings and on the command line.
+    pub id: &'static str,
+    pub name: &'static str,
+    pub extension: &'static str,
+    pub options: Vec<OptionSpec>,
+}
+
+/// Option values keyed by `OptionSpec::key`. Missing keys fall back to the spec default.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ExportOptions(BTreeMap<String, String>);
+
+impl ExportOptions {
+    pub fn get<'a>(&'a self, spec: &'a OptionSpec) -> &'a str {
+        self.0.get(spec.key).map(String::as_str).unwrap_or(spec.default)
+    }
+
+    pub fn set(&mut self, key: &str, value: impl Into<String>) {
+        self.0.insert(key.to_string(), value.into());
+    }
+
+    /// Looks up a value, checking it against the spec's allowed choices.
+    pub fn value<'a>(&'a self, spec: &'a OptionSpec) -> Result<&'a str> {
+        let value = self.get(spec);
+        if let OptionKind::Choice(choices) = spec.kind
+            && !choices.contains(&value)
+        {
+            bail!("Invalid {} \"{}\", expected one of: {}", spec.label, value, choices.join(", "));
+        }
+        Ok(value)
+    }
+
+    pub fn flag(&self, spec: &OptionSpec) -> Result<bool> {
+        match self.value(spec)? {
+            "true" => Ok(true),
+            "false" => Ok(false),
+            other => bail!("Invalid {} \"{}\", expected true or false", spec.label, other),
+        }
+    }
+}
+
+/// A column to export and the header label to write for it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ExportColumn {
+    pub field: Field,
+    pub header: String,
+}
+
+impl ExportColumn {
+    pub fn new(field: Field) -> Self {
+        Self {
+            field,
+            header: field.label().to_string(),
+        }
+    }
+
+    /// All fields in their natural order with default headers.
+    pub fn all() -> Vec<ExportColumn> {
+        Field::ALL.into_iter().map(Self::new).collect()
+    }
+}
+
+/// Everything an exporter needs to write one file.
+pub struct ExportContext<'a> {
+    pub data: &'a [TableData],
+    /// Columns to write, in order.
+    pub columns: &'a [ExportColumn],
+    pub options: &'a ExportOptions,
+    /// Which rows are included, e.g. the active filter, for report metadata.
+    pub filter: &'a str,
+    /// Regional format for dates and numbers that are written as text.
+    pub locale: Locale,
+    /// Whether `data` was masked, so it must not be presented as the exact records.
+    pub masked: bool,
+    pub progress: &'a ExportProgress,
+}
+
+/// Rows written so far, shared between a running export and whoever displays
+/// or cancels it.
+#[derive(Debug, Default)]
+pub struct ExportProgress {
+    total: AtomicUsize,
+    done: AtomicUsize,
+    cancelled: AtomicBool,
+}
+
+impl ExportProgress {
+    pub fn new(total: usize) -> Self {
+        Self {
+            total: AtomicUsize::new(total),
+            done: AtomicUsize::new(0),
+            cancelled: AtomicBool::new(false),
+        }
+    }
+
+    /// Records progress, failing once the export has been cancelled so the
+    /// exporter stops at its next update.
+    pub fn set(&self, done: usize) -> Result<()> {
+        self.done.store(done, Ordering::Relaxed);
+        if self.is_cancelled() {
+            bail!("Export cancelled");
+        }
+        Ok(())
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+    }
+
+    pub fn done(&self) -> usize {
+        self.done.load(Ordering::Relaxed)
+    }
+
+    /// Completed share between 0.0 and 1.0.
+    pub fn fraction(&self) -> f32 {
+        match self.total.load(Ordering::Relaxed) {
+            0 => 1.0,
+            total => (self.done() as f32 / total as f32).min(1.0),
+        }
+    }
+}
+
+pub trait Exporter: Send + Sync {
+    fn info(&self) -> ExporterInfo;
+
+    /// Whether the export adds to a file already at the path instead of replacing it.
+    fn appends(&self, _options: &ExportOptions) -> bool {
+        false
+    }
+
+    fn export(&self, context: &ExportContext, path: &Path) -> Result<()>;
+}
+
+/// All export formats known to the application, in menu order.
+pub struct ExportRegistry {
+    exporters: Vec<Arc<dyn Exporter>>,
+}
+
+impl ExportRegistry {
+    pub fn new() -> Self {
+        Self { exporters: Vec::new() }
+    }
+
+    /// Registry with the built-in formats. Register additional formats here.
+    pub fn with_builtin() -> Self {
+        let mut registry = Self::new();
+        registry.register(Box::new(ExcelExporter::new()));
+        registry.register(Box::new(DelimitedExporter::csv()));
+        registry.register(Box::new(DelimitedExporter::tsv()));
+        registry.register(Box::new(JsonExporter::json()));
+        registry.register(Box::new(JsonExporter::json_lines()));
+        registry.register(Box::new(ColumnarExporter::parquet()));
+        registry.register(Box::new(ColumnarExporter::arrow_ipc()));
+        registry.register(Box::new(SqliteExporter::new()));
+        registry.register(Box::new(PdfExporter::new()));
+        registry.register(Box::new(HtmlExporter::new()));
+        registry
+    }
+
+    pub fn register(&mut self, exporter: Box<dyn Exporter>) {
+        self.exporters.push(Arc::from(exporter));
+    }
+
+    pub fn get(&self, id: &str) -> Option<&dyn Exporter> {
+        self.exporters.iter().find(|exporter| exporter.info().id == id).map(|exporter| exporter.as_ref())
+    }
+
+    /// Shared handle to an exporter, for running it on another thread.
+    pub fn shared(&self, id: &str) -> Option<Arc<dyn Exporter>> {
+        self.exporters.iter().find(|exporter| exporter.info().id == id).cloned()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &dyn Exporter> {
+        self.exporters.iter().map(|exporter| exporter.as_ref())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Quoting {
+    /// Quote only fields containing the delimiter, quotes or line breaks.
+    Minimal,
+    /// Quote every field.
+    All,
+    /// Quote every field except numbers.
+    NonNumeric,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LineEnding {
+    Lf,
+    CrLf,
+}
+
+impl LineEnding {
+    fn from_options(options: &ExportOptions) -> Result<Self> {
+        Ok(match options.value(&LINE_ENDING_OPTION)? {
+            "lf" => LineEnding::Lf,
+            _ => LineEnding::CrLf,
+        })
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            LineEnding::Lf => "\n",
+            LineEnding::CrLf => "\r\n",
+        }
+    }
+}
+
+const QUOTING_OPTION: OptionSpec = OptionSpec {
+    key: "quoting",
+    label: "Quoting",
+    kind: OptionKind::Choice(&["minimal", "all", "non-numeric"]),
+    default: "minimal",
+    help: "Which fields are wrapped in double quotes",
+};
+
+const LINE_ENDING_OPTION: OptionSpec = OptionSpec {
+    key: "line_ending",
+    label: "Line endings",
+    kind: OptionKind::Choice(&["crlf", "lf"]),
+    default: "crlf",
+    help: "CRLF for Windows tools, LF for Unix tools",
+};
+
+const DATE_FORMAT_OPTION: OptionSpec = OptionSpec {
+    key: "date_format",
+    label: "Date format",
+    kind: OptionKind::Text,
+    default: "",
+    help: "chrono format string, e.g. %Y-%m-%d or %d.%m.%Y %H:%M; empty uses the regional format from Settings",
+};
+
+/// Options shared by the CSV, TSV and JSON exporters.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextExportOptions {
+    pub delimiter: char,
+    pub quoting: Quoting,
+    pub line_ending: LineEnding,
+    /// `chrono` format string used for the `date` column.
+    pub date_format: String,
+    /// Decimal separator of values and the default date format.
+    pub locale: Locale,
+}
+
+impl TextExportOptions {
+    fn from_options(options: &ExportOptions, delimiter: char, locale: Locale) -> Result<Self> {
+        let quoting = match options.value(&QUOTING_OPTION)? {
+            "all" => Quoting::All,
+            "non-numeric" => Quoting::NonNumeric,
+            _ => Quoting::Minimal,
+        };
+        let line_ending = LineEnding::from_options(options)?;
+        let date_format = date_format(options, locale)?;
+
+        Ok(Self {
+            delimiter,
+            quoting,
+            line_ending,
+            date_format,
+            locale,
+        })
+    }
+}
+
+/// The `chrono` date format from `DATE_FORMAT_OPTION`, or the locale's when it
+/// is empty, checked for invalid specifiers.
+fn date_format(options: &ExportOptions, locale: Locale) -> Result<String> {
+    let date_format = match options.value(&DATE_FORMAT_OPTION)?.trim() {
+        "" => locale.date_time_format().to_string(),
+        format => format.to_string(),
+    };
+    if chrono::format::StrftimeItems::new(&date_format).any(|item| item == chrono::format::Item::Error) {
+        bail!("Invalid date format \"{}\"", date_format);
+    }
+    Ok(date_format)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ExportScope {
+    All,
+    /// Rows matching the active filter.
+    Filtered,
+    /// Rows selected in the Data Table.
+    Selection,
+}
+
+impl ExportScope {
+    pub const ALL: [ExportScope; 3] = [ExportScope::All, ExportScope::Filtered, ExportScope::Selection];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            ExportScope::All => "All rows",
+            ExportScope::Filtered => "Filtered view",
+            ExportScope::Selection => "Selected rows",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColumnChoice {
+    pub column: ExportColumn,
+    pub enabled: bool,
+    #[serde(default)]
+    pub mask: MaskRule,
+}
+
+/// Where and what to export, as chosen in the export dialog.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportProfile {
+    pub directory: PathBuf,
+    /// File name without extension. Supports `{date}`, `{time}`, `{format}` and `{count}`.
+    pub filename_template: String,
+    pub columns: Vec<ColumnChoice>,
+    pub scope: ExportScope,
+    /// Salt for hashed and pseudonymised columns. Keeping it stable keeps
+    /// masked values comparable between exports.
+    pub mask_salt: String,
+}
+
+impl Default for ExportProfile {
+    fn default() -> Self {
+        Self {
+            directory: dirs::download_dir().unwrap_or_else(|| PathBuf::from(".")),
+            filename_template: "export_{date}_{time}".to_string(),
+            columns: ExportColumn::all()
+                .into_iter()
+                .map(|column| ColumnChoice {
+                    column,
+                    enabled: true,
+                    mask: MaskRule::Keep,
+                })
+                .collect(),
+            scope: ExportScope::All,
+            mask_salt: mask::new_salt(),
+        }
+    }
+}
+
+impl ExportProfile {
+    pub fn columns(&self) -> Vec<ExportColumn> {
+        self.columns
+            .iter()
+            .filter(|choice| choice.enabled)
+            .map(|choice| choice.column.clone())
+            .collect()
+    }
+
+    /// Masking rules of the enabled columns.
+    pub fn masking(&self) -> Masking {
+        Masking {
+            salt: self.mask_salt.clone(),
+            rules: self
+                .columns
+                .iter()
+                .filter(|choice| choice.enabled)
+                .map(|choice| (choice.column.field, choice.mask))
+                .collect(),
+        }
+    }
+
+    pub fn file_path(&self, info: &ExporterInfo, count: usize) -> PathBuf {
+        let now = chrono::Local::now();
+        let name = self
+            .filename_template
+            .replace("{date}", &now.format("%Y%m%d").to_string())
+            .replace("{time}", &now.format("%H%M%S").to_string())
+            .replace("{format}", info.id)
+            .replace("{count}", &count.to_string());
+        self.directory.join(format!("{}.{}", name, info.extension))
+    }
+}
+
+/// Timestamped file name in the Downloads folder.
+pub fn default_export_path(extension: &str) -> PathBuf {
+    let mut path = dirs::download_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push(format!("export_{}.{}", chrono::Local::now().format("%Y%m%d_%H%M%S"), extension));
+    path
+}
+
+/// First free variant of `path`, e.g. `report (2).csv` when `report.csv` exists.
+pub fn next_free_path(path: &Path) -> PathBuf {
+    let stem = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+    let extension = path.extension().map(|ext| format!(".{}", ext.to_string_lossy())).unwrap_or_default();
+
+    // The existing file counts as the first copy, so numbering starts at 2.
+    (2..)
+        .map(|n| path.with_file_name(format!("{} ({}){}", stem, n, extension)))
+        .find(|candidate| !candidate.exists())
+        .expect("unbounded range always yields a free name")
+}
+
+/// Sample records and a scratch directory for the exporters' tests.
+#[cfg(test)]
+pub(crate) mod testing {
+    use super::*;
+    use chrono::{Local, TimeZone};
+    use std::path::PathBuf;
+
+    pub fn record(id: u32, name: &str, value: f64, (year, month, day): (i32, u32, u32)) -> TableData {
+        TableData {
+            id,
+            name: name.to_string(),
+            value,
+            date: Local.with_ymd_and_hms(year, month, day, 9, 30, 0).unwrap(),
+        }
+    }
+
+    pub fn records() -> Vec<TableData> {
+        vec![
+            record(1, "Alpha", 10.5, (2024, 1, 15)),
+            record(2, "Beta", -3.25, (2024, 2, 1)),
+            record(3, "Alpha", 1234.0, (2024, 2, 20)),
+        ]
+    }
+
+    /// Runs `exporter` over `data` with every column and the given options.
+    pub fn export(exporter: &dyn Exporter, data: &[TableData], options: &ExportOptions, path: &Path) -> Result<()> {
+        export_columns(exporter, data, &ExportColumn::all(), options, path)
+    }
+
+    pub fn export_columns(
+        exporter: &dyn Exporter,
+        data: &[TableData],
+        columns: &[ExportColumn],
+        options: &ExportOptions,
+        path: &Path,
+    ) -> Result<()> {
+        run(exporter, data, columns, options, &ExportProgress::new(data.len()), false, path)
+    }
+
+    /// Runs `exporter` with every column over records marked as masked.
+    pub fn export_masked(exporter: &dyn Exporter, data: &[TableData], options: &ExportOptions, path: &Path) -> Result<()> {
+        run(exporter, data, &ExportColumn::all(), options, &ExportProgress::new(data.len()), true, path)
+    }
+
+    /// Runs `exporter` with every column, reporting to `progress`.
+    pub fn export_with_progress(
+        exporter: &dyn Exporter,
+        data: &[TableData],
+        options: &ExportOptions,
+        progress: &ExportProgress,
+        path: &Path,
+    ) -> Result<()> {
+        run(exporter, data, &ExportColumn::all(), options, progress, false, path)
+    }
+
+    fn run(
+        exporter: &dyn Exporter,
+        data: &[TableData],
+        columns: &[ExportColumn],
+        options: &ExportOptions,
+        progress: &ExportProgress,
+        masked: bool,
+        path: &Path,
+    ) -> Result<()> {
+        let context = ExportContext {
+            data,
+            columns,
+            options,
+            filter: "All rows",
+            locale: Locale::Standard,
+            masked,
+            progress,
+        };
+        exporter.export(&context, path)
+    }
+
+    /// A new directory under the system temp directory, removed when dropped.
+    pub struct TempDir(PathBuf);
+
+    impl TempDir {
+        pub fn new() -> Self {
+            let path = std::env::temp_dir().join(format!("desktop-app-export-{}", fastrand::u64(..)));
+            std::fs::create_dir_all(&path).unwrap();
+            Self(path)
+        }
+
+        pub fn join(&self, name: &str) -> PathBuf {
+            self.0.join(name)
+        }
+
+        /// Names of the files in the directory, sorted.
+        pub fn files(&self) -> Vec<String> {
+            let mut files: Vec<String> = std::fs::read_dir(&self.0)
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+                .collect();
+            files.sort();
+            files
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_dir_all(&self.0);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn next_free_path_numbers_copies_from_two() {
+        let dir = testing::TempDir::new();
+        let path = dir.join("report.csv");
+        std::fs::write(&path, "").unwrap();
+
+        let first = next_free_path(&path);
+        assert_eq!(first, dir.join("report (2).csv"));
+        std::fs::write(&first, "").unwrap();
+        assert_eq!(next_free_path(&path), dir.join("report (3).csv"));
+    }
+}
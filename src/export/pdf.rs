@@ -0,0 +1,457 @@
+use super::{DATE_FORMAT_OPTION, ExportContext, Exporter, ExporterInfo, OptionKind, OptionSpec, date_format, report};
+use crate::data::{Field, TableData};
+use crate::locale::Locale;
+use anyhow::{Context, Result, anyhow};
+use printpdf::{
+    Color, Image, ImageTransform, IndirectFontRef, Line, Mm, PdfDocument,
+    PdfDocumentReference, PdfLayerReference, Point, Rect, Rgb, image_crate, path::PaintMode,
+};
+use std::fs::File;
+use std::io::BufWriter;
+use std::path::Path;
+
+const TITLE_OPTION: OptionSpec = OptionSpec {
+    key: "title",
+    label: "Title",
+    kind: OptionKind::Text,
+    default: "Data Report",
+    help: "Heading on the first page",
+};
+
+const PAGE_SIZE_OPTION: OptionSpec = OptionSpec {
+    key: "page_size",
+    label: "Page size",
+    kind: OptionKind::Choice(&["a4", "a3", "letter", "legal"]),
+    default: "a4",
+    help: "Paper size",
+};
+
+const ORIENTATION_OPTION: OptionSpec = OptionSpec {
+    key: "orientation",
+    label: "Orientation",
+    kind: OptionKind::Choice(&["portrait", "landscape"]),
+    default: "portrait",
+    help: "Landscape fits wider tables",
+};
+
+const CHARTS_OPTION: OptionSpec = OptionSpec {
+    key: "charts",
+    label: "Charts",
+    kind: OptionKind::Bool,
+    default: "true",
+    help: "Add a chart of value over date and a chart of totals per name",
+};
+
+const LOGO_OPTION: OptionSpec = OptionSpec {
+    key: "logo",
+    label: "Logo",
+    kind: OptionKind::Text,
+    default: "",
+    help: "Path to a PNG or JPEG shown at the top of the first page. Leave empty for none",
+};
+
+const MARGIN: f32 = 15.0;
+const LOGO_HEIGHT: f32 = 15.0;
+const CHART_HEIGHT: f32 = 60.0;
+const ROW_HEIGHT: f32 = 6.0;
+const TABLE_FONT_SIZE: f32 = 9.0;
+const FOOTER_FONT_SIZE: f32 = 8.0;
+
+const PT_TO_MM: f32 = 0.3528;
+/// Average glyph width of the report font, in ems.
+const CHAR_WIDTH: f32 = 0.55;
+
+/// Embedded so names outside Latin-1, such as Czech "ř", are drawn; the
+/// built-in PDF fonts only cover WinAnsi.
+const FONT_REGULAR: &[u8] = include_bytes!("../../assets/fonts/DejaVuSans.ttf");
+const FONT_BOLD: &[u8] = include_bytes!("../../assets/fonts/DejaVuSans-Bold.ttf");
+
+/// Writes a printable report: title, summary figures, optional charts and
+/// the data table, repeating the table header on every page.
+pub struct PdfExporter;
+
+impl PdfExporter {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Exporter for PdfExporter {
+    fn info(&self) -> ExporterInfo {
+        ExporterInfo {
+            id: "pdf",
+            name: "PDF report",
+            extension: "pdf",
+            options: vec![
+                TITLE_OPTION,
+                PAGE_SIZE_OPTION,
+                ORIENTATION_OPTION,
+                CHARTS_OPTION,
+                LOGO_OPTION,
+                DATE_FORMAT_OPTION,
+            ],
+        }
+    }
+
+    fn export(&self, context: &ExportContext, path: &Path) -> Result<()> {
+        let options = context.options;
+        let title = options.value(&TITLE_OPTION)?;
+        let date_format = date_format(options, context.locale)?;
+        let (width, height) = match options.value(&PAGE_SIZE_OPTION)? {
+            "a3" => (297.0, 420.0),
+            "letter" => (215.9, 279.4),
+            "legal" => (215.9, 355.6),
+            _ => (210.0, 297.0),
+        };
+        let (width, height) = match options.value(&ORIENTATION_OPTION)? {
+            "landscape" => (height, width),
+            _ => (width, height),
+        };
+
+        let mut report = Report::new(title, width, height)?;
+
+        let logo = options.value(&LOGO_OPTION)?.trim();
+        if !logo.is_empty() {
+            report.logo(Path::new(logo))?;
+        }
+        report.text(MARGIN, report.y - 8.0, 20.0, true, title);
+        report.y -= 14.0;
+        let generated = format!(
+            "Generated {} | {} | {} rows",
+            context.locale.format_date_time(&chrono::Local::now()),
+            context.filter,
+            context.data.len()
+        );
+        report.text(MARGIN, report.y, 9.0, false, &generated);
+        report.y -= 12.0;
+
+        report.summary(context, &date_format);
+
+        if options.flag(&CHARTS_OPTION)? && !context.data.is_empty() {
+            let has = |field| report::has_column(context, field);
+            if has(Field::Date) && has(Field::Value) {
+                report.line_chart(context.data, context.locale);
+            }
+            if has(Field::Name) && has(Field::Value) {
+                report.bar_chart(context.data, context.locale);
+            }
+        }
+
+        report.table(context, &date_format)?;
+        report.footers(title);
+        report.save(path)
+    }
+}
+
+/// Page state while laying out the report. `y` is the top of the free space,
+/// measured from the bottom of the page like all PDF coordinates.
+struct Report {
+    doc: PdfDocumentReference,
+    layers: Vec<PdfLayerReference>,
+    regular: IndirectFontRef,
+    bold: IndirectFontRef,
+    width: f32,
+    height: f32,
+    y: f32,
+}
+
+impl Report {
+    fn new(title: &str, width: f32, height: f32) -> Result<Self> {
+        let (doc, page, layer) = PdfDocument::new(title, Mm(width), Mm(height), "Report");
+        let layer = doc.get_page(page).get_layer(layer);
+        let regular = doc.add_external_font(FONT_REGULAR)?;
+        let bold = doc.add_external_font(FONT_BOLD)?;
+        Ok(Self {
+            doc,
+            layers: vec![layer],
+            regular,
+            bold,
+            width,
+            height,
+            y: height - MARGIN,
+        })
+    }
+
+    fn layer(&self) -> &PdfLayerReference {
+        self.layers.last().expect("a report always has a page")
+    }
+
+    fn content_width(&self) -> f32 {
+        self.width - 2.0 * MARGIN
+    }
+
+    fn new_page(&mut self) {
+        let (page, layer) = self.doc.add_page(Mm(self.width), Mm(self.height), "Report");
+        self.layers.push(self.doc.get_page(page).get_layer(layer));
+        self.y = self.height - MARGIN;
+    }
+
+    /// Starts a new page unless `needed` millimetres fit above the footer.
+    fn ensure_space(&mut self, needed: f32) -> bool {
+        if self.y - needed < MARGIN + 5.0 {
+            self.new_page();
+            return true;
+        }
+        false
+    }
+
+    fn text(&self, x: f32, y: f32, size: f32, bold: bool, text: &str) {
+        let font = if bold { &self.bold } else { &self.regular };
+        self.layer().use_text(text, size, Mm(x), Mm(y), font);
+    }
+
+    fn text_right(&self, right: f32, y: f32, size: f32, bold: bool, text: &str) {
+        self.text(right - text_width(text, size), y, size, bold, text);
+    }
+
+    fn fill(&self, x: f32, y: f32, width: f32, height: f32, color: (f32, f32, f32)) {
+        let layer = self.layer();
+        layer.set_fill_color(rgb(color));
+        layer.add_rect(Rect::new(Mm(x), Mm(y), Mm(x + width), Mm(y + height)).with_mode(PaintMode::Fill));
+        layer.set_fill_color(rgb((0.0, 0.0, 0.0)));
+    }
+
+    fn stroke(&self, points: &[(f32, f32)], color: (f32, f32, f32), thickness: f32) {
+        let layer = self.layer();
+        layer.set_outline_color(rgb(color));
+        layer.set_outline_thickness(thickness);
+        layer.add_line(Line {
+            points: points.iter().map(|&(x, y)| (Point::new(Mm(x), Mm(y)), false)).collect(),
+            is_closed: false,
+        });
+    }
+
+    fn logo(&mut self, path: &Path) -> Result<()> {
+        let image = image_crate::open(path).with_context(|| format!("Failed to load logo {}", path.display()))?;
+        // Alpha channels render black in some viewers, so the logo is flattened to RGB
+        let image = image_crate::DynamicImage::ImageRgb8(image.to_rgb8());
+        let dpi = image.height() as f32 * 25.4 / LOGO_HEIGHT;
+        let logo_width = image.width() as f32 / dpi * 25.4;
+
+        Image::from_dynamic_image(&image).add_to_layer(
+            self.layer().clone(),
+            ImageTransform {
+                translate_x: Some(Mm(self.width - MARGIN - logo_width)),
+                translate_y: Some(Mm(self.height - MARGIN - LOGO_HEIGHT)),
+                dpi: Some(dpi),
+                ..Default::default()
+            },
+        );
+        Ok(())
+    }
+
+    fn heading(&mut self, text: &str) {
+        self.text(MARGIN, self.y - 5.0, 13.0, true, text);
+        self.y -= 10.0;
+    }
+
+    fn summary(&mut self, context: &ExportContext, date_format: &str) {
+        let figures = report::summary_figures(context, date_format);
+
+        self.heading("Summary");
+        for (label, value) in figures {
+            self.text(MARGIN, self.y - 4.0, 10.0, false, label);
+            self.text(MARGIN + 35.0, self.y - 4.0, 10.0, true, &value);
+            self.y -= 5.5;
+        }
+        self.y -= 6.0;
+    }
+
+    fn chart_frame(&mut self, title: &str) -> (f32, f32, f32, f32) {
+        self.ensure_space(CHART_HEIGHT + 12.0);
+        self.heading(title);
+        let (left, bottom) = (MARGIN + 18.0, self.y - CHART_HEIGHT + 8.0);
+        let (width, height) = (self.content_width() - 18.0, CHART_HEIGHT - 8.0);
+        self.stroke(&[(left, bottom + height), (left, bottom), (left + width, bottom)], (0.4, 0.4, 0.4), 0.5);
+        self.y -= CHART_HEIGHT + 6.0;
+        (left, bottom, width, height)
+    }
+
+    fn line_chart(&mut self, data: &[TableData], locale: Locale) {
+        let points = report::value_over_time(data);
+
+        let (left, bottom, width, height) = self.chart_frame("Value over time");
+        let (first, last) = (points[0].0, points[points.len() - 1].0);
+        let (min, max) = report::value_bounds(points.iter().map(|&(_, value)| value));
+        let x = |date| left + width * report::time_fraction(date, first, last);
+        let y = |value: f64| bottom + height * ((value - min) / (max - min)) as f32;
+
+        let line: Vec<(f32, f32)> = points.iter().map(|&(date, value)| (x(date), y(value))).collect();
+        self.stroke(&line, (0.2, 0.4, 0.8), 1.0);
+
+        self.text_right(left - 1.5, bottom + height - 2.0, 7.0, false, &locale.format_number(max, 2));
+        self.text_right(left - 1.5, bottom, 7.0, false, &locale.format_number(min, 2));
+        self.text(left, bottom - 4.0, 7.0, false, &locale.format_date(&first));
+        self.text_right(left + width, bottom - 4.0, 7.0, false, &locale.format_date(&last));
+    }
+
+    fn bar_chart(&mut self, data: &[TableData], locale: Locale) {
+        let (title, totals) = report::totals_per_name(data);
+
+        let (left, bottom, width, height) = self.chart_frame(&title);
+        let (min, max) = report::value_bounds(totals.iter().map(|&(_, total)| total).chain([0.0]));
+        let y = |value: f64| bottom + height * ((value - min) / (max - min)) as f32;
+        let slot = width / totals.len() as f32;
+
+        for (index, (name, total)) in totals.iter().enumerate() {
+            let x = left + slot * index as f32 + slot * 0.15;
+            let (top, base) = (y(*total), y(0.0));
+            self.fill(x, top.min(base), slot * 0.7, (top - base).abs(), (0.3, 0.6, 0.4));
+            self.text(x, bottom - 4.0, 7.0, false, &truncate(name, slot, 7.0));
+        }
+        self.text_right(left - 1.5, bottom + height - 2.0, 7.0, false, &locale.format_number(max, 2));
+        self.text_right(left - 1.5, bottom, 7.0, false, &locale.format_number(min, 2));
+    }
+
+    fn table(&mut self, context: &ExportContext, date_format: &str) -> Result<()> {
+        let weights: Vec<f32> = context
+            .columns
+            .iter()
+            .map(|column| match column.field {
+                Field::Id => 1.0,
+                Field::Name => 3.0,
+                Field::Value => 1.6,
+                Field::Date => 2.4,
+            })
+            .collect();
+        let scale = self.content_width() / weights.iter().sum::<f32>().max(1.0);
+        let widths: Vec<f32> = weights.iter().map(|weight| weight * scale).collect();
+
+        self.ensure_space(ROW_HEIGHT * 3.0 + 10.0);
+        self.heading("Data");
+        self.table_header(context, &widths);
+
+        for (index, item) in context.data.iter().enumerate() {
+            if self.ensure_space(ROW_HEIGHT) {
+                self.table_header(context, &widths);
+            }
+            if index % 2 == 1 {
+                self.fill(MARGIN, self.y - ROW_HEIGHT, self.content_width(), ROW_HEIGHT, (0.95, 0.95, 0.95));
+            }
+
+            let mut x = MARGIN;
+            for (column, width) in context.columns.iter().zip(&widths) {
+                let baseline = self.y - ROW_HEIGHT + 1.8;
+                match column.field {
+                    Field::Id => self.text_right(x + width - 1.5, baseline, TABLE_FONT_SIZE, false, &item.id.to_string()),
+                    Field::Value => {
+                        self.text_right(x + width - 1.5, baseline, TABLE_FONT_SIZE, false, &context.locale.format_number(item.value, 2))
+                    }
+                    Field::Name => {
+                        self.text(x + 1.5, baseline, TABLE_FONT_SIZE, false, &truncate(&item.name, width - 3.0, TABLE_FONT_SIZE))
+                    }
+                    Field::Date => {
+                        let date = item.date.format(date_format).to_string();
+                        self.text(x + 1.5, baseline, TABLE_FONT_SIZE, false, &truncate(&date, width - 3.0, TABLE_FONT_SIZE))
+                    }
+                }
+                x += width;
+            }
+            self.y -= ROW_HEIGHT;
+            context.progress.set(index + 1)?;
+        }
+        Ok(())
+    }
+
+    fn table_header(&mut self, context: &ExportContext, widths: &[f32]) {
+        self.fill(MARGIN, self.y - ROW_HEIGHT, self.content_width(), ROW_HEIGHT, (0.83, 0.83, 0.83));
+        let mut x = MARGIN;
+        for (column, width) in context.columns.iter().zip(widths) {
+            let baseline = self.y - ROW_HEIGHT + 1.8;
+            let header = truncate(&column.header, width - 3.0, TABLE_FONT_SIZE);
+            match column.field {
+                Field::Id | Field::Value => self.text_right(x + width - 1.5, baseline, TABLE_FONT_SIZE, true, &header),
+                Field::Name | Field::Date => self.text(x + 1.5, baseline, TABLE_FONT_SIZE, true, &header),
+            }
+            x += width;
+        }
+        self.y -= ROW_HEIGHT;
+    }
+
+    /// Page numbers can only be written once the page count is known.
+    fn footers(&self, title: &str) {
+        let count = self.layers.len();
+        for (index, layer) in self.layers.iter().enumerate() {
+            let page = format!("Page {} of {}", index + 1, count);
+            let x = self.width - MARGIN - text_width(&page, FOOTER_FONT_SIZE);
+            layer.use_text(title, FOOTER_FONT_SIZE, Mm(MARGIN), Mm(MARGIN - 7.0), &self.regular);
+            layer.use_text(page, FOOTER_FONT_SIZE, Mm(x), Mm(MARGIN - 7.0), &self.regular);
+        }
+    }
+
+    fn save(self, path: &Path) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.doc.save(&mut writer).map_err(|e| anyhow!("Failed to write PDF: {}", e))
+    }
+}
+
+fn rgb((r, g, b): (f32, f32, f32)) -> Color {
+    Color::Rgb(Rgb::new(r, g, b, None))
+}
+
+/// Rough DejaVu Sans width in millimetres, from its average glyph width.
+fn text_width(text: &str, size: f32) -> f32 {
+    text.chars().count() as f32 * size * CHAR_WIDTH * PT_TO_MM
+}
+
+/// Shortens `text` to fit `width` millimetres, marking the cut with "...".
+fn truncate(text: &str, width: f32, size: f32) -> String {
+    if text_width(text, size) <= width {
+        return text.to_string();
+    }
+    let fit = ((width / (size * CHAR_WIDTH * PT_TO_MM)) as usize).saturating_sub(3);
+    format!("{}...", text.chars().take(fit).collect::<String>())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::{self, TempDir};
+    use super::super::ExportOptions;
+    use super::*;
+
+    fn write(data: &[TableData], options: &ExportOptions, path: &Path) -> String {
+        testing::export(&PdfExporter::new(), data, options, path).unwrap();
+        String::from_utf8_lossy(&std::fs::read(path).unwrap()).into_owned()
+    }
+
+    fn page_count(pdf: &str) -> usize {
+        let count = &pdf[pdf.find("/Type/Pages/Count ").unwrap() + 18..];
+        count[..count.find('/').unwrap()].parse().unwrap()
+    }
+
+    #[test]
+    fn reports_are_titled_pdfs_with_embedded_fonts() {
+        let dir = TempDir::new();
+        let mut options = ExportOptions::default();
+        options.set(TITLE_OPTION.key, "Monthly figures");
+        options.set(CHARTS_OPTION.key, "false");
+
+        let pdf = write(&testing::records(), &options, &dir.join("report.pdf"));
+
+        assert!(pdf.starts_with("%PDF-") && pdf.trim_end().ends_with("%%EOF"));
+        assert!(pdf.contains("/Title(Monthly figures)"));
+        assert_eq!(pdf.matches("/FontFile2 ").count(), 2, "regular and bold are embedded");
+        assert_eq!(page_count(&pdf), 1);
+        // A4 portrait in points.
+        assert!(pdf.contains("/MediaBox[0 0 595.27563 841.88983]"));
+    }
+
+    #[test]
+    fn long_tables_run_over_pages_in_the_chosen_layout() {
+        let dir = TempDir::new();
+        let data: Vec<TableData> =
+            (0..200).map(|id| testing::record(id, "Alpha", f64::from(id), (2024, 1, 15))).collect();
+        let mut options = ExportOptions::default();
+        options.set(CHARTS_OPTION.key, "false");
+
+        let portrait = page_count(&write(&data, &options, &dir.join("portrait.pdf")));
+        options.set(PAGE_SIZE_OPTION.key, "letter");
+        options.set(ORIENTATION_OPTION.key, "landscape");
+        let landscape = write(&data, &options, &dir.join("landscape.pdf"));
+
+        assert!(portrait > 1, "{}", portrait);
+        assert!(page_count(&landscape) > portrait);
+        // US Letter landscape in points.
+        assert!(landscape.contains("/MediaBox[0 0 792.00006 612.00006]"));
+    }
+}
@@ -0,0 +1,80 @@
+//! Figures and chart series shared by the PDF and HTML reports.
+
+use super::ExportContext;
+use crate::data::{Field, TableData};
+use chrono::{DateTime, Local};
+use std::collections::BTreeMap;
+
+/// Enough points for a smooth line without bloating the file.
+const MAX_CHART_POINTS: usize = 1000;
+const MAX_CHART_BARS: usize = 15;
+
+pub fn has_column(context: &ExportContext, field: Field) -> bool {
+    context.columns.iter().any(|column| column.field == field)
+}
+
+/// Quick Stats style figures for the exported rows and columns, as label and text.
+pub fn summary_figures(context: &ExportContext, date_format: &str) -> Vec<(&'static str, String)> {
+    let data = context.data;
+    let locale = context.locale;
+    let mut figures = vec![("Records", locale.format_count(data.len()))];
+
+    if has_column(context, Field::Value) && !data.is_empty() {
+        let total: f64 = data.iter().map(|item| item.value).sum();
+        let (min, max) = value_range(data.iter().map(|item| item.value));
+        figures.push(("Total", locale.format_number(total, 2)));
+        figures.push(("Average", locale.format_number(total / data.len() as f64, 2)));
+        figures.push(("Minimum", locale.format_number(min, 2)));
+        figures.push(("Maximum", locale.format_number(max, 2)));
+    }
+    if has_column(context, Field::Date)
+        && let (Some(first), Some(last)) = (data.iter().map(|item| item.date).min(), data.iter().map(|item| item.date).max())
+    {
+        figures.push(("First date", first.format(date_format).to_string()));
+        figures.push(("Last date", last.format(date_format).to_string()));
+    }
+    figures
+}
+
+/// Values sorted by date, thinned out to at most `MAX_CHART_POINTS`.
+pub fn value_over_time(data: &[TableData]) -> Vec<(DateTime<Local>, f64)> {
+    let mut points: Vec<(DateTime<Local>, f64)> = data.iter().map(|item| (item.date, item.value)).collect();
+    points.sort_by_key(|&(date, _)| date);
+    let step = points.len().div_ceil(MAX_CHART_POINTS).max(1);
+    points.into_iter().step_by(step).collect()
+}
+
+/// Total value per name, largest first, limited to `MAX_CHART_BARS`, plus a
+/// chart title saying whether names were left out.
+pub fn totals_per_name(data: &[TableData]) -> (String, Vec<(&str, f64)>) {
+    let mut totals: BTreeMap<&str, f64> = BTreeMap::new();
+    for item in data {
+        *totals.entry(item.name.as_str()).or_default() += item.value;
+    }
+    let mut totals: Vec<(&str, f64)> = totals.into_iter().collect();
+    totals.sort_by(|a, b| b.1.total_cmp(&a.1));
+
+    let title = if totals.len() > MAX_CHART_BARS {
+        format!("Total per name (top {})", MAX_CHART_BARS)
+    } else {
+        "Total per name".to_string()
+    };
+    totals.truncate(MAX_CHART_BARS);
+    (title, totals)
+}
+
+fn value_range(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    values.fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| (min.min(value), max.max(value)))
+}
+
+/// Range to scale chart values into, widened when all values are equal.
+pub fn value_bounds(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    let (min, max) = value_range(values);
+    if max > min { (min, max) } else { (min - 1.0, max + 1.0) }
+}
+
+/// Position of `date` between `first` and `last`, from 0.0 to 1.0.
+pub fn time_fraction(date: DateTime<Local>, first: DateTime<Local>, last: DateTime<Local>) -> f32 {
+    let span = (last - first).num_seconds();
+    if span > 0 { (date - first).num_seconds() as f32 / span as f32 } else { 0.5 }
+}
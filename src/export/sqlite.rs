@@ -0,0 +1,305 @@
+use super::{ExportContext, ExportOptions, Exporter, ExporterInfo, OptionKind, OptionSpec};
+use crate::data::Field;
+use crate::storage;
+use anyhow::{Result, bail};
+use chrono::{SecondsFormat, Utc};
+use rusqlite::types::Value;
+use rusqlite::{Connection, params, params_from_iter};
+use std::path::Path;
+
+const TABLE_OPTION: OptionSpec = OptionSpec {
+    key: "table",
+    label: "Table name",
+    kind: OptionKind::Text,
+    default: "records",
+    help: "Name of the table the records are written to",
+};
+
+const APPEND_OPTION: OptionSpec = OptionSpec {
+    key: "append",
+    label: "Append",
+    kind: OptionKind::Bool,
+    default: "false",
+    help: "Add the records to the table of an existing database instead of replacing the file",
+};
+
+/// Key/value table describing the last export into the database.
+const METADATA_TABLE: &str = "_metadata";
+
+/// Writes the records to a table in an SQLite database, with indexes on the
+/// name and date columns and a metadata table.
+pub struct SqliteExporter;
+
+impl SqliteExporter {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+impl Exporter for SqliteExporter {
+    fn info(&self) -> ExporterInfo {
+        ExporterInfo {
+            id: "sqlite",
+            name: "SQLite",
+            extension: "sqlite",
+            options: vec![TABLE_OPTION, APPEND_OPTION],
+        }
+    }
+
+    fn appends(&self, options: &ExportOptions) -> bool {
+        options.flag(&APPEND_OPTION).unwrap_or(false)
+    }
+
+    fn export(&self, context: &ExportContext, path: &Path) -> Result<()> {
+        let table = context.options.value(&TABLE_OPTION)?.trim();
+        if table.is_empty() || table == METADATA_TABLE || table.to_lowercase().starts_with("sqlite_") {
+            bail!("Invalid table name \"{}\"", table);
+        }
+        if context.options.flag(&APPEND_OPTION)? {
+            // Everything happens in one transaction, so a failed or cancelled
+            // append leaves the database as it was.
+            return write_database(context, table, path);
+        }
+
+        // A new database is built next to the old one and only replaces it once complete.
+        storage::replace_file(path, |temp_path| write_database(context, table, temp_path))
+    }
+}
+
+/// Creates or appends to the table in one transaction.
+fn write_database(context: &ExportContext, table: &str, path: &Path) -> Result<()> {
+    let mut connection = Connection::open(path)?;
+    let transaction = connection.transaction()?;
+    let columns: Vec<(String, &str)> = context
+        .columns
+        .iter()
+        .map(|column| (column.header.clone(), column_type(column.field)))
+        .collect();
+
+    let existing = table_columns(&transaction, table)?;
+    if existing.is_empty() {
+        let definitions: Vec<String> = columns
+            .iter()
+            .map(|(name, column_type)| format!("{} {}", quote(name), column_type))
+            .collect();
+        transaction.execute(&format!("CREATE TABLE {} ({})", quote(table), definitions.join(", ")), [])?;
+    } else if existing != columns {
+        let describe = |columns: &[(String, &str)]| {
+            columns.iter().map(|(name, column_type)| format!("{} {}", name, column_type)).collect::<Vec<_>>().join(", ")
+        };
+        bail!(
+            "Table \"{}\" has the columns ({}), but this export writes ({})",
+            table,
+            describe(&existing),
+            describe(&columns)
+        );
+    }
+
+    for column in context.columns {
+        if matches!(column.field, Field::Name | Field::Date) {
+            let index = format!("{}_{}", table, column.header);
+            transaction.execute(
+                &format!("CREATE INDEX IF NOT EXISTS {} ON {} ({})", quote(&index), quote(table), quote(&column.header)),
+                [],
+            )?;
+        }
+    }
+
+    {
+        let placeholders = vec!["?"; columns.len()].join(", ");
+        let mut insert =
+            transaction.prepare(&format!("INSERT INTO {} VALUES ({})", quote(table), placeholders))?;
+        for (index, item) in context.data.iter().enumerate() {
+            let values = context.columns.iter().map(|column| match column.field {
+                Field::Id => Value::Integer(i64::from(item.id)),
+                Field::Name => Value::Text(item.name.clone()),
+                // SQLite stores NaN as NULL
+                Field::Value => Value::Real(item.value),
+                Field::Date => Value::Text(date_text(item.date.with_timezone(&Utc))),
+            });
+            insert.execute(params_from_iter(values))?;
+            context.progress.set(index + 1)?;
+        }
+    }
+
+    let total: i64 = transaction.query_row(&format!("SELECT COUNT(*) FROM {}", quote(table)), [], |row| row.get(0))?;
+    transaction.execute(
+        &format!(
+            "CREATE TABLE IF NOT EXISTS {} (key TEXT PRIMARY KEY, value TEXT NOT NULL)",
+            quote(METADATA_TABLE)
+        ),
+        [],
+    )?;
+    let metadata = [
+        ("Application", env!("CARGO_PKG_NAME").to_string()),
+        ("App version", env!("CARGO_PKG_VERSION").to_string()),
+        ("Exported at", chrono::Local::now().to_rfc3339()),
+        ("Table", table.to_string()),
+        ("Record count", total.to_string()),
+        ("Records in last export", context.data.len().to_string()),
+        ("Exported columns", serde_json::to_string(context.columns)?),
+        ("Filter", context.filter.to_string()),
+        ("Dates", "ISO 8601 text in UTC".to_string()),
+    ];
+    for (key, value) in metadata {
+        transaction.execute(
+            &format!("INSERT OR REPLACE INTO {} (key, value) VALUES (?1, ?2)", quote(METADATA_TABLE)),
+            params![key, value],
+        )?;
+    }
+
+    transaction.commit()?;
+    Ok(())
+}
+
+fn column_type(field: Field) -> &'static str {
+    match field {
+        Field::Id => "INTEGER NOT NULL",
+        Field::Name => "TEXT NOT NULL",
+        Field::Value => "REAL",
+        Field::Date => "TEXT NOT NULL",
+    }
+}
+
+/// Fixed-width UTC timestamps sort in time order and work with SQLite's date functions.
+fn date_text(date: chrono::DateTime<Utc>) -> String {
+    date.to_rfc3339_opts(SecondsFormat::Micros, true)
+}
+
+/// Names and declared types of a table's columns; empty when there is no such table.
+fn table_columns(connection: &Connection, table: &str) -> Result<Vec<(String, &'static str)>> {
+    let mut statement = connection.prepare(&format!("PRAGMA table_info({})", quote(table)))?;
+    let rows = statement.query_map([], |row| {
+        let name: String = row.get("name")?;
+        let declared: String = row.get("type")?;
+        let not_null: bool = row.get("notnull")?;
+        Ok((name, declared, not_null))
+    })?;
+
+    let mut columns = Vec::new();
+    for row in rows {
+        let (name, declared, not_null) = row?;
+        // Map back onto the types this exporter writes, so they compare equal.
+        let column_type = Field::ALL
+            .into_iter()
+            .map(column_type)
+            .find(|column_type| {
+                let (base, constraint) = column_type.split_once(' ').unwrap_or((column_type, ""));
+                base.eq_ignore_ascii_case(&declared) && not_null == (constraint == "NOT NULL")
+            })
+            .unwrap_or("other");
+        columns.push((name, column_type));
+    }
+    Ok(columns)
+}
+
+fn quote(identifier: &str) -> String {
+    format!("\"{}\"", identifier.replace('"', "\"\""))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::{self, TempDir};
+    use super::super::{ExportColumn, ExportProgress};
+    use super::*;
+
+    fn query_strings(connection: &Connection, sql: &str) -> Vec<String> {
+        let mut statement = connection.prepare(sql).unwrap();
+        statement.query_map([], |row| row.get(0)).unwrap().map(Result::unwrap).collect()
+    }
+
+    fn metadata(connection: &Connection, key: &str) -> String {
+        connection.query_row("SELECT value FROM _metadata WHERE key = ?1", [key], |row| row.get(0)).unwrap()
+    }
+
+    fn count(path: &Path) -> i64 {
+        Connection::open(path).unwrap().query_row("SELECT COUNT(*) FROM records", [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn databases_have_typed_columns_indexes_and_metadata() {
+        let dir = TempDir::new();
+        let path = dir.join("records.sqlite");
+        let mut data = testing::records();
+        data.push(testing::record(4, "Missing", f64::NAN, (2024, 3, 1)));
+
+        testing::export(&SqliteExporter::new(), &data, &ExportOptions::default(), &path).unwrap();
+
+        let connection = Connection::open(&path).unwrap();
+        let columns = table_columns(&connection, "records").unwrap();
+        assert_eq!(
+            columns,
+            [
+                ("ID".to_string(), "INTEGER NOT NULL"),
+                ("Name".to_string(), "TEXT NOT NULL"),
+                ("Value".to_string(), "REAL"),
+                ("Date".to_string(), "TEXT NOT NULL"),
+            ]
+        );
+        let indexes = query_strings(
+            &connection,
+            "SELECT name FROM sqlite_master WHERE type = 'index' AND tbl_name = 'records' ORDER BY name",
+        );
+        assert_eq!(indexes, ["records_Date", "records_Name"]);
+
+        let (id, name, value, date): (i64, String, Option<f64>, String) = connection
+            .query_row("SELECT * FROM records WHERE ID = 2", [], |row| {
+                Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?))
+            })
+            .unwrap();
+        assert_eq!((id, name.as_str(), value), (2, "Beta", Some(-3.25)));
+        assert_eq!(date, date_text(data[1].date.with_timezone(&Utc)));
+        let missing: Option<f64> =
+            connection.query_row("SELECT Value FROM records WHERE ID = 4", [], |row| row.get(0)).unwrap();
+        assert_eq!(missing, None);
+
+        assert_eq!(metadata(&connection, "Table"), "records");
+        assert_eq!(metadata(&connection, "Record count"), "4");
+        assert_eq!(metadata(&connection, "App version"), env!("CARGO_PKG_VERSION"));
+        assert_eq!(metadata(&connection, "Filter"), "All rows");
+    }
+
+    #[test]
+    fn appending_adds_rows_and_rejects_a_different_table() {
+        let dir = TempDir::new();
+        let path = dir.join("records.sqlite");
+        let mut options = ExportOptions::default();
+        options.set(APPEND_OPTION.key, "true");
+        let exporter = SqliteExporter::new();
+
+        testing::export(&exporter, &testing::records(), &options, &path).unwrap();
+        testing::export(&exporter, &testing::records()[..1], &options, &path).unwrap();
+        assert_eq!(count(&path), 4);
+        let connection = Connection::open(&path).unwrap();
+        assert_eq!(metadata(&connection, "Record count"), "4");
+        assert_eq!(metadata(&connection, "Records in last export"), "1");
+        drop(connection);
+
+        let columns = [ExportColumn::new(Field::Name), ExportColumn::new(Field::Value)];
+        let error = testing::export_columns(&exporter, &testing::records(), &columns, &options, &path).unwrap_err();
+        assert!(error.to_string().contains("Table \"records\" has the columns (ID INTEGER NOT NULL"), "{}", error);
+        assert_eq!(count(&path), 4);
+
+        let progress = ExportProgress::new(3);
+        progress.cancel();
+        assert!(testing::export_with_progress(&exporter, &testing::records(), &options, &progress, &path).is_err());
+        assert_eq!(count(&path), 4);
+
+        options.set(APPEND_OPTION.key, "false");
+        testing::export(&exporter, &testing::records(), &options, &path).unwrap();
+        assert_eq!(count(&path), 3);
+        assert_eq!(dir.files(), ["records.sqlite"]);
+    }
+
+    #[test]
+    fn reserved_table_names_are_rejected() {
+        let dir = TempDir::new();
+        let mut options = ExportOptions::default();
+        for table in ["", "_metadata", "sqlite_master"] {
+            options.set(TABLE_OPTION.key, table);
+            let path = dir.join("records.sqlite");
+            assert!(testing::export(&SqliteExporter::new(), &testing::records(), &options, &path).is_err());
+        }
+        assert!(dir.files().is_empty());
+    }
+}
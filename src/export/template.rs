@@ -0,0 +1,966 @@
+//! Fills a user-supplied .xlsx template by editing its sheet XML in place, so
+//! everything besides the filled cells is kept exactly as the template has it.
+
+use super::ExportContext;
+use super::xlsx::{
+    WORKBOOK_PART, WORKBOOK_RELS_PART, attribute, escape_string, is, read_part, reference_column, sheet_names,
+    sheet_part,
+};
+use crate::data::{Field, TableData};
+use crate::storage;
+use anyhow::{Context, Result, anyhow, bail};
+use chrono::{NaiveDate, NaiveDateTime};
+use quick_xml::Reader;
+use quick_xml::escape::escape;
+use quick_xml::events::{BytesStart, Event};
+use rust_xlsxwriter::{column_name_to_number, column_number_to_name, row_col_to_cell};
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::Path;
+use zip::write::SimpleFileOptions;
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+const CONTENT_TYPES_PART: &str = "[Content_Types].xml";
+const CALC_CHAIN_PART: &str = "xl/calcChain.xml";
+
+/// Workbook elements that follow `calcPr`, to find where a new one belongs.
+const AFTER_CALC_PR: [&str; 10] = [
+    "<oleSize",
+    "<customWorkbookViews",
+    "<pivotCaches",
+    "<smartTagPr",
+    "<smartTagTypes",
+    "<webPublishing",
+    "<fileRecoveryPr",
+    "<webPublishObjects",
+    "<extLst",
+    "</workbook>",
+];
+
+const MAX_ROWS: usize = 1_048_576;
+const MAX_COLUMNS: usize = 16_384;
+
+/// Rows between progress updates.
+const PROGRESS_INTERVAL: usize = 1000;
+
+/// Elements whose text is a formula or a list of ranges.
+const FORMULA_ELEMENTS: [&str; 6] = ["f", "formula", "formula1", "formula2", "definedName", "sqref"];
+
+/// Attributes holding ranges of the part's own sheet, by element.
+const RANGE_ATTRIBUTES: [(&str, &str); 6] = [
+    ("conditionalFormatting", "sqref"),
+    ("dataValidation", "sqref"),
+    ("autoFilter", "ref"),
+    ("table", "ref"),
+    ("sortState", "ref"),
+    ("sortCondition", "ref"),
+];
+
+/// Where the data goes in the template.
+pub struct TemplateTarget {
+    pub sheet: String,
+    /// Zero-based row and column of the first data cell.
+    pub row: u32,
+    pub col: u16,
+    /// Field for each column from `col` onwards; `None` leaves the column untouched.
+    pub fields: Vec<Option<Field>>,
+}
+
+/// A cell of the template sheet, kept as its original XML.
+struct SheetCell {
+    style: Option<String>,
+    /// Whether the cell holds a value or formula, as opposed to only a style.
+    has_content: bool,
+    xml: String,
+}
+
+/// A row of the template sheet. Attributes are kept escaped, as read.
+#[derive(Default)]
+struct SheetRow {
+    attributes: Vec<(String, String)>,
+    cells: BTreeMap<u16, SheetCell>,
+}
+
+/// Writes a copy of `template` to `path` with the data filled in at `target`.
+pub fn fill(context: &ExportContext, template: &Path, target: &TemplateTarget, path: &Path) -> Result<()> {
+    if target.row as usize + context.data.len() > MAX_ROWS {
+        bail!(
+            "The template sheet has room for {} rows from {}",
+            MAX_ROWS - target.row as usize,
+            row_col_to_cell(target.row, target.col)
+        );
+    }
+    if target.col as usize + target.fields.len() > MAX_COLUMNS {
+        bail!("The template columns go past the last Excel column");
+    }
+
+    // Read up front, so the export may overwrite the template itself.
+    let bytes = fs::read(template).with_context(|| format!("Failed to read template {}", template.display()))?;
+    let mut archive = ZipArchive::new(Cursor::new(bytes)).context("The template is not an .xlsx workbook")?;
+
+    let workbook = read_part(&mut archive, WORKBOOK_PART)?;
+    let relationships = read_part(&mut archive, WORKBOOK_RELS_PART)?;
+    let content_types = read_part(&mut archive, CONTENT_TYPES_PART)?;
+    let sheet_part = sheet_part(&workbook, &relationships, &target.sheet)?;
+    let sheet = read_part(&mut archive, &sheet_part)?;
+
+    let date1904 = workbook.contains("date1904=\"1\"") || workbook.contains("date1904=\"true\"");
+    let date_base = match date1904 {
+        true => NaiveDate::from_ymd_opt(1904, 1, 1),
+        false => NaiveDate::from_ymd_opt(1899, 12, 30),
+    }
+    .and_then(|date| date.and_hms_opt(0, 0, 0))
+    .expect("Excel epochs are valid dates");
+
+    let mut parts = BTreeMap::new();
+    let filled = fill_sheet(context, &sheet, target, date_base)?;
+    // Formulas over the filled cells hold stale results, so Excel is told to
+    // recalculate on open and the cached calculation order is dropped.
+    let mut workbook_part = recalculate_on_load(&workbook);
+    match Growth::new(target, context.data.len()) {
+        Some(growth) => {
+            parts.insert(sheet_part.clone(), growth.grow_part(&filled, Some(&target.sheet))?);
+            workbook_part = growth.grow_part(&workbook_part, None)?;
+            grow_other_parts(&mut archive, &growth, &workbook, &relationships, &sheet_part, &mut parts)?;
+        }
+        None => {
+            parts.insert(sheet_part, filled);
+        }
+    }
+    parts.insert(WORKBOOK_PART.to_string(), workbook_part);
+    parts.insert(
+        WORKBOOK_RELS_PART.to_string(),
+        remove_elements(&relationships, "Relationship", "calcChain"),
+    );
+    // An .xltx template turns into a regular workbook.
+    let content_types = remove_elements(&content_types, "Override", "calcChain")
+        .replace("spreadsheetml.template.main+xml", "spreadsheetml.sheet.main+xml");
+    parts.insert(CONTENT_TYPES_PART.to_string(), content_types);
+
+    // Written next to the destination and renamed once complete, so a failed
+    // export never leaves a broken workbook behind.
+    storage::replace_file(path, |temp_path| {
+        let mut writer = ZipWriter::new(fs::File::create(temp_path)?);
+        let options = SimpleFileOptions::default().compression_method(CompressionMethod::Deflated);
+        for index in 0..archive.len() {
+            let file = archive.by_index(index)?;
+            let name = file.name().to_string();
+            if name == CALC_CHAIN_PART {
+                continue;
+            }
+            match parts.get(&name) {
+                Some(content) => {
+                    writer.start_file(name, options)?;
+                    writer.write_all(content.as_bytes())?;
+                }
+                None => writer.raw_copy_file(file)?,
+            }
+        }
+        writer.finish()?;
+        Ok(())
+    })
+}
+
+/// Grows the ranges of the other sheets, the target sheet's tables and the
+/// charts that refer to the data, adding the changed parts to `parts`.
+fn grow_other_parts(
+    archive: &mut ZipArchive<Cursor<Vec<u8>>>,
+    growth: &Growth,
+    workbook: &str,
+    relationships: &str,
+    target_part: &str,
+    parts: &mut BTreeMap<String, String>,
+) -> Result<()> {
+    let mut grow = |archive: &mut ZipArchive<Cursor<Vec<u8>>>, part: String, own_sheet: Option<&str>| -> Result<()> {
+        let xml = read_part(archive, &part)?;
+        let grown = growth.grow_part(&xml, own_sheet)?;
+        if grown != xml {
+            parts.insert(part, grown);
+        }
+        Ok(())
+    };
+
+    for name in sheet_names(workbook)? {
+        let part = sheet_part(workbook, relationships, &name)?;
+        if part != target_part {
+            grow(archive, part, Some(&name))?;
+        }
+    }
+    for table in table_parts(archive, target_part)? {
+        grow(archive, table, Some(growth.sheet))?;
+    }
+    let charts: Vec<String> = archive
+        .file_names()
+        .filter(|name| name.starts_with("xl/charts/chart") && name.ends_with(".xml"))
+        .map(String::from)
+        .collect();
+    for chart in charts {
+        grow(archive, chart, None)?;
+    }
+    Ok(())
+}
+
+/// Table parts belonging to a worksheet part, from its relationships.
+fn table_parts(archive: &mut ZipArchive<Cursor<Vec<u8>>>, sheet_part: &str) -> Result<Vec<String>> {
+    let (directory, file) = sheet_part.rsplit_once('/').unwrap_or(("", sheet_part));
+    let rels_part = format!("{}/_rels/{}.rels", directory, file);
+    if archive.by_name(&rels_part).is_err() {
+        return Ok(Vec::new());
+    }
+    let rels = read_part(archive, &rels_part)?;
+    let mut tables = Vec::new();
+    let mut reader = Reader::from_str(&rels);
+    loop {
+        match reader.read_event()? {
+            Event::Start(element) | Event::Empty(element) if is(&element, "Relationship") => {
+                let is_table = attribute(&element, "Type")?.is_some_and(|kind| kind.ends_with("/table"));
+                if is_table && let Some(target) = attribute(&element, "Target")? {
+                    tables.push(resolve_part(directory, &target));
+                }
+            }
+            Event::Eof => return Ok(tables),
+            _ => {}
+        }
+    }
+}
+
+/// Package path of a relationship target relative to `directory`.
+fn resolve_part(directory: &str, target: &str) -> String {
+    if let Some(absolute) = target.strip_prefix('/') {
+        return absolute.to_string();
+    }
+    let mut segments: Vec<&str> = directory.split('/').filter(|segment| !segment.is_empty()).collect();
+    for segment in target.split('/') {
+        match segment {
+            ".." => {
+                segments.pop();
+            }
+            "." | "" => {}
+            segment => segments.push(segment),
+        }
+    }
+    segments.join("/")
+}
+
+/// Namespace prefix of an element including the colon, e.g. `x:`, or an empty string.
+fn prefix(element: &BytesStart) -> String {
+    match element.name().prefix() {
+        Some(prefix) => format!("{}:", String::from_utf8_lossy(prefix.as_ref())),
+        None => String::new(),
+    }
+}
+
+fn position(reader: &Reader<&[u8]>) -> usize {
+    reader.buffer_position() as usize
+}
+
+/// Returns the sheet XML with the data filled in and the `dimension`
+/// element, which would no longer match the used range, left out.
+fn fill_sheet(context: &ExportContext, sheet: &str, target: &TemplateTarget, date_base: NaiveDateTime) -> Result<String> {
+    let mut reader = Reader::from_str(sheet);
+    let mut output = String::with_capacity(sheet.len() + context.data.len() * target.fields.len() * 40);
+    // Everything before this byte of `sheet` has been handled.
+    let mut copied = 0;
+
+    loop {
+        let start = position(&reader);
+        let event = reader.read_event()?;
+        let end = position(&reader);
+        match event {
+            Event::Empty(element) if is(&element, "dimension") => {
+                output.push_str(&sheet[copied..start]);
+                copied = end;
+            }
+            Event::Start(element) if is(&element, "sheetData") => {
+                output.push_str(&sheet[copied..end]);
+                let (mut rows, close) = read_rows(&mut reader, sheet)?;
+                fill_rows(context, &mut rows, target, date_base, &prefix(&element))?;
+                write_rows(&mut output, &rows, &prefix(&element));
+                copied = close;
+            }
+            Event::Empty(element) if is(&element, "sheetData") => {
+                let prefix = prefix(&element);
+                let mut rows = BTreeMap::new();
+                fill_rows(context, &mut rows, target, date_base, &prefix)?;
+                output.push_str(&sheet[copied..start]);
+                let _ = write!(output, "<{}sheetData>", prefix);
+                write_rows(&mut output, &rows, &prefix);
+                let _ = write!(output, "</{}sheetData>", prefix);
+                copied = end;
+            }
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+    output.push_str(&sheet[copied..]);
+    Ok(output)
+}
+
+/// Reads the rows of `sheetData`, returning them with the position of its end tag.
+fn read_rows(reader: &mut Reader<&[u8]>, sheet: &str) -> Result<(BTreeMap<u32, SheetRow>, usize)> {
+    let mut rows = BTreeMap::new();
+    let mut next_row = 0;
+    loop {
+        let start = position(reader);
+        let (element, has_cells) = match reader.read_event()? {
+            Event::Start(element) if is(&element, "row") => (element, true),
+            Event::Empty(element) if is(&element, "row") => (element, false),
+            Event::End(element) if element.local_name().as_ref() == b"sheetData" => return Ok((rows, start)),
+            Event::Eof => bail!("The template sheet ends inside its data"),
+            _ => continue,
+        };
+
+        let number = match attribute(&element, "r")? {
+            Some(number) => number.parse::<u32>().ok().and_then(|number| number.checked_sub(1)),
+            None => Some(next_row),
+        }
+        .ok_or_else(|| anyhow!("The template sheet has an invalid row number"))?;
+        next_row = number + 1;
+
+        let mut row = SheetRow::default();
+        for attribute in element.attributes() {
+            let attribute = attribute?;
+            let key = String::from_utf8_lossy(attribute.key.as_ref()).into_owned();
+            // Spans are only a loading hint and may no longer be right.
+            if key != "r" && key != "spans" {
+                row.attributes.push((key, String::from_utf8_lossy(&attribute.value).into_owned()));
+            }
+        }
+        if has_cells {
+            row.cells = read_cells(reader, sheet)?;
+        }
+        rows.insert(number, row);
+    }
+}
+
+/// Reads the cells of a row up to and including its end tag.
+fn read_cells(reader: &mut Reader<&[u8]>, sheet: &str) -> Result<BTreeMap<u16, SheetCell>> {
+    let mut cells = BTreeMap::new();
+    let mut next_col = 0;
+    loop {
+        let start = position(reader);
+        let (element, has_content) = match reader.read_event()? {
+            Event::Start(element) if is(&element, "c") => (element, read_cell_content(reader)?),
+            Event::Empty(element) if is(&element, "c") => (element, false),
+            Event::End(element) if element.local_name().as_ref() == b"row" => return Ok(cells),
+            Event::Eof => bail!("The template sheet ends inside a row"),
+            _ => continue,
+        };
+
+        let col = match attribute(&element, "r")? {
+            Some(reference) => reference_column(&reference)
+                .ok_or_else(|| anyhow!("The template sheet has an invalid cell reference \"{}\"", reference))?,
+            None => next_col,
+        };
+        next_col = col + 1;
+        let cell = SheetCell {
+            style: attribute(&element, "s")?,
+            has_content,
+            xml: sheet[start..position(reader)].to_string(),
+        };
+        cells.insert(col, cell);
+    }
+}
+
+/// Reads the rest of a cell up to and including its end tag, returning
+/// whether it has a value, inline string or formula.
+fn read_cell_content(reader: &mut Reader<&[u8]>) -> Result<bool> {
+    let mut has_content = false;
+    loop {
+        match reader.read_event()? {
+            Event::Start(element) | Event::Empty(element)
+                if is(&element, "v") || is(&element, "is") || is(&element, "f") =>
+            {
+                has_content = true;
+            }
+            Event::End(element) if element.local_name().as_ref() == b"c" => return Ok(has_content),
+            Event::Eof => bail!("The template sheet ends inside a cell"),
+            _ => {}
+        }
+    }
+}
+
+/// Puts the data into the rows. A filled cell keeps the template's style for
+/// that cell, or else takes the style of the first data row, so formatting
+/// that row formats every row. Fails rather than overwrite a template cell
+/// that holds a value or formula, such as a totals row below the data.
+fn fill_rows(
+    context: &ExportContext,
+    rows: &mut BTreeMap<u32, SheetRow>,
+    target: &TemplateTarget,
+    date_base: NaiveDateTime,
+    prefix: &str,
+) -> Result<()> {
+    let first_row = rows.get(&target.row);
+    let row_attributes = first_row.map(|row| row.attributes.clone()).unwrap_or_default();
+    let first_styles: Vec<Option<String>> = (0..target.fields.len())
+        .map(|offset| {
+            let col = target.col + offset as u16;
+            first_row.and_then(|row| row.cells.get(&col)).and_then(|cell| cell.style.clone())
+        })
+        .collect();
+
+    for (index, item) in context.data.iter().enumerate() {
+        let number = target.row + index as u32;
+        let row = rows.entry(number).or_insert_with(|| SheetRow {
+            attributes: row_attributes.clone(),
+            cells: BTreeMap::new(),
+        });
+        for (offset, field) in target.fields.iter().enumerate() {
+            let Some(field) = field else { continue };
+            let col = target.col + offset as u16;
+            let existing = row.cells.get(&col);
+            if existing.is_some_and(|cell| cell.has_content) {
+                bail!(
+                    "Cell {} of \"{}\" is not empty and would be overwritten by record {} of {}; clear it or move it below the data",
+                    row_col_to_cell(number, col),
+                    target.sheet,
+                    index + 1,
+                    context.data.len()
+                );
+            }
+            let style = existing.and_then(|cell| cell.style.clone()).or_else(|| first_styles[offset].clone());
+            let xml = cell_xml(prefix, number, col, style.as_deref(), item, *field, date_base);
+            row.cells.insert(
+                col,
+                SheetCell {
+                    style,
+                    has_content: true,
+                    xml,
+                },
+            );
+        }
+        if index % PROGRESS_INTERVAL == 0 {
+            context.progress.set(index)?;
+        }
+    }
+    context.progress.set(context.data.len())
+}
+
+fn cell_xml(
+    prefix: &str,
+    row: u32,
+    col: u16,
+    style: Option<&str>,
+    item: &TableData,
+    field: Field,
+    date_base: NaiveDateTime,
+) -> String {
+    let reference = row_col_to_cell(row, col);
+    let style = style.map(|style| format!(" s=\"{}\"", escape(style))).unwrap_or_default();
+    let number = match field {
+        Field::Name => {
+            return format!(
+                "<{p}c r=\"{}\"{} t=\"inlineStr\"><{p}is><{p}t xml:space=\"preserve\">{}</{p}t></{p}is></{p}c>",
+                reference,
+                style,
+                escape(escape_string(&item.name)),
+                p = prefix
+            );
+        }
+        Field::Id => f64::from(item.id),
+        Field::Value => item.value,
+        // Excel dates are days since its epoch, with the time of day as the fraction.
+        Field::Date => (item.date.naive_local() - date_base).num_milliseconds() as f64 / 86_400_000.0,
+    };
+    if number.is_finite() {
+        format!("<{p}c r=\"{}\"{}><{p}v>{}</{p}v></{p}c>", reference, style, number, p = prefix)
+    } else {
+        format!("<{p}c r=\"{}\"{} t=\"e\"><{p}v>#NUM!</{p}v></{p}c>", reference, style, p = prefix)
+    }
+}
+
+fn write_rows(output: &mut String, rows: &BTreeMap<u32, SheetRow>, prefix: &str) {
+    for (number, row) in rows {
+        let _ = write!(output, "<{}row r=\"{}\"", prefix, number + 1);
+        for (key, value) in &row.attributes {
+            let _ = write!(output, " {}=\"{}\"", key, value);
+        }
+        if row.cells.is_empty() {
+            output.push_str("/>");
+            continue;
+        }
+        output.push('>');
+        for cell in row.cells.values() {
+            output.push_str(&cell.xml);
+        }
+        let _ = write!(output, "</{}row>", prefix);
+    }
+}
+
+/// Sets `fullCalcOnLoad` on the workbook's calculation properties.
+fn recalculate_on_load(workbook: &str) -> String {
+    if let Some(start) = workbook.find("<calcPr") {
+        let end = workbook[start..].find('>').map_or(workbook.len(), |end| start + end);
+        if workbook[start..end].contains("fullCalcOnLoad") {
+            let properties = workbook[start..end]
+                .replace("fullCalcOnLoad=\"0\"", "fullCalcOnLoad=\"1\"")
+                .replace("fullCalcOnLoad=\"false\"", "fullCalcOnLoad=\"1\"");
+            return format!("{}{}{}", &workbook[..start], properties, &workbook[end..]);
+        }
+        let insert = start + "<calcPr".len();
+        return format!("{} fullCalcOnLoad=\"1\"{}", &workbook[..insert], &workbook[insert..]);
+    }
+    let insert = AFTER_CALC_PR
+        .iter()
+        .filter_map(|tag| workbook.find(tag))
+        .min()
+        .unwrap_or(workbook.len());
+    format!("{}<calcPr fullCalcOnLoad=\"1\"/>{}", &workbook[..insert], &workbook[insert..])
+}
+
+/// Removes the self-closing `tag` elements that contain `needle`.
+fn remove_elements(xml: &str, tag: &str, needle: &str) -> String {
+    let open = format!("<{} ", tag);
+    let mut result = String::with_capacity(xml.len());
+    let mut rest = xml;
+    while let Some(start) = rest.find(&open) {
+        let Some(length) = rest[start..].find("/>") else { break };
+        let end = start + length + 2;
+        result.push_str(&rest[..start]);
+        if !rest[start..end].contains(needle) {
+            result.push_str(&rest[start..end]);
+        }
+        rest = &rest[end..];
+    }
+    result.push_str(rest);
+    result
+}
+
+/// A cell reference such as `$B$2`, zero-based.
+struct CellRef {
+    col: u16,
+    row: u32,
+    absolute_col: bool,
+    absolute_row: bool,
+}
+
+impl CellRef {
+    fn parse(text: &str) -> Option<Self> {
+        let (absolute_col, text) = match text.strip_prefix('$') {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        let letters = text.len() - text.trim_start_matches(|c: char| c.is_ascii_uppercase()).len();
+        if !(1..=3).contains(&letters) {
+            return None;
+        }
+        let (column, text) = text.split_at(letters);
+        let (absolute_row, digits) = match text.strip_prefix('$') {
+            Some(rest) => (true, rest),
+            None => (false, text),
+        };
+        if digits.is_empty() || !digits.bytes().all(|digit| digit.is_ascii_digit()) {
+            return None;
+        }
+        let row = digits.parse::<u32>().ok()?.checked_sub(1).filter(|&row| (row as usize) < MAX_ROWS)?;
+        let col = column_name_to_number(column);
+        ((col as usize) < MAX_COLUMNS).then_some(Self {
+            col,
+            row,
+            absolute_col,
+            absolute_row,
+        })
+    }
+
+    fn format(&self) -> String {
+        format!(
+            "{}{}{}{}",
+            if self.absolute_col { "$" } else { "" },
+            column_number_to_name(self.col),
+            if self.absolute_row { "$" } else { "" },
+            self.row + 1
+        )
+    }
+}
+
+/// The filled area of the target sheet. Ranges that start at or above its
+/// first row and end inside it are grown to end on its last row, as Excel
+/// does when rows are inserted into a range, so tables, names, formulas and
+/// conditional formats sized for the template's sample rows cover the data.
+struct Growth<'a> {
+    sheet: &'a str,
+    first_row: u32,
+    last_row: u32,
+    first_col: u16,
+    last_col: u16,
+}
+
+impl<'a> Growth<'a> {
+    /// `None` when there is no data, which leaves every range as it is.
+    fn new(target: &'a TemplateTarget, count: usize) -> Option<Self> {
+        let last_row = (target.row as usize + count).checked_sub(1).filter(|_| count > 0)?;
+        Some(Self {
+            sheet: &target.sheet,
+            first_row: target.row,
+            last_row: last_row as u32,
+            first_col: target.col,
+            last_col: target.col + target.fields.len().saturating_sub(1) as u16,
+        })
+    }
+
+    fn parse_range(range: &str) -> Option<(CellRef, CellRef)> {
+        let (start, end) = range.split_once(':')?;
+        Some((CellRef::parse(start)?, CellRef::parse(end)?))
+    }
+
+    fn overlaps_columns(&self, start: &CellRef, end: &CellRef) -> bool {
+        start.col.min(end.col) <= self.last_col && start.col.max(end.col) >= self.first_col
+    }
+
+    /// The grown form of a range such as `B2:B10`, or `None` when it stays.
+    fn grow_range(&self, range: &str) -> Option<String> {
+        let (start, mut end) = Self::parse_range(range)?;
+        let grows = self.overlaps_columns(&start, &end)
+            && start.row <= self.first_row
+            && (self.first_row..self.last_row).contains(&end.row);
+        grows.then(|| {
+            end.row = self.last_row;
+            format!("{}:{}", start.format(), end.format())
+        })
+    }
+
+    /// Whether a range or single cell lies partly inside the filled rows.
+    fn overlaps_data(&self, range: &str) -> bool {
+        let (start, end) = range.split_once(':').unwrap_or((range, range));
+        let (Some(start), Some(end)) = (CellRef::parse(start), CellRef::parse(end)) else {
+            return false;
+        };
+        self.overlaps_columns(&start, &end)
+            && start.row.min(end.row) <= self.last_row
+            && start.row.max(end.row) >= self.first_row
+    }
+
+    fn is_target(&self, sheet: &str) -> bool {
+        sheet.to_lowercase() == self.sheet.to_lowercase()
+    }
+
+    /// Grows the ranges of a formula or range list that refer to the target
+    /// sheet. Unqualified ranges belong to `own_sheet`; text in string
+    /// literals is left alone.
+    fn grow_formula(&self, formula: &str, own_sheet: Option<&str>) -> String {
+        let is_word = |byte: u8| byte.is_ascii_alphanumeric() || matches!(byte, b'_' | b'.' | b'$' | b':' | b'!') || byte >= 0x80;
+        let bytes = formula.as_bytes();
+        let mut output = String::with_capacity(formula.len());
+        let mut copied = 0;
+        let mut quoted_sheet: Option<String> = None;
+        let mut i = 0;
+
+        while i < bytes.len() {
+            match bytes[i] {
+                b'"' => {
+                    i += 1;
+                    while i < bytes.len() {
+                        if bytes[i] == b'"' {
+                            if bytes.get(i + 1) != Some(&b'"') {
+                                break;
+                            }
+                            i += 1;
+                        }
+                        i += 1;
+                    }
+                    i += 1;
+                    quoted_sheet = None;
+                }
+                b'\'' => {
+                    let start = i + 1;
+                    i += 1;
+                    while i < bytes.len() && !(bytes[i] == b'\'' && bytes.get(i + 1) != Some(&b'\'')) {
+                        i += if bytes[i] == b'\'' { 2 } else { 1 };
+                    }
+                    let name = formula[start..i.min(bytes.len())].replace("''", "'");
+                    i += 1;
+                    quoted_sheet = None;
+                    if bytes.get(i) == Some(&b'!') {
+                        i += 1;
+                        quoted_sheet = Some(name);
+                    }
+                }
+                byte if is_word(byte) => {
+                    let start = i;
+                    while i < bytes.len() && is_word(bytes[i]) {
+                        i += 1;
+                    }
+                    let word = &formula[start..i];
+                    let (sheet, range) = match quoted_sheet.take() {
+                        Some(sheet) => (Some(sheet), word),
+                        None => match word.rsplit_once('!') {
+                            Some((sheet, range)) => (Some(sheet.to_string()), range),
+                            None => (None, word),
+                        },
+                    };
+                    let on_target = match sheet {
+                        Some(sheet) => self.is_target(&sheet),
+                        None => own_sheet.is_some_and(|own| self.is_target(own)),
+                    };
+                    if on_target && let Some(grown) = self.grow_range(range) {
+                        output.push_str(&formula[copied..i - range.len()]);
+                        output.push_str(&grown);
+                        copied = i;
+                    }
+                }
+                _ => {
+                    i += 1;
+                    quoted_sheet = None;
+                }
+            }
+        }
+        output.push_str(&formula[copied..]);
+        output
+    }
+
+    /// Grows the formulas, defined names and range attributes of a package
+    /// part. Fails on merged cells in the data area of the target sheet, and
+    /// on a table with a totals row that the data would overwrite.
+    fn grow_part(&self, xml: &str, own_sheet: Option<&str>) -> Result<String> {
+        let on_target = own_sheet.is_some_and(|own| self.is_target(own));
+        let mut reader = Reader::from_str(xml);
+        let mut output = String::with_capacity(xml.len());
+        let mut copied = 0;
+        let mut in_formula = false;
+
+        loop {
+            let start = position(&reader);
+            let event = reader.read_event()?;
+            let end = position(&reader);
+            let opens = matches!(event, Event::Start(_));
+            match event {
+                Event::Start(element) | Event::Empty(element) => {
+                    let local_name = String::from_utf8_lossy(element.local_name().as_ref()).into_owned();
+                    if on_target && local_name == "mergeCell" {
+                        let range = attribute(&element, "ref")?.unwrap_or_default();
+                        if self.overlaps_data(&range) {
+                            bail!(
+                                "The template merges cells {} in the data area of \"{}\"; unmerge them first",
+                                range,
+                                self.sheet
+                            );
+                        }
+                    }
+                    in_formula = opens && FORMULA_ELEMENTS.contains(&local_name.as_str());
+
+                    let mut tag = xml[start..end].to_string();
+                    for (_, key) in RANGE_ATTRIBUTES.iter().filter(|(name, _)| *name == local_name) {
+                        let Some(value) = attribute(&element, key)? else { continue };
+                        let grown = self.grow_formula(&value, own_sheet);
+                        if grown == value {
+                            continue;
+                        }
+                        if local_name == "table"
+                            && attribute(&element, "totalsRowCount")?.is_some_and(|count| count != "0")
+                        {
+                            bail!(
+                                "Table \"{}\" on \"{}\" has a totals row, which the data would overwrite; remove it from the template",
+                                attribute(&element, "displayName")?.unwrap_or_default(),
+                                self.sheet
+                            );
+                        }
+                        for quote in ['"', '\''] {
+                            let old = format!("{}={}{}{}", key, quote, escape(&value), quote);
+                            if tag.contains(&old) {
+                                tag = tag.replacen(&old, &format!("{}={}{}{}", key, quote, escape(&grown), quote), 1);
+                                break;
+                            }
+                        }
+                    }
+                    if tag != xml[start..end] {
+                        output.push_str(&xml[copied..start]);
+                        output.push_str(&tag);
+                        copied = end;
+                    }
+                }
+                Event::Text(text) if in_formula => {
+                    let formula = text.unescape()?;
+                    let grown = self.grow_formula(&formula, own_sheet);
+                    if grown != formula {
+                        output.push_str(&xml[copied..start]);
+                        output.push_str(&escape(&grown));
+                        copied = end;
+                    }
+                }
+                Event::End(_) => in_formula = false,
+                Event::Eof => break,
+                _ => {}
+            }
+        }
+        output.push_str(&xml[copied..]);
+        Ok(output)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::testing::{self, TempDir};
+    use super::super::xlsx::{self, Package};
+    use super::super::{ExcelExporter, ExportOptions};
+    use super::*;
+    use rust_xlsxwriter::{Table, TableColumn, Workbook};
+
+    /// Data filled into Data!B2:C21, 20 rows.
+    fn target() -> TemplateTarget {
+        TemplateTarget {
+            sheet: "Data".to_string(),
+            row: 1,
+            col: 1,
+            fields: vec![Some(Field::Name), Some(Field::Value)],
+        }
+    }
+
+    #[test]
+    fn ranges_into_the_data_grow_to_its_last_row() {
+        let target = target();
+        let growth = Growth::new(&target, 20).unwrap();
+        assert_eq!(growth.grow_formula("SUM(C2:C10)", Some("Data")), "SUM(C2:C21)");
+        assert_eq!(growth.grow_formula("SUM($C$1:$C$3)*2", Some("Data")), "SUM($C$1:$C$21)*2");
+        assert_eq!(growth.grow_formula("B1:C2 E1:E5", Some("Data")), "B1:C21 E1:E5");
+        assert_eq!(growth.grow_formula("Data!$B$2:$B$5", None), "Data!$B$2:$B$21");
+        assert_eq!(growth.grow_formula("'Data'!B2:B5", Some("Other")), "'Data'!B2:B21");
+    }
+
+    #[test]
+    fn other_ranges_are_left_alone() {
+        let target = target();
+        let growth = Growth::new(&target, 20).unwrap();
+        for formula in [
+            "SUM(C2:C50)",
+            "SUM(C5:C10)",
+            "SUM(E2:E10)",
+            "SUM(C:C)",
+            "COUNTIF(E2:E10,\"C2:C3\")",
+            "LOG10(C2)",
+        ] {
+            assert_eq!(growth.grow_formula(formula, Some("Data")), formula);
+        }
+        assert_eq!(growth.grow_formula("SUM(C2:C10)", Some("Other")), "SUM(C2:C10)");
+        assert_eq!(growth.grow_formula("Other!C2:C10", Some("Data")), "Other!C2:C10");
+        assert!(Growth::new(&target, 0).is_none());
+    }
+
+    #[test]
+    fn parts_get_grown_attributes_and_formulas() {
+        let target = target();
+        let growth = Growth::new(&target, 20).unwrap();
+        let table = r#"<table ref="B1:C4" displayName="Sales"><autoFilter ref="B1:C4"/></table>"#;
+        assert_eq!(
+            growth.grow_part(table, Some("Data")).unwrap(),
+            r#"<table ref="B1:C21" displayName="Sales"><autoFilter ref="B1:C21"/></table>"#
+        );
+        let sheet = r#"<worksheet><c r="E1"><f>SUM(C2:C4)&amp;""</f></c><conditionalFormatting sqref="C2:C4"/></worksheet>"#;
+        assert_eq!(
+            growth.grow_part(sheet, Some("Data")).unwrap(),
+            r#"<worksheet><c r="E1"><f>SUM(C2:C21)&amp;&quot;&quot;</f></c><conditionalFormatting sqref="C2:C21"/></worksheet>"#
+        );
+        let names = r#"<definedNames><definedName name="Values">Data!$C$2:$C$4</definedName></definedNames>"#;
+        assert!(growth.grow_part(names, None).unwrap().contains("Data!$C$2:$C$21"));
+    }
+
+    #[test]
+    fn merged_cells_and_totals_rows_in_the_data_fail() {
+        let target = target();
+        let growth = Growth::new(&target, 20).unwrap();
+        let merged = r#"<mergeCells><mergeCell ref="B5:C5"/></mergeCells>"#;
+        assert!(growth.grow_part(merged, Some("Data")).is_err());
+        let header = r#"<mergeCells><mergeCell ref="B1:C1"/></mergeCells>"#;
+        assert!(growth.grow_part(header, Some("Data")).is_ok());
+        let totals = r#"<table ref="B1:C4" totalsRowCount="1"/>"#;
+        assert!(growth.grow_part(totals, Some("Data")).is_err());
+    }
+
+    #[test]
+    fn relationship_targets_resolve_against_the_sheet_directory() {
+        assert_eq!(resolve_part("xl/worksheets", "../tables/table1.xml"), "xl/tables/table1.xml");
+        assert_eq!(resolve_part("xl/worksheets", "/xl/tables/table2.xml"), "xl/tables/table2.xml");
+    }
+
+    /// A template with a two-row table at Data!B1:C3, a total beside it and
+    /// a formula on another sheet that sums the table's values.
+    fn write_template(path: &Path, total_below: bool) {
+        let mut workbook = Workbook::new();
+        let data = workbook.add_worksheet().set_name("Data").unwrap();
+        let table = Table::new()
+            .set_name("Sales")
+            .set_columns(&[TableColumn::new().set_header("Name"), TableColumn::new().set_header("Value")]);
+        data.add_table(0, 1, 2, 2, &table).unwrap();
+        data.write_formula(0, 4, "=SUM(C2:C3)").unwrap();
+        if total_below {
+            data.write_formula(3, 2, "=SUM(C2:C3)").unwrap();
+        }
+        let summary = workbook.add_worksheet().set_name("Summary").unwrap();
+        summary.write_formula(0, 0, "=SUM(Data!C2:C3)").unwrap();
+        workbook.save(path).unwrap();
+    }
+
+    /// Fills Data!B2 onwards with names and values, as set up in the export dialog.
+    fn fill_template(data: &[TableData], template: &Path, path: &Path) -> Result<()> {
+        let mut options = ExportOptions::default();
+        options.set("template", template.to_string_lossy());
+        options.set("template_start", "Data!B2");
+        options.set("template_columns", "Name, Value");
+        testing::export(&ExcelExporter::new(), data, &options, path)
+    }
+
+    fn open(path: &Path) -> Package {
+        ZipArchive::new(Cursor::new(fs::read(path).unwrap())).unwrap()
+    }
+
+    fn sheet_xml(package: &mut Package, name: &str) -> String {
+        let workbook = read_part(package, WORKBOOK_PART).unwrap();
+        let relationships = read_part(package, WORKBOOK_RELS_PART).unwrap();
+        let part = sheet_part(&workbook, &relationships, name).unwrap();
+        read_part(package, &part).unwrap()
+    }
+
+    #[test]
+    fn filling_grows_the_table_and_the_formulas_over_it() {
+        let dir = TempDir::new();
+        let (template, output) = (dir.join("template.xlsx"), dir.join("filled.xlsx"));
+        write_template(&template, false);
+        let mut data = testing::records();
+        data.push(testing::record(4, "Tab\tand\u{1}control", 2.0, (2024, 3, 1)));
+        data.push(testing::record(5, "_x000D_ & <literal>", 3.0, (2024, 3, 2)));
+
+        fill_template(&data, &template, &output).unwrap();
+
+        let mut package = open(&output);
+        let sheet = sheet_xml(&mut package, "Data");
+        let mut rows = BTreeMap::new();
+        xlsx::read_rows(&sheet, &xlsx::shared_strings(&mut package).unwrap(), |row, cells| {
+            rows.insert(row, cells.clone());
+            Ok(())
+        })
+        .unwrap();
+        assert_eq!(rows[&0][&1], "Name");
+        for (index, item) in data.iter().enumerate() {
+            let row = &rows[&(index as u32 + 1)];
+            assert_eq!(row[&1], item.name);
+            assert_eq!(row[&2].parse::<f64>().unwrap(), item.value);
+        }
+
+        assert!(sheet.contains("_x0001_control") && sheet.contains("_x005F_x000D_"), "{}", sheet);
+        assert!(sheet.contains("<f>SUM(C2:C6)</f>"), "{}", sheet);
+        assert!(sheet_xml(&mut package, "Summary").contains("<f>SUM(Data!C2:C6)</f>"));
+        let table = read_part(&mut package, "xl/tables/table1.xml").unwrap();
+        assert!(table.contains(r#"ref="B1:C6""#), "{}", table);
+        assert!(read_part(&mut package, WORKBOOK_PART).unwrap().contains("fullCalcOnLoad=\"1\""));
+    }
+
+    #[test]
+    fn data_that_would_overwrite_template_cells_fails_and_keeps_the_old_file() {
+        let dir = TempDir::new();
+        let (template, output) = (dir.join("template.xlsx"), dir.join("filled.xlsx"));
+        write_template(&template, true);
+        fs::write(&output, b"previous export").unwrap();
+
+        // Two records fit above the total in C4.
+        fill_template(&testing::records()[..2], &template, &output).unwrap();
+        assert!(sheet_xml(&mut open(&output), "Data").contains("<f>SUM(C2:C3)</f>"));
+
+        fs::write(&output, b"previous export").unwrap();
+        let error = fill_template(&testing::records(), &template, &output).unwrap_err();
+        assert!(error.to_string().contains("Cell C4 of \"Data\""), "{}", error);
+        assert_eq!(fs::read(&output).unwrap(), b"previous export");
+        assert_eq!(dir.files(), ["filled.xlsx", "template.xlsx"]);
+    }
+}
@@ -0,0 +1,248 @@
+//! Reading the parts of an .xlsx package, for filling templates and importing exports.
+
+use anyhow::{Result, anyhow, bail};
+use quick_xml::Reader;
+use quick_xml::events::{BytesStart, Event};
+use rust_xlsxwriter::column_name_to_number;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::io::{Cursor, Read};
+use zip::ZipArchive;
+
+pub const WORKBOOK_PART: &str = "xl/workbook.xml";
+pub const WORKBOOK_RELS_PART: &str = "xl/_rels/workbook.xml.rels";
+const SHARED_STRINGS_PART: &str = "xl/sharedStrings.xml";
+
+pub type Package = ZipArchive<Cursor<Vec<u8>>>;
+
+/// Cell text of a row by zero-based column.
+pub type Row = BTreeMap<u16, String>;
+
+pub fn read_part(package: &mut Package, name: &str) -> Result<String> {
+    let mut file = package.by_name(name).map_err(|_| anyhow!("The workbook has no {}", name))?;
+    let mut content = String::new();
+    file.read_to_string(&mut content)?;
+    Ok(content)
+}
+
+/// Unescaped value of the attribute with the given local name, ignoring namespace prefixes.
+pub fn attribute(element: &BytesStart, name: &str) -> Result<Option<String>> {
+    for attribute in element.attributes() {
+        let attribute = attribute?;
+        if attribute.key.local_name().as_ref() == name.as_bytes() {
+            return Ok(Some(attribute.unescape_value()?.into_owned()));
+        }
+    }
+    Ok(None)
+}
+
+pub fn is(element: &BytesStart, local_name: &str) -> bool {
+    element.local_name().as_ref() == local_name.as_bytes()
+}
+
+/// Names of the workbook's sheets, in order, with their relationship IDs.
+fn sheets(workbook: &str) -> Result<Vec<(String, String)>> {
+    let mut sheets = Vec::new();
+    let mut reader = Reader::from_str(workbook);
+    loop {
+        match reader.read_event()? {
+            Event::Start(element) | Event::Empty(element) if is(&element, "sheet") => {
+                if let (Some(sheet), Some(id)) = (attribute(&element, "name")?, attribute(&element, "id")?) {
+                    sheets.push((sheet, id));
+                }
+            }
+            Event::Eof => return Ok(sheets),
+            _ => {}
+        }
+    }
+}
+
+pub fn sheet_names(workbook: &str) -> Result<Vec<String>> {
+    Ok(sheets(workbook)?.into_iter().map(|(sheet, _)| sheet).collect())
+}
+
+pub fn has_sheet(workbook: &str, name: &str) -> Result<bool> {
+    Ok(sheets(workbook)?.iter().any(|(sheet, _)| sheet == name))
+}
+
+/// Path inside the package of the worksheet called `name`.
+pub fn sheet_part(workbook: &str, relationships: &str, name: &str) -> Result<String> {
+    let sheets = sheets(workbook)?;
+    let Some((_, id)) = sheets.iter().find(|(sheet, _)| sheet == name) else {
+        let names: Vec<&str> = sheets.iter().map(|(sheet, _)| sheet.as_str()).collect();
+        bail!("The workbook has no sheet \"{}\"; its sheets are: {}", name, names.join(", "));
+    };
+
+    let mut reader = Reader::from_str(relationships);
+    loop {
+        match reader.read_event()? {
+            Event::Start(element) | Event::Empty(element)
+                if is(&element, "Relationship") && attribute(&element, "Id")?.as_ref() == Some(id) =>
+            {
+                let target = attribute(&element, "Target")?
+                    .ok_or_else(|| anyhow!("Sheet \"{}\" has no target", name))?;
+                return Ok(match target.strip_prefix('/') {
+                    Some(absolute) => absolute.to_string(),
+                    None => format!("xl/{}", target),
+                });
+            }
+            Event::Eof => bail!("Sheet \"{}\" has no relationship", name),
+            _ => {}
+        }
+    }
+}
+
+/// Column of a cell reference such as `B12`.
+pub fn reference_column(reference: &str) -> Option<u16> {
+    let letters = reference.trim_end_matches(|c: char| c.is_ascii_digit());
+    if letters.is_empty() || !letters.chars().all(|c| c.is_ascii_uppercase()) {
+        return None;
+    }
+    Some(column_name_to_number(letters))
+}
+
+/// Encodes the control characters XML cannot hold as `_xHHHH_`, as Excel does,
+/// and escapes the `_` of literal `_xHHHH_` text as `_x005F_` so it reads back
+/// unchanged. The result still needs XML escaping.
+pub fn escape_string(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    for (index, c) in text.char_indices() {
+        match c {
+            '\t' | '\n' => result.push(c),
+            '\0'..='\x1F' | '\u{FFFE}' | '\u{FFFF}' => {
+                let _ = write!(result, "_x{:04X}_", c as u32);
+            }
+            '_' if is_escape(&text[index..]) => result.push_str("_x005F_"),
+            c => result.push(c),
+        }
+    }
+    result
+}
+
+/// Whether `text` starts with an escape such as `_x000D_`.
+fn is_escape(text: &str) -> bool {
+    let bytes = text.as_bytes();
+    bytes.len() >= 7
+        && bytes.starts_with(b"_x")
+        && bytes[2..6].iter().all(u8::is_ascii_hexdigit)
+        && bytes[6] == b'_'
+}
+
+/// Decodes the `_xHHHH_` escapes Excel uses for control characters in strings.
+fn unescape_string(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(start) = rest.find("_x") {
+        let escape = rest[start + 2..]
+            .get(..5)
+            .filter(|escape| escape.ends_with('_'))
+            .and_then(|escape| u32::from_str_radix(&escape[..4], 16).ok())
+            .and_then(char::from_u32);
+        result.push_str(&rest[..start]);
+        match escape {
+            Some(c) => {
+                result.push(c);
+                rest = &rest[start + 7..];
+            }
+            None => {
+                result.push_str("_x");
+                rest = &rest[start + 2..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+/// The workbook's shared string table; empty when strings are stored inline.
+pub fn shared_strings(package: &mut Package) -> Result<Vec<String>> {
+    if package.by_name(SHARED_STRINGS_PART).is_err() {
+        return Ok(Vec::new());
+    }
+    let xml = read_part(package, SHARED_STRINGS_PART)?;
+    let mut strings = Vec::new();
+    let mut reader = Reader::from_str(&xml);
+    let mut current = String::new();
+    let mut in_text = false;
+    // Phonetic hints repeat part of the string and are skipped.
+    let mut in_phonetic = false;
+    loop {
+        match reader.read_event()? {
+            Event::Start(element) if is(&element, "si") => current.clear(),
+            Event::Start(element) if is(&element, "rPh") => in_phonetic = true,
+            Event::End(element) if element.local_name().as_ref() == b"rPh" => in_phonetic = false,
+            Event::Start(element) if is(&element, "t") => in_text = !in_phonetic,
+            Event::End(element) if element.local_name().as_ref() == b"t" => in_text = false,
+            Event::Text(text) if in_text => current.push_str(&text.unescape()?),
+            Event::End(element) if element.local_name().as_ref() == b"si" => strings.push(unescape_string(&current)),
+            Event::Eof => return Ok(strings),
+            _ => {}
+        }
+    }
+}
+
+/// Calls `on_row` with the zero-based number and the cell text by column of
+/// every row of a sheet that has cells. Strings are resolved, numbers are
+/// given as written.
+pub fn read_rows(
+    sheet: &str,
+    shared_strings: &[String],
+    mut on_row: impl FnMut(u32, &Row) -> Result<()>,
+) -> Result<()> {
+    let mut reader = Reader::from_str(sheet);
+    let mut cells = BTreeMap::new();
+    let (mut row, mut next_row) = (0, 0);
+    let (mut col, mut next_col) = (0, 0);
+    let mut cell_type = None;
+    let mut text = String::new();
+    let mut in_text = false;
+    let mut in_phonetic = false;
+
+    loop {
+        match reader.read_event()? {
+            Event::Start(element) if is(&element, "row") => {
+                row = match attribute(&element, "r")? {
+                    Some(number) => number.parse::<u32>().ok().and_then(|number| number.checked_sub(1)),
+                    None => Some(next_row),
+                }
+                .ok_or_else(|| anyhow!("The sheet has an invalid row number"))?;
+                next_row = row + 1;
+                next_col = 0;
+                cells.clear();
+            }
+            Event::End(element) if element.local_name().as_ref() == b"row" => on_row(row, &cells)?,
+            Event::Start(element) if is(&element, "c") => {
+                col = match attribute(&element, "r")? {
+                    Some(reference) => reference_column(&reference)
+                        .ok_or_else(|| anyhow!("The sheet has an invalid cell reference \"{}\"", reference))?,
+                    None => next_col,
+                };
+                next_col = col + 1;
+                cell_type = attribute(&element, "t")?;
+                text.clear();
+            }
+            Event::Empty(element) if is(&element, "c") => next_col += 1,
+            Event::Start(element) if is(&element, "rPh") => in_phonetic = true,
+            Event::End(element) if element.local_name().as_ref() == b"rPh" => in_phonetic = false,
+            Event::Start(element) if is(&element, "v") || is(&element, "t") => in_text = !in_phonetic,
+            Event::End(element) if matches!(element.local_name().as_ref(), b"v" | b"t") => in_text = false,
+            Event::Text(content) if in_text => text.push_str(&content.unescape()?),
+            Event::End(element) if element.local_name().as_ref() == b"c" => {
+                let value = match cell_type.as_deref() {
+                    Some("s") => text
+                        .trim()
+                        .parse::<usize>()
+                        .ok()
+                        .and_then(|index| shared_strings.get(index))
+                        .cloned()
+                        .ok_or_else(|| anyhow!("The sheet refers to a missing shared string"))?,
+                    Some("inlineStr") | Some("str") => unescape_string(&text),
+                    _ => text.clone(),
+                };
+                cells.insert(col, value);
+            }
+            Event::Eof => return Ok(()),
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,223 @@
+//! Regional formats for the numbers and dates shown in the UI and written by exports.
+
+use chrono::{DateTime, Local, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub enum Locale {
+    /// Decimal point and ISO dates, e.g. 1234.56 and 2024-01-31.
+    #[default]
+    Standard,
+    EnglishUs,
+    EnglishUk,
+    German,
+    French,
+    Czech,
+}
+
+impl Locale {
+    pub const ALL: [Locale; 6] = [
+        Locale::Standard,
+        Locale::EnglishUs,
+        Locale::EnglishUk,
+        Locale::German,
+        Locale::French,
+        Locale::Czech,
+    ];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Locale::Standard => "Standard (ISO)",
+            Locale::EnglishUs => "English (US)",
+            Locale::EnglishUk => "English (UK)",
+            Locale::German => "Deutsch",
+            Locale::French => "Français",
+            Locale::Czech => "Čeština",
+        }
+    }
+
+    pub fn decimal_separator(&self) -> char {
+        match self {
+            Locale::Standard | Locale::EnglishUs | Locale::EnglishUk => '.',
+            Locale::German | Locale::French | Locale::Czech => ',',
+        }
+    }
+
+    /// Separator between groups of thousands; the standard format has none.
+    pub fn group_separator(&self) -> Option<char> {
+        match self {
+            Locale::Standard => None,
+            Locale::EnglishUs | Locale::EnglishUk => Some(','),
+            Locale::German => Some('.'),
+            Locale::French | Locale::Czech => Some('\u{a0}'),
+        }
+    }
+
+    /// `chrono` format for dates.
+    pub fn date_format(&self) -> &'static str {
+        match self {
+            Locale::Standard => "%Y-%m-%d",
+            Locale::EnglishUs => "%m/%d/%Y",
+            Locale::EnglishUk | Locale::French => "%d/%m/%Y",
+            Locale::German | Locale::Czech => "%d.%m.%Y",
+        }
+    }
+
+    /// `chrono` format for a month, e.g. in timeline labels.
+    pub fn month_format(&self) -> &'static str {
+        match self {
+            Locale::Standard => "%Y-%m",
+            Locale::EnglishUs | Locale::EnglishUk | Locale::French => "%m/%Y",
+            Locale::German | Locale::Czech => "%m.%Y",
+        }
+    }
+
+    /// `chrono` format for dates with the time of day.
+    pub fn date_time_format(&self) -> &'static str {
+        match self {
+            Locale::Standard => "%Y-%m-%d %H:%M:%S",
+            Locale::EnglishUs => "%m/%d/%Y %I:%M:%S %p",
+            Locale::EnglishUk | Locale::French => "%d/%m/%Y %H:%M:%S",
+            Locale::German | Locale::Czech => "%d.%m.%Y %H:%M:%S",
+        }
+    }
+
+    /// Excel number format for dates.
+    pub fn excel_date_format(&self) -> &'static str {
+        match self {
+            Locale::Standard => "yyyy-mm-dd",
+            Locale::EnglishUs => "mm/dd/yyyy",
+            Locale::EnglishUk | Locale::French => "dd/mm/yyyy",
+            Locale::German | Locale::Czech => "dd.mm.yyyy",
+        }
+    }
+
+    /// Excel number format for dates with the time of day.
+    pub fn excel_date_time_format(&self) -> &'static str {
+        match self {
+            Locale::Standard => "yyyy-mm-dd hh:mm:ss",
+            Locale::EnglishUs => "mm/dd/yyyy h:mm:ss AM/PM",
+            Locale::EnglishUk | Locale::French => "dd/mm/yyyy hh:mm:ss",
+            Locale::German | Locale::Czech => "dd.mm.yyyy hh:mm:ss",
+        }
+    }
+
+    /// Number rounded to `decimals` places, with grouped thousands, for display.
+    pub fn format_number(&self, value: f64, decimals: usize) -> String {
+        if !value.is_finite() {
+            return value.to_string();
+        }
+        let text = format!("{:.*}", decimals, value.abs());
+        let (whole, fraction) = text.split_once('.').unwrap_or((&text, ""));
+        let mut result = String::with_capacity(text.len() + whole.len() / 3 + 1);
+        if value.is_sign_negative() && text.bytes().any(|digit| matches!(digit, b'1'..=b'9')) {
+            result.push('-');
+        }
+        result.push_str(&self.group(whole));
+        if !fraction.is_empty() {
+            result.push(self.decimal_separator());
+            result.push_str(fraction);
+        }
+        result
+    }
+
+    pub fn format_count(&self, count: usize) -> String {
+        self.group(&count.to_string())
+    }
+
+    /// Number with every digit kept and no grouping, for data files.
+    pub fn format_decimal(&self, value: f64) -> String {
+        let text = value.to_string();
+        match self.decimal_separator() {
+            '.' => text,
+            separator => text.replace('.', &separator.to_string()),
+        }
+    }
+
+    pub fn format_date(&self, date: &DateTime<Local>) -> String {
+        date.format(self.date_format()).to_string()
+    }
+
+    pub fn format_day(&self, date: NaiveDate) -> String {
+        date.format(self.date_format()).to_string()
+    }
+
+    pub fn format_month(&self, date: NaiveDate) -> String {
+        date.format(self.month_format()).to_string()
+    }
+
+    pub fn format_date_time(&self, date: &DateTime<Local>) -> String {
+        date.format(self.date_time_format()).to_string()
+    }
+
+    fn group(&self, digits: &str) -> String {
+        let Some(separator) = self.group_separator() else {
+            return digits.to_string();
+        };
+        let mut grouped = String::with_capacity(digits.len() + digits.len() / 3);
+        for (index, digit) in digits.chars().enumerate() {
+            if index > 0 && (digits.len() - index).is_multiple_of(3) {
+                grouped.push(separator);
+            }
+            grouped.push(digit);
+        }
+        grouped
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    #[test]
+    fn numbers_are_grouped_and_rounded() {
+        let value = 1_234_567.891;
+        assert_eq!(Locale::Standard.format_number(value, 2), "1234567.89");
+        assert_eq!(Locale::EnglishUs.format_number(value, 2), "1,234,567.89");
+        assert_eq!(Locale::German.format_number(value, 2), "1.234.567,89");
+        assert_eq!(Locale::French.format_number(value, 2), "1\u{a0}234\u{a0}567,89");
+        assert_eq!(Locale::EnglishUk.format_number(999.0, 0), "999");
+        assert_eq!(Locale::EnglishUk.format_number(1000.0, 0), "1,000");
+        assert_eq!(Locale::German.format_count(1_234_567), "1.234.567");
+        assert_eq!(Locale::Standard.format_count(1_234_567), "1234567");
+    }
+
+    #[test]
+    fn negative_numbers_keep_their_sign_unless_they_round_to_zero() {
+        assert_eq!(Locale::EnglishUs.format_number(-1234.5, 2), "-1,234.50");
+        assert_eq!(Locale::German.format_number(-123.456, 1), "-123,5");
+        assert_eq!(Locale::EnglishUs.format_number(-0.001, 2), "0.00");
+        assert_eq!(Locale::Czech.format_decimal(-1234.5678), "-1234,5678");
+    }
+
+    #[test]
+    fn non_finite_numbers_are_written_as_is() {
+        for locale in Locale::ALL {
+            assert_eq!(locale.format_number(f64::NAN, 2), "NaN");
+            assert_eq!(locale.format_number(f64::INFINITY, 2), "inf");
+            assert_eq!(locale.format_number(f64::NEG_INFINITY, 2), "-inf");
+            assert_eq!(locale.format_decimal(f64::NAN), "NaN");
+        }
+    }
+
+    #[test]
+    fn dates_follow_the_regional_order() {
+        let date = Local.with_ymd_and_hms(2024, 1, 31, 13, 5, 9).unwrap();
+        let day = date.date_naive();
+        let expected = [
+            (Locale::Standard, "2024-01-31", "2024-01", "2024-01-31 13:05:09"),
+            (Locale::EnglishUs, "01/31/2024", "01/2024", "01/31/2024 01:05:09 PM"),
+            (Locale::EnglishUk, "31/01/2024", "01/2024", "31/01/2024 13:05:09"),
+            (Locale::German, "31.01.2024", "01.2024", "31.01.2024 13:05:09"),
+            (Locale::French, "31/01/2024", "01/2024", "31/01/2024 13:05:09"),
+            (Locale::Czech, "31.01.2024", "01.2024", "31.01.2024 13:05:09"),
+        ];
+        for (locale, date_text, month, date_time) in expected {
+            assert_eq!(locale.format_date(&date), date_text);
+            assert_eq!(locale.format_day(day), date_text);
+            assert_eq!(locale.format_month(day), month);
+            assert_eq!(locale.format_date_time(&date), date_time);
+        }
+    }
+}
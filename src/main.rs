@@ -1,6 +1,15 @@
 mod app;
+mod cli;
 mod data;
 mod export;
+mod locale;
+mod remote;
+mod schedule;
+mod settings;
+mod snapshot;
+mod storage;
+mod sync;
+mod timeline;
 mod updater;
 
 use app::DesktopApp;
@@ -9,6 +18,11 @@ use app::DesktopApp;
 async fn main() -> Result<(), eframe::Error> {
     env_logger::init();
 
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
+
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
             .with_inner_size([1200.0, 800.0])
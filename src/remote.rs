@@ -0,0 +1,369 @@
+use crate::data::TableData;
+use anyhow::{Result, anyhow, bail};
+use reqwest::{RequestBuilder, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::time::Duration;
+
+/// How requests are authenticated. Secrets are kept in memory only and never
+/// written to the settings file, so they are entered once per session.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub enum AuthMethod {
+    #[default]
+    None,
+    Bearer {
+        #[serde(skip)]
+        token: String,
+    },
+    Basic {
+        username: String,
+        #[serde(skip)]
+        password: String,
+    },
+    Header {
+        name: String,
+        #[serde(skip)]
+        value: String,
+    },
+}
+
+impl AuthMethod {
+    pub fn label(&self) -> &'static str {
+        match self {
+            AuthMethod::None => "None",
+            AuthMethod::Bearer { .. } => "Bearer token",
+            AuthMethod::Basic { .. } => "Basic",
+            AuthMethod::Header { .. } => "Custom header",
+        }
+    }
+
+    /// Whether the secret still has to be entered this session.
+    pub fn missing_secret(&self) -> bool {
+        match self {
+            AuthMethod::None => false,
+            AuthMethod::Bearer { token } => token.is_empty(),
+            AuthMethod::Basic { password, .. } => password.is_empty(),
+            AuthMethod::Header { value, .. } => value.is_empty(),
+        }
+    }
+}
+
+/// Connection settings for a REST endpoint serving `TableData` records as JSON.
+///
+/// Records are read with `GET {base_url}/{records_path}?page=N&per_page=M` until a
+/// short page is returned, and written back with `PUT {base_url}/{records_path}/{id}`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RestConfig {
+    pub enabled: bool,
+    pub base_url: String,
+    pub records_path: String,
+    pub auth: AuthMethod,
+    pub page_size: u32,
+    pub max_retries: u32,
+    pub initial_backoff_ms: u64,
+    /// Pull automatically every N minutes. Zero disables scheduled pulls.
+    pub pull_interval_minutes: u32,
+}
+
+impl Default for RestConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            base_url: "http://localhost:8080/api".to_string(),
+            records_path: "records".to_string(),
+            auth: AuthMethod::None,
+            page_size: 100,
+            max_retries: 3,
+            initial_backoff_ms: 500,
+            pull_interval_minutes: 0,
+        }
+    }
+}
+
+pub struct RestClient {
+    config: RestConfig,
+    client: reqwest::Client,
+}
+
+impl RestClient {
+    pub fn new(config: RestConfig) -> Result<Self> {
+        if config.page_size == 0 {
+            bail!("Page size must be greater than zero");
+        }
+        if config.auth.missing_secret() {
+            bail!("Enter the {} credentials in Settings; they are not saved between sessions", config.auth.label().to_lowercase());
+        }
+        let client = reqwest::Client::builder().timeout(Duration::from_secs(30)).build()?;
+        Ok(Self { config, client })
+    }
+
+    fn records_url(&self) -> String {
+        format!(
+            "{}/{}",
+            self.config.base_url.trim_end_matches('/'),
+            self.config.records_path.trim_matches('/')
+        )
+    }
+
+    fn authorize(&self, request: RequestBuilder) -> RequestBuilder {
+        match &self.config.auth {
+            AuthMethod::None => request,
+            AuthMethod::Bearer { token } => request.bearer_auth(token),
+            AuthMethod::Basic { username, password } => request.basic_auth(username, Some(password)),
+            AuthMethod::Header { name, value } => request.header(name.as_str(), value.as_str()),
+        }
+    }
+
+    /// Sends a request, retrying with exponential backoff on connection errors,
+    /// server errors and `429 Too Many Requests`.
+    async fn send_with_retry(&self, build: impl Fn() -> RequestBuilder) -> Result<reqwest::Response> {
+        let mut delay = Duration::from_millis(self.config.initial_backoff_ms);
+        let mut attempt = 0;
+
+        loop {
+            let error = match self.authorize(build()).send().await {
+                Ok(response) if response.status().is_server_error() || response.status() == StatusCode::TOO_MANY_REQUESTS => {
+                    anyhow!("Server returned {}", response.status())
+                }
+                Ok(response) => return Ok(response.error_for_status()?),
+                Err(e) => e.into(),
+            };
+
+            if attempt >= self.config.max_retries {
+                return Err(error.context(format!("Request failed after {} attempts", attempt + 1)));
+            }
+            log::warn!("Request failed ({}), retrying in {:?}", error, delay);
+            tokio::time::sleep(delay).await;
+            delay *= 2;
+            attempt += 1;
+        }
+    }
+
+    /// Fetches all records, following pagination until a short or empty page.
+    pub async fn pull(&self) -> Result<Vec<TableData>> {
+        let url = self.records_url();
+        let page_size = self.config.page_size;
+        let mut records = Vec::new();
+        let mut page = 1u32;
+
+        loop {
+            let batch: Vec<TableData> = self
+                .send_with_retry(|| {
+                    self.client
+                        .get(&url)
+                        .query(&[("page", page), ("per_page", page_size)])
+                })
+                .await?
+                .json()
+                .await?;
+
+            let count = batch.len();
+            if page > 1 && count > 0 && records.first().map(|item: &TableData| item.id) == batch.first().map(|item| item.id) {
+                bail!("The server returned the same page twice; it may not support pagination");
+            }
+            records.extend(batch);
+
+            if count < page_size as usize {
+                return Ok(records);
+            }
+            page += 1;
+        }
+    }
+
+    /// Uploads the given records one by one and returns how many were written.
+    pub async fn push(&self, records: &[TableData]) -> Result<usize> {
+        let url = self.records_url();
+        for record in records {
+            let record_url = format!("{}/{}", url, record.id);
+            self.send_with_retry(|| self.client.put(&record_url).json(record)).await?;
+        }
+        Ok(records.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, TimeZone};
+    use std::io::{BufRead, BufReader, Read, Write};
+    use std::net::TcpListener;
+    use std::sync::{Arc, Mutex};
+    use std::thread;
+
+    /// Request line, lower-cased headers and body of a request the mock server received.
+    #[derive(Debug)]
+    struct Received {
+        line: String,
+        headers: Vec<(String, String)>,
+        body: String,
+    }
+
+    impl Received {
+        fn header(&self, name: &str) -> Option<&str> {
+            self.headers.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+        }
+    }
+
+    /// Serves each connection with the status and body `respond` returns for
+    /// it, and records every request.
+    fn mock_server(
+        respond: impl Fn(usize, &Received) -> (u16, String) + Send + 'static,
+    ) -> (String, Arc<Mutex<Vec<Received>>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/api", listener.local_addr().unwrap());
+        let received = Arc::new(Mutex::new(Vec::new()));
+        let log = received.clone();
+        thread::spawn(move || {
+            for (index, stream) in listener.incoming().enumerate() {
+                let mut stream = stream.unwrap();
+                let mut reader = BufReader::new(stream.try_clone().unwrap());
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let mut headers = Vec::new();
+                loop {
+                    let mut header = String::new();
+                    reader.read_line(&mut header).unwrap();
+                    let Some((name, value)) = header.trim_end().split_once(':') else {
+                        break;
+                    };
+                    headers.push((name.to_lowercase(), value.trim().to_string()));
+                }
+                let request = Received {
+                    line: line.trim_end().to_string(),
+                    headers,
+                    body: String::new(),
+                };
+                let length = request.header("content-length").map_or(0, |value| value.parse().unwrap());
+                let mut body = vec![0; length];
+                reader.read_exact(&mut body).unwrap();
+                let request = Received {
+                    body: String::from_utf8(body).unwrap(),
+                    ..request
+                };
+
+                let (status, body) = respond(index, &request);
+                log.lock().unwrap().push(request);
+                write!(
+                    stream,
+                    "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    status,
+                    body.len(),
+                    body
+                )
+                .unwrap();
+            }
+        });
+        (url, received)
+    }
+
+    fn config(base_url: String) -> RestConfig {
+        RestConfig {
+            enabled: true,
+            base_url,
+            page_size: 2,
+            max_retries: 2,
+            initial_backoff_ms: 1,
+            ..RestConfig::default()
+        }
+    }
+
+    fn record(id: u32) -> TableData {
+        TableData {
+            id,
+            name: format!("Record {}", id),
+            value: f64::from(id),
+            date: Local.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+        }
+    }
+
+    fn page(ids: &[u32]) -> String {
+        serde_json::to_string(&ids.iter().map(|&id| record(id)).collect::<Vec<_>>()).unwrap()
+    }
+
+    fn run<T>(future: impl std::future::Future<Output = T>) -> T {
+        tokio::runtime::Runtime::new().unwrap().block_on(future)
+    }
+
+    #[test]
+    fn pull_follows_pages_until_a_short_page() {
+        let (url, received) = mock_server(|index, _| match index {
+            0 => (200, page(&[1, 2])),
+            1 => (200, page(&[3, 4])),
+            _ => (200, page(&[5])),
+        });
+        let records = run(RestClient::new(config(url)).unwrap().pull()).unwrap();
+
+        assert_eq!(records.iter().map(|item| item.id).collect::<Vec<_>>(), [1, 2, 3, 4, 5]);
+        let lines: Vec<String> = received.lock().unwrap().iter().map(|request| request.line.clone()).collect();
+        assert_eq!(
+            lines,
+            [
+                "GET /api/records?page=1&per_page=2 HTTP/1.1",
+                "GET /api/records?page=2&per_page=2 HTTP/1.1",
+                "GET /api/records?page=3&per_page=2 HTTP/1.1",
+            ]
+        );
+    }
+
+    #[test]
+    fn retries_server_errors_then_succeeds() {
+        let (url, received) = mock_server(|index, _| match index {
+            0 => (503, String::new()),
+            1 => (429, String::new()),
+            _ => (200, page(&[1])),
+        });
+        let records = run(RestClient::new(config(url)).unwrap().pull()).unwrap();
+
+        assert_eq!(records.len(), 1);
+        assert_eq!(received.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn gives_up_after_the_maximum_number_of_retries() {
+        let (url, received) = mock_server(|_, _| (500, String::new()));
+        let error = run(RestClient::new(config(url)).unwrap().pull()).unwrap_err();
+
+        assert!(format!("{:#}", error).contains("Request failed after 3 attempts"), "{:#}", error);
+        assert_eq!(received.lock().unwrap().len(), 3);
+    }
+
+    #[test]
+    fn push_puts_each_record_with_credentials() {
+        let (url, received) = mock_server(|_, _| (200, String::new()));
+        let mut config = config(url);
+        config.auth = AuthMethod::Bearer {
+            token: "secret".to_string(),
+        };
+        let written = run(RestClient::new(config).unwrap().push(&[record(7), record(8)])).unwrap();
+
+        assert_eq!(written, 2);
+        let received = received.lock().unwrap();
+        assert_eq!(received[0].line, "PUT /api/records/7 HTTP/1.1");
+        assert_eq!(received[1].line, "PUT /api/records/8 HTTP/1.1");
+        assert_eq!(received[0].header("authorization"), Some("Bearer secret"));
+        let sent: TableData = serde_json::from_str(&received[0].body).unwrap();
+        assert_eq!(sent.id, 7);
+    }
+
+    #[test]
+    fn push_reports_client_errors_without_retrying() {
+        let (url, received) = mock_server(|_, _| (404, String::new()));
+        let error = run(RestClient::new(config(url)).unwrap().push(&[record(1)])).unwrap_err();
+
+        assert!(error.to_string().contains("404"), "{}", error);
+        assert_eq!(received.lock().unwrap().len(), 1);
+    }
+
+    #[test]
+    fn secrets_are_not_saved() {
+        let auth = AuthMethod::Basic {
+            username: "user".to_string(),
+            password: "secret".to_string(),
+        };
+        let json = serde_json::to_string(&auth).unwrap();
+        assert!(!json.contains("secret"), "{}", json);
+        let loaded: AuthMethod = serde_json::from_str(&json).unwrap();
+        assert!(loaded.missing_secret());
+        assert!(RestClient::new(RestConfig { auth: loaded, ..RestConfig::default() }).is_err());
+    }
+}
@@ -0,0 +1,399 @@
+use crate::data::DateRange;
+use crate::export::ExportProfile;
+use crate::storage;
+use anyhow::Result;
+use chrono::{DateTime, Duration, Local, NaiveTime, TimeZone};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// How many log entries are kept in memory for display.
+const LOG_HISTORY: usize = 200;
+
+/// Entries the log file may hold before it is trimmed back to the last
+/// `LOG_HISTORY` entries and each preset's last run.
+const LOG_FILE_LIMIT: usize = 1000;
+
+/// When a preset runs.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Schedule {
+    EveryHours(u32),
+    DailyAt(NaiveTime),
+}
+
+impl Schedule {
+    /// First run time after `last`, in the time zone of `last`.
+    pub fn next_after<Tz: TimeZone>(&self, last: DateTime<Tz>) -> DateTime<Tz> {
+        match *self {
+            Schedule::EveryHours(hours) => last + Duration::hours(i64::from(hours.max(1))),
+            Schedule::DailyAt(time) => {
+                let zone = last.timezone();
+                let mut day = last.date_naive();
+                loop {
+                    let naive = day.and_time(time);
+                    // A time skipped by a daylight saving change runs an hour
+                    // later; a repeated one runs the first time round.
+                    let at = zone
+                        .from_local_datetime(&naive)
+                        .earliest()
+                        .or_else(|| zone.from_local_datetime(&(naive + Duration::hours(1))).earliest());
+                    if let Some(at) = at
+                        && at > last
+                    {
+                        return at;
+                    }
+                    day = day.succ_opt().unwrap_or(day);
+                }
+            }
+        }
+    }
+
+    pub fn describe(&self) -> String {
+        match self {
+            Schedule::EveryHours(1) => "Every hour".to_string(),
+            Schedule::EveryHours(hours) => format!("Every {} hours", hours),
+            Schedule::DailyAt(time) => format!("Daily at {}", time.format("%H:%M")),
+        }
+    }
+}
+
+/// A named export that runs on a schedule while the app is open.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ExportPreset {
+    /// Stable identifier, so renaming a preset keeps its run history.
+    pub id: u64,
+    pub name: String,
+    pub enabled: bool,
+    pub format_id: String,
+    /// Destination and columns. `ExportScope::Filtered` exports the rows in
+    /// `filter`; any other scope exports all rows.
+    pub profile: ExportProfile,
+    pub filter: DateRange,
+    pub schedule: Schedule,
+    /// Starting point of the schedule until the first run.
+    pub created: DateTime<Local>,
+}
+
+impl Default for ExportPreset {
+    fn default() -> Self {
+        Self {
+            id: fastrand::u64(..),
+            name: "Scheduled export".to_string(),
+            enabled: true,
+            format_id: "csv".to_string(),
+            profile: ExportProfile::default(),
+            filter: DateRange::default(),
+            schedule: Schedule::EveryHours(24),
+            created: Local::now(),
+        }
+    }
+}
+
+impl ExportPreset {
+    /// When the preset is due, given when it last ran. Runs missed while the
+    /// app was closed are due straight away, once.
+    pub fn next_run(&self, last_run: Option<DateTime<Local>>) -> DateTime<Local> {
+        self.schedule.next_after(last_run.unwrap_or(self.created))
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum RunOutcome {
+    Exported { path: PathBuf, records: usize },
+    Failed(String),
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ScheduleLogEntry {
+    /// When the run started.
+    pub at: DateTime<Local>,
+    pub preset_id: u64,
+    pub preset: String,
+    /// When the run was due; well before `at` for runs caught up at startup.
+    pub due: DateTime<Local>,
+    pub outcome: RunOutcome,
+}
+
+impl ScheduleLogEntry {
+    pub fn caught_up(&self) -> bool {
+        self.at - self.due > Duration::minutes(1)
+    }
+}
+
+/// Record of scheduled export runs, stored as JSON lines. Also the source of
+/// each preset's last run time. The file is trimmed once it grows past
+/// `LOG_FILE_LIMIT` entries, keeping the recent history and the last run of
+/// every preset.
+pub struct ScheduleLog {
+    path: PathBuf,
+    entries: Vec<ScheduleLogEntry>,
+    last_runs: BTreeMap<u64, DateTime<Local>>,
+    /// Each preset's most recent entry, kept when the file is trimmed.
+    latest: BTreeMap<u64, ScheduleLogEntry>,
+    /// Entries in the file.
+    file_entries: usize,
+}
+
+impl ScheduleLog {
+    pub fn load() -> Self {
+        Self::load_from(storage::app_data_dir().join("export_schedule_log.jsonl"))
+    }
+
+    fn load_from(path: PathBuf) -> Self {
+        let mut entries: Vec<ScheduleLogEntry> = fs::read_to_string(&path)
+            .map(|text| text.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+            .unwrap_or_default();
+
+        let mut log = Self {
+            path,
+            entries: Vec::new(),
+            last_runs: BTreeMap::new(),
+            latest: BTreeMap::new(),
+            file_entries: entries.len(),
+        };
+        for entry in &entries {
+            log.note_run(entry);
+        }
+        let skip = entries.len().saturating_sub(LOG_HISTORY);
+        entries.drain(..skip);
+        log.entries = entries;
+
+        if log.file_entries > LOG_FILE_LIMIT
+            && let Err(e) = log.trim()
+        {
+            log::warn!("Failed to trim {}: {}", log.path.display(), e);
+        }
+        log
+    }
+
+    pub fn entries(&self) -> &[ScheduleLogEntry] {
+        &self.entries
+    }
+
+    pub fn last_run(&self, preset_id: u64) -> Option<DateTime<Local>> {
+        self.last_runs.get(&preset_id).copied()
+    }
+
+    /// Notes a run as started, so the preset is not due again while it runs.
+    pub fn started(&mut self, preset_id: u64, at: DateTime<Local>) {
+        self.last_runs.insert(preset_id, at);
+    }
+
+    fn note_run(&mut self, entry: &ScheduleLogEntry) {
+        self.last_runs
+            .entry(entry.preset_id)
+            .and_modify(|last| *last = (*last).max(entry.at))
+            .or_insert(entry.at);
+        if self.latest.get(&entry.preset_id).is_none_or(|latest| latest.at <= entry.at) {
+            self.latest.insert(entry.preset_id, entry.clone());
+        }
+    }
+
+    pub fn record(&mut self, entry: ScheduleLogEntry) -> Result<()> {
+        self.note_run(&entry);
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+        self.file_entries += 1;
+
+        self.entries.push(entry);
+        if self.entries.len() > LOG_HISTORY {
+            self.entries.remove(0);
+        }
+        if self.file_entries > LOG_FILE_LIMIT {
+            self.trim()?;
+        }
+        Ok(())
+    }
+
+    /// Rewrites the file with the entries in memory and each preset's last run.
+    fn trim(&mut self) -> Result<()> {
+        let mut kept: Vec<&ScheduleLogEntry> = self
+            .latest
+            .values()
+            .filter(|latest| {
+                !self.entries.iter().any(|entry| entry.preset_id == latest.preset_id && entry.at == latest.at)
+            })
+            .chain(&self.entries)
+            .collect();
+        kept.sort_by_key(|entry| entry.at);
+
+        let mut text = String::new();
+        for entry in &kept {
+            text.push_str(&serde_json::to_string(entry)?);
+            text.push('\n');
+        }
+        storage::write_atomic(&self.path, text.as_bytes())?;
+        self.file_entries = kept.len();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, LocalResult, NaiveDate, NaiveDateTime, Offset, Utc};
+
+    /// Central European time in 2024: UTC+1, and UTC+2 from 31 March 01:00
+    /// UTC until 27 October 01:00 UTC.
+    #[derive(Debug, Clone, Copy)]
+    struct Cet2024;
+
+    impl Cet2024 {
+        const WINTER: i32 = 3600;
+        const SUMMER: i32 = 7200;
+
+        fn at(month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Self> {
+            Self.from_local_datetime(&naive(month, day, hour, minute)).earliest().unwrap()
+        }
+    }
+
+    fn naive(month: u32, day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, month, day).unwrap().and_hms_opt(hour, minute, 0).unwrap()
+    }
+
+    impl TimeZone for Cet2024 {
+        type Offset = FixedOffset;
+
+        fn from_offset(_: &FixedOffset) -> Self {
+            Cet2024
+        }
+
+        fn offset_from_local_date(&self, local: &NaiveDate) -> LocalResult<FixedOffset> {
+            self.offset_from_local_datetime(&local.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_local_datetime(&self, local: &NaiveDateTime) -> LocalResult<FixedOffset> {
+            let fits = |seconds: i32| {
+                let offset = FixedOffset::east_opt(seconds).unwrap();
+                (self.offset_from_utc_datetime(&(*local - offset)) == offset).then_some(offset)
+            };
+            match (fits(Self::SUMMER), fits(Self::WINTER)) {
+                (Some(summer), Some(winter)) => LocalResult::Ambiguous(summer, winter),
+                (Some(offset), None) | (None, Some(offset)) => LocalResult::Single(offset),
+                (None, None) => LocalResult::None,
+            }
+        }
+
+        fn offset_from_utc_date(&self, utc: &NaiveDate) -> FixedOffset {
+            self.offset_from_utc_datetime(&utc.and_hms_opt(0, 0, 0).unwrap())
+        }
+
+        fn offset_from_utc_datetime(&self, utc: &NaiveDateTime) -> FixedOffset {
+            let summer = (naive(3, 31, 1, 0)..naive(10, 27, 1, 0)).contains(utc);
+            FixedOffset::east_opt(if summer { Self::SUMMER } else { Self::WINTER }).unwrap()
+        }
+    }
+
+    fn utc(month: u32, day: u32, hour: u32, minute: u32) -> DateTime<Utc> {
+        Utc.from_utc_datetime(&naive(month, day, hour, minute))
+    }
+
+    #[test]
+    fn daily_runs_are_on_the_next_day_at_the_time() {
+        let schedule = Schedule::DailyAt(NaiveTime::from_hms_opt(9, 0, 0).unwrap());
+        assert_eq!(schedule.next_after(Cet2024::at(1, 10, 8, 0)), Cet2024::at(1, 10, 9, 0));
+        assert_eq!(schedule.next_after(Cet2024::at(1, 10, 9, 0)), Cet2024::at(1, 11, 9, 0));
+        assert_eq!(schedule.next_after(Cet2024::at(12, 31, 23, 0)).date_naive(), NaiveDate::from_ymd_opt(2025, 1, 1).unwrap());
+    }
+
+    #[test]
+    fn daily_runs_across_daylight_saving_changes() {
+        let schedule = Schedule::DailyAt(NaiveTime::from_hms_opt(2, 30, 0).unwrap());
+        // 02:30 does not exist on 31 March, so the run is at 03:30 summer time.
+        let spring = schedule.next_after(Cet2024::at(3, 30, 12, 0));
+        assert_eq!(spring.naive_local(), naive(3, 31, 3, 30));
+        assert_eq!(spring, utc(3, 31, 1, 30));
+        // 02:30 happens twice on 27 October; the run is the first time round.
+        let autumn = schedule.next_after(Cet2024::at(10, 26, 12, 0));
+        assert_eq!(autumn, utc(10, 27, 0, 30));
+        assert_eq!(autumn.offset().fix().local_minus_utc(), Cet2024::SUMMER);
+        // And not again at the repeated 02:30.
+        assert_eq!(schedule.next_after(autumn).naive_local(), naive(10, 28, 2, 30));
+    }
+
+    #[test]
+    fn hourly_runs_count_elapsed_time_across_daylight_saving_changes() {
+        let schedule = Schedule::EveryHours(24);
+        let next = schedule.next_after(Cet2024::at(3, 30, 12, 0));
+        assert_eq!(next.naive_local(), naive(3, 31, 13, 0));
+        assert_eq!(Schedule::EveryHours(0).next_after(utc(1, 1, 0, 0)), utc(1, 1, 1, 0));
+    }
+
+    fn preset(schedule: Schedule, created: DateTime<Local>) -> ExportPreset {
+        ExportPreset {
+            schedule,
+            created,
+            ..ExportPreset::default()
+        }
+    }
+
+    #[test]
+    fn presets_first_run_a_period_after_they_are_created() {
+        let created = Local::now();
+        let preset = preset(Schedule::EveryHours(6), created);
+        assert_eq!(preset.next_run(None), created + Duration::hours(6));
+        let last_run = created + Duration::hours(7);
+        assert_eq!(preset.next_run(Some(last_run)), last_run + Duration::hours(6));
+    }
+
+    #[test]
+    fn missed_runs_are_due_once() {
+        let now = Local::now();
+        let dir = std::env::temp_dir().join(format!("desktop-app-schedule-{}", fastrand::u64(..)));
+        let mut log = ScheduleLog::load_from(dir.join("log.jsonl"));
+        let preset = preset(Schedule::EveryHours(24), now - Duration::days(10));
+
+        // Nine runs were missed while the app was closed; one is due now.
+        let due = preset.next_run(log.last_run(preset.id));
+        assert!(due <= now);
+        log.started(preset.id, now);
+        assert!(preset.next_run(log.last_run(preset.id)) > now);
+
+        log.record(entry(&preset, now, due)).unwrap();
+        let reloaded = ScheduleLog::load_from(dir.join("log.jsonl"));
+        assert_eq!(reloaded.last_run(preset.id), Some(now));
+        assert!(reloaded.entries()[0].caught_up());
+        assert!(preset.next_run(reloaded.last_run(preset.id)) > now);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    fn entry(preset: &ExportPreset, at: DateTime<Local>, due: DateTime<Local>) -> ScheduleLogEntry {
+        ScheduleLogEntry {
+            at,
+            preset_id: preset.id,
+            preset: preset.name.clone(),
+            due,
+            outcome: RunOutcome::Failed("test".to_string()),
+        }
+    }
+
+    #[test]
+    fn the_log_file_is_trimmed_but_keeps_every_presets_last_run() {
+        let dir = std::env::temp_dir().join(format!("desktop-app-schedule-{}", fastrand::u64(..)));
+        let path = dir.join("log.jsonl");
+        let start = Local::now() - Duration::days(30);
+        let (rare, frequent) = (ExportPreset::default(), ExportPreset::default());
+
+        let mut log = ScheduleLog::load_from(path.clone());
+        log.record(entry(&rare, start, start)).unwrap();
+        for minute in 1..=LOG_FILE_LIMIT as i64 {
+            let at = start + Duration::minutes(minute);
+            log.record(entry(&frequent, at, at)).unwrap();
+        }
+
+        let lines = fs::read_to_string(&path).unwrap().lines().count();
+        assert_eq!(lines, LOG_HISTORY + 1);
+        let reloaded = ScheduleLog::load_from(path);
+        assert_eq!(reloaded.last_run(rare.id), Some(start));
+        assert_eq!(reloaded.last_run(frequent.id), Some(start + Duration::minutes(LOG_FILE_LIMIT as i64)));
+        assert_eq!(reloaded.entries().len(), LOG_HISTORY);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,55 @@
+use crate::export::{ExportOptions, ExportProfile};
+use crate::locale::Locale;
+use crate::remote::RestConfig;
+use crate::schedule::ExportPreset;
+use crate::storage;
+use crate::updater::UpdateChannel;
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::PathBuf;
+
+/// User settings persisted between sessions.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AppSettings {
+    pub rest: RestConfig,
+    /// Number and date formats of the UI and text exports.
+    pub locale: Locale,
+    /// Exporter options keyed by exporter ID.
+    pub export_options: BTreeMap<String, ExportOptions>,
+    /// Choices last used in the export dialog.
+    pub export_profile: ExportProfile,
+    /// Exports that run on a schedule while the app is open.
+    pub export_presets: Vec<ExportPreset>,
+    pub update_channel: UpdateChannel,
+}
+
+impl AppSettings {
+    fn path() -> PathBuf {
+        let mut path = dirs::config_dir().unwrap_or_else(|| PathBuf::from("."));
+        path.push("desktop-app");
+        path.push("settings.json");
+        path
+    }
+
+    pub fn load() -> Self {
+        let path = Self::path();
+        match fs::read(&path) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                log::warn!("Ignoring invalid settings file {}: {}", path.display(), e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        storage::write_atomic(&path, &serde_json::to_vec_pretty(self)?)
+    }
+}
@@ -0,0 +1,442 @@
+use crate::data::TableData;
+use crate::locale::Locale;
+use crate::storage;
+use anyhow::{Context, Result, bail};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub name: String,
+    pub taken_at: DateTime<Local>,
+    pub records: Vec<TableData>,
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+/// Plaintext snapshots are JSON; encrypted ones use the data file's encrypted format.
+const PLAIN_EXTENSION: &str = "json";
+const ENCRYPTED_EXTENSION: &str = "snapshot";
+
+/// Named copies of the `DataStore`, persisted as one file each. While an
+/// encrypted data file is open, snapshots are encrypted with its password.
+pub struct SnapshotManager {
+    dir: PathBuf,
+    snapshots: Vec<Snapshot>,
+    password: Option<String>,
+    /// Encrypted snapshots the current password cannot open.
+    locked: usize,
+}
+
+impl SnapshotManager {
+    pub fn new() -> Self {
+        let mut dir = storage::app_data_dir();
+        dir.push("snapshots");
+        Self::with_dir(dir)
+    }
+
+    fn with_dir(dir: PathBuf) -> Self {
+        let mut manager = Self {
+            dir,
+            snapshots: Vec::new(),
+            password: None,
+            locked: 0,
+        };
+        if let Err(e) = manager.reload() {
+            log::warn!("Failed to load snapshots: {}", e);
+        }
+        manager
+    }
+
+    /// Sets the password of the open data file, used to encrypt new snapshots
+    /// and to open encrypted ones, and reloads the list.
+    pub fn set_password(&mut self, password: Option<String>) {
+        if self.password == password {
+            return;
+        }
+        self.password = password;
+        if let Err(e) = self.reload() {
+            log::warn!("Failed to load snapshots: {}", e);
+        }
+    }
+
+    /// Changes the password of the encrypted snapshots together with the data
+    /// file's, which `change_file` changes. The snapshots are re-encrypted for
+    /// `password`, or decrypted when it is `None`, into staging files first and
+    /// only put in place once `change_file` succeeds, so a failure leaves every
+    /// snapshot under the old password. Refuses while any snapshot is locked,
+    /// as those could not be re-encrypted.
+    pub fn change_password(&mut self, password: Option<String>, change_file: impl FnOnce() -> Result<()>) -> Result<()> {
+        if self.locked > 0 {
+            bail!(
+                "{} snapshots are encrypted with another password; delete them before changing the password",
+                self.locked
+            );
+        }
+
+        let old_password = std::mem::replace(&mut self.password, password);
+        // Staging file, final path and old path of each re-encrypted snapshot.
+        let mut staged: Vec<(PathBuf, PathBuf, PathBuf)> = Vec::new();
+        let result = (|| -> Result<()> {
+            for snapshot in &self.snapshots {
+                if snapshot.path.extension().is_none_or(|ext| ext != ENCRYPTED_EXTENSION) {
+                    continue;
+                }
+                let path = self.snapshot_path(&snapshot.name, snapshot.taken_at);
+                let mut staging_name = std::ffi::OsString::from(".");
+                staging_name.push(path.file_name().unwrap_or_default());
+                staging_name.push(".rekey");
+                let staging = path.with_file_name(staging_name);
+                self.write_snapshot(snapshot, &staging)?;
+                staged.push((staging, path, snapshot.path.clone()));
+            }
+            change_file()
+        })();
+        if let Err(e) = result {
+            for (staging, _, _) in &staged {
+                let _ = fs::remove_file(staging);
+            }
+            self.password = old_password;
+            return Err(e);
+        }
+
+        for (staging, path, old_path) in staged {
+            fs::rename(&staging, &path).with_context(|| format!("Failed to replace {}", path.display()))?;
+            if old_path != path {
+                fs::remove_file(&old_path)?;
+            }
+        }
+        self.reload()
+    }
+
+    /// Number of encrypted snapshots hidden because the current password cannot open them.
+    pub fn locked(&self) -> usize {
+        self.locked
+    }
+
+    pub fn reload(&mut self) -> Result<()> {
+        self.snapshots.clear();
+        self.locked = 0;
+        if !self.dir.exists() {
+            return Ok(());
+        }
+
+        for entry in fs::read_dir(&self.dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != PLAIN_EXTENSION && ext != ENCRYPTED_EXTENSION) {
+                continue;
+            }
+            match self.read_snapshot(&path) {
+                Ok(Some(snapshot)) => self.snapshots.push(snapshot),
+                Ok(None) => self.locked += 1,
+                Err(e) => log::warn!("Skipping snapshot {}: {}", path.display(), e),
+            }
+        }
+        self.snapshots.sort_by_key(|snapshot| snapshot.taken_at);
+        Ok(())
+    }
+
+    /// Reads a snapshot, or `None` when it is encrypted and the current password
+    /// does not open it.
+    fn read_snapshot(&self, path: &Path) -> Result<Option<Snapshot>> {
+        let bytes = fs::read(path)?;
+        let json = if storage::is_encrypted(&bytes) {
+            match self.password.as_deref().map(|password| storage::decrypt(&bytes, password)) {
+                Some(Ok(json)) => json,
+                _ => return Ok(None),
+            }
+        } else {
+            bytes
+        };
+        let mut snapshot: Snapshot = serde_json::from_slice(&json)?;
+        snapshot.path = path.to_path_buf();
+        Ok(Some(snapshot))
+    }
+
+    fn snapshot_path(&self, name: &str, taken_at: DateTime<Local>) -> PathBuf {
+        let slug: String = name
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+            .collect();
+        let extension = match self.password {
+            Some(_) => ENCRYPTED_EXTENSION,
+            None => PLAIN_EXTENSION,
+        };
+        self.dir.join(format!("{}_{}.{}", taken_at.format("%Y%m%d_%H%M%S"), slug, extension))
+    }
+
+    fn write_snapshot(&self, snapshot: &Snapshot, path: &Path) -> Result<()> {
+        let json = serde_json::to_vec_pretty(snapshot)?;
+        let bytes = match &self.password {
+            Some(password) => storage::encrypt(&json, password)?,
+            None => json,
+        };
+        fs::create_dir_all(&self.dir)?;
+        storage::write_atomic(path, &bytes)
+    }
+
+    pub fn snapshots(&self) -> &[Snapshot] {
+        &self.snapshots
+    }
+
+    pub fn take(&mut self, name: &str, records: &[TableData]) -> Result<()> {
+        let name = name.trim();
+        if name.is_empty() {
+            bail!("Snapshot name cannot be empty");
+        }
+        if self.snapshots.iter().any(|snapshot| snapshot.name == name) {
+            bail!("A snapshot named \"{}\" already exists", name);
+        }
+
+        let taken_at = Local::now();
+        let snapshot = Snapshot {
+            name: name.to_string(),
+            taken_at,
+            records: records.to_vec(),
+            path: self.snapshot_path(name, taken_at),
+        };
+
+        self.write_snapshot(&snapshot, &snapshot.path)?;
+        self.snapshots.push(snapshot);
+        Ok(())
+    }
+
+    pub fn delete(&mut self, index: usize) -> Result<()> {
+        if let Some(snapshot) = self.snapshots.get(index) {
+            fs::remove_file(&snapshot.path)?;
+            self.snapshots.remove(index);
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub old: String,
+    pub new: String,
+}
+
+#[derive(Debug, Clone)]
+pub struct RecordChange {
+    pub id: u32,
+    pub changes: Vec<FieldChange>,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct RecordDiff {
+    pub added: Vec<TableData>,
+    pub removed: Vec<TableData>,
+    pub changed: Vec<RecordChange>,
+}
+
+impl RecordDiff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+/// Compares two record sets by ID and reports the differences field by field,
+/// with changed values written in the given regional format.
+pub fn diff_records(old: &[TableData], new: &[TableData], locale: Locale) -> RecordDiff {
+    let old_by_id: BTreeMap<u32, &TableData> = old.iter().map(|item| (item.id, item)).collect();
+    let new_by_id: BTreeMap<u32, &TableData> = new.iter().map(|item| (item.id, item)).collect();
+
+    let mut diff = RecordDiff::default();
+
+    for (id, old_item) in &old_by_id {
+        match new_by_id.get(id) {
+            Some(new_item) => {
+                let changes = field_changes(old_item, new_item, locale);
+                if !changes.is_empty() {
+                    diff.changed.push(RecordChange { id: *id, changes });
+                }
+            }
+            None => diff.removed.push((*old_item).clone()),
+        }
+    }
+
+    for (id, new_item) in &new_by_id {
+        if !old_by_id.contains_key(id) {
+            diff.added.push((*new_item).clone());
+        }
+    }
+
+    diff
+}
+
+fn field_changes(old: &TableData, new: &TableData, locale: Locale) -> Vec<FieldChange> {
+    let mut changes = Vec::new();
+
+    if old.name != new.name {
+        changes.push(FieldChange {
+            field: "Name",
+            old: old.name.clone(),
+            new: new.name.clone(),
+        });
+    }
+    if old.value != new.value {
+        changes.push(FieldChange {
+            field: "Value",
+            // Every digit, so that tiny changes show.
+            old: locale.format_decimal(old.value),
+            new: locale.format_decimal(new.value),
+        });
+    }
+    if old.date != new.date {
+        changes.push(FieldChange {
+            field: "Date",
+            old: locale.format_date_time(&old.date),
+            new: locale.format_date_time(&new.date),
+        });
+    }
+
+    changes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::export::testing;
+
+    struct TempDir(PathBuf);
+
+    impl TempDir {
+        fn new() -> Self {
+            Self(std::env::temp_dir().join(format!("desktop-app-snapshots-{}", fastrand::u64(..))))
+        }
+
+        /// A manager over the directory that opens snapshots with `password`.
+        fn manager(&self, password: Option<&str>) -> SnapshotManager {
+            let mut manager = SnapshotManager::with_dir(self.0.clone());
+            manager.set_password(password.map(String::from));
+            manager
+        }
+
+        fn files(&self) -> Vec<String> {
+            let mut files: Vec<String> = fs::read_dir(&self.0)
+                .unwrap()
+                .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+                .collect();
+            files.sort();
+            files
+        }
+    }
+
+    impl Drop for TempDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    #[test]
+    fn changing_the_password_re_encrypts_every_snapshot() {
+        let dir = TempDir::new();
+        let mut manager = dir.manager(Some("old"));
+        manager.take("January", &[]).unwrap();
+        manager.take("February", &[]).unwrap();
+
+        let mut file_changed = false;
+        manager.change_password(Some("new".to_string()), || {
+            file_changed = true;
+            Ok(())
+        })
+        .unwrap();
+
+        assert!(file_changed);
+        assert_eq!(manager.snapshots().len(), 2);
+        assert_eq!(dir.manager(Some("new")).snapshots().len(), 2);
+        assert_eq!(dir.manager(Some("old")).locked(), 2);
+        assert!(dir.files().iter().all(|file| file.ends_with(".snapshot")));
+
+        manager.change_password(None, || Ok(())).unwrap();
+        assert_eq!(dir.manager(None).snapshots().len(), 2);
+        assert!(dir.files().iter().all(|file| file.ends_with(".json")));
+    }
+
+    #[test]
+    fn a_failed_password_change_keeps_the_old_password() {
+        let dir = TempDir::new();
+        let mut manager = dir.manager(Some("old"));
+        manager.take("January", &[]).unwrap();
+        let files = dir.files();
+
+        let result = manager.change_password(Some("new".to_string()), || bail!("The data file could not be saved"));
+
+        assert!(result.is_err());
+        assert_eq!(dir.files(), files);
+        assert_eq!(dir.manager(Some("old")).snapshots().len(), 1);
+        manager.take("February", &[]).unwrap();
+        assert_eq!(dir.manager(Some("old")).snapshots().len(), 2);
+    }
+
+    #[test]
+    fn changing_the_password_is_refused_while_snapshots_are_locked() {
+        let dir = TempDir::new();
+        dir.manager(Some("other")).take("Other file", &[]).unwrap();
+        let mut manager = dir.manager(Some("old"));
+        manager.take("January", &[]).unwrap();
+        assert_eq!(manager.locked(), 1);
+
+        let mut file_changed = false;
+        let result = manager.change_password(Some("new".to_string()), || {
+            file_changed = true;
+            Ok(())
+        });
+
+        assert!(result.is_err());
+        assert!(!file_changed);
+        assert_eq!(dir.manager(Some("old")).snapshots().len(), 1);
+    }
+
+    #[test]
+    fn diffs_report_added_removed_and_changed_records_field_by_field() {
+        let dir = TempDir::new();
+        let mut manager = dir.manager(None);
+        manager.take("January", &testing::records()).unwrap();
+        let mut records = testing::records();
+        records[0].value = 10.25;
+        records[0].name = "Alpha 2".to_string();
+        records[2].date = testing::record(3, "Alpha", 1234.0, (2024, 3, 1)).date;
+        records.remove(1);
+        records.push(testing::record(4, "Gamma", 7.0, (2024, 3, 2)));
+
+        let diff = diff_records(&manager.snapshots()[0].records, &records, Locale::German);
+
+        assert_eq!(diff.added.iter().map(|item| item.id).collect::<Vec<_>>(), [4]);
+        assert_eq!(diff.removed.iter().map(|item| item.id).collect::<Vec<_>>(), [2]);
+        let changes: Vec<(u32, &str, &str, &str)> = diff
+            .changed
+            .iter()
+            .flat_map(|change| {
+                change
+                    .changes
+                    .iter()
+                    .map(move |field| (change.id, field.field, field.old.as_str(), field.new.as_str()))
+            })
+            .collect();
+        assert_eq!(
+            changes,
+            [
+                (1, "Name", "Alpha", "Alpha 2"),
+                (1, "Value", "10,5", "10,25"),
+                (3, "Date", "20.02.2024 09:30:00", "01.03.2024 09:30:00"),
+            ]
+        );
+    }
+
+    #[test]
+    fn identical_records_have_an_empty_diff() {
+        let records = testing::records();
+        let mut reordered = records.clone();
+        reordered.reverse();
+
+        assert!(diff_records(&records, &reordered, Locale::Standard).is_empty());
+        assert!(diff_records(&[], &[], Locale::Standard).is_empty());
+        let diff = diff_records(&[], &records, Locale::Standard);
+        assert_eq!((diff.added.len(), diff.removed.len()), (3, 0));
+    }
+}
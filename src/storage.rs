@@ -0,0 +1,256 @@
+use crate::data::TableData;
+use anyhow::{Context, Result, anyhow, bail};
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::aead::rand_core::RngCore;
+use chacha20poly1305::aead::{Aead, KeyInit, OsRng, Payload};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+const FORMAT_VERSION: u32 = 1;
+
+/// Marks a data file as encrypted. Plaintext files are JSON and never start with this.
+const ENCRYPTED_MAGIC: &[u8; 8] = b"DAPPENC1";
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 24;
+/// Magic, three little-endian `u32` Argon2 parameters, salt and nonce.
+const HEADER_LEN: usize = ENCRYPTED_MAGIC.len() + 12 + SALT_LEN + NONCE_LEN;
+
+/// Upper limits for the Argon2 parameters read from a file header, so a
+/// crafted file cannot make key derivation use unbounded memory or time.
+const MAX_MEMORY_KIB: u32 = 1024 * 1024;
+const MAX_ITERATIONS: u32 = 10;
+const MAX_PARALLELISM: u32 = 16;
+
+/// On-disk representation of a saved `DataStore`.
+#[derive(Serialize, Deserialize)]
+struct DataFile {
+    format_version: u32,
+    saved_at: DateTime<Local>,
+    records: Vec<TableData>,
+}
+
+/// Directory for application state such as snapshots.
+pub fn app_data_dir() -> PathBuf {
+    let mut path = dirs::data_dir().unwrap_or_else(|| PathBuf::from("."));
+    path.push("desktop-app");
+    path
+}
+
+/// Saves records to `path`, encrypting them when a password is given.
+pub fn save_records(path: &Path, records: &[TableData], password: Option<&str>) -> Result<()> {
+    let file = DataFile {
+        format_version: FORMAT_VERSION,
+        saved_at: Local::now(),
+        records: records.to_vec(),
+    };
+
+    let json = serde_json::to_vec_pretty(&file)?;
+    let bytes = match password {
+        Some(password) => encrypt(&json, password)?,
+        None => json,
+    };
+
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    write_atomic(path, &bytes)
+}
+
+/// Writes `bytes` to a temporary file next to `path`, syncs it and renames it
+/// over `path`, so an interrupted save never leaves a truncated file behind.
+pub fn write_atomic(path: &Path, bytes: &[u8]) -> Result<()> {
+    replace_file(path, |temp_path| {
+        let mut file = fs::File::create(temp_path)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        Ok(())
+    })
+    .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Lets `write` create the file at a temporary path next to `path` and renames
+/// it over `path` once `write` succeeds. On failure the temporary file is
+/// removed and any file already at `path` is left as it was.
+pub fn replace_file(path: &Path, write: impl FnOnce(&Path) -> Result<()>) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("{} is not a file path", path.display()))?;
+    let mut temp_name = std::ffi::OsString::from(".");
+    temp_name.push(file_name);
+    temp_name.push(".tmp");
+    let temp_path = path.with_file_name(temp_name);
+    // A leftover from an interrupted run would otherwise be appended to.
+    if temp_path.exists() {
+        fs::remove_file(&temp_path)?;
+    }
+
+    let result = write(&temp_path)
+        .and_then(|()| fs::rename(&temp_path, path).with_context(|| format!("Failed to replace {}", path.display())));
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+/// Loads records from `path`. Encrypted files require the password they were saved with.
+pub fn load_records(path: &Path, password: Option<&str>) -> Result<Vec<TableData>> {
+    load_file(path, password).map(|(records, _)| records)
+}
+
+/// Loads records from `path` like `load_records`, also returning whether the
+/// file was encrypted, i.e. whether `password` was used.
+pub fn load_file(path: &Path, password: Option<&str>) -> Result<(Vec<TableData>, bool)> {
+    let bytes = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+
+    let encrypted = is_encrypted(&bytes);
+    let json = if encrypted {
+        let password = password.ok_or_else(|| anyhow!("{} is encrypted; a password is required", path.display()))?;
+        decrypt(&bytes, password)?
+    } else {
+        bytes
+    };
+
+    let file: DataFile = serde_json::from_slice(&json)
+        .with_context(|| format!("{} is not a valid data file", path.display()))?;
+
+    if file.format_version > FORMAT_VERSION {
+        bail!("{} was saved by a newer version of the application", path.display());
+    }
+    Ok((file.records, encrypted))
+}
+
+/// Re-saves an existing file under a new password. Passing `None` as the new
+/// password removes encryption.
+pub fn change_password(path: &Path, old_password: Option<&str>, new_password: Option<&str>) -> Result<()> {
+    let records = load_records(path, old_password)?;
+    save_records(path, &records, new_password)
+}
+
+/// Whether `bytes` are in the encrypted format written by `encrypt`.
+pub fn is_encrypted(bytes: &[u8]) -> bool {
+    bytes.starts_with(ENCRYPTED_MAGIC)
+}
+
+fn derive_key(password: &str, salt: &[u8], params: Params) -> Result<[u8; 32]> {
+    let mut key = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+        .hash_password_into(password.as_bytes(), salt, &mut key)
+        .map_err(|e| anyhow!("Key derivation failed: {}", e))?;
+    Ok(key)
+}
+
+pub fn encrypt(plaintext: &[u8], password: &str) -> Result<Vec<u8>> {
+    let params = Params::default();
+    let mut salt = [0u8; SALT_LEN];
+    let mut nonce = [0u8; NONCE_LEN];
+    OsRng.fill_bytes(&mut salt);
+    OsRng.fill_bytes(&mut nonce);
+
+    let mut header = Vec::with_capacity(HEADER_LEN);
+    header.extend_from_slice(ENCRYPTED_MAGIC);
+    header.extend_from_slice(&params.m_cost().to_le_bytes());
+    header.extend_from_slice(&params.t_cost().to_le_bytes());
+    header.extend_from_slice(&params.p_cost().to_le_bytes());
+    header.extend_from_slice(&salt);
+    header.extend_from_slice(&nonce);
+
+    let key = derive_key(password, &salt, params)?;
+    let cipher = XChaCha20Poly1305::new(&key.into());
+    // The header is authenticated too, so the KDF parameters cannot be tampered with.
+    let ciphertext = cipher
+        .encrypt(XNonce::from_slice(&nonce), Payload { msg: plaintext, aad: &header })
+        .map_err(|_| anyhow!("Encryption failed"))?;
+
+    let mut bytes = header;
+    bytes.extend_from_slice(&ciphertext);
+    Ok(bytes)
+}
+
+pub fn decrypt(bytes: &[u8], password: &str) -> Result<Vec<u8>> {
+    if bytes.len() < HEADER_LEN {
+        bail!("Encrypted file is truncated");
+    }
+    let (header, ciphertext) = bytes.split_at(HEADER_LEN);
+
+    let read_u32 = |offset: usize| u32::from_le_bytes(header[offset..offset + 4].try_into().unwrap());
+    let params_offset = ENCRYPTED_MAGIC.len();
+    let (memory, iterations, parallelism) =
+        (read_u32(params_offset), read_u32(params_offset + 4), read_u32(params_offset + 8));
+    if memory > MAX_MEMORY_KIB || iterations > MAX_ITERATIONS || parallelism > MAX_PARALLELISM {
+        bail!(
+            "Encrypted file asks for unsupported key parameters (memory {} KiB, {} iterations, parallelism {})",
+            memory,
+            iterations,
+            parallelism
+        );
+    }
+    let params = Params::new(memory, iterations, parallelism, None)
+        .map_err(|e| anyhow!("Encrypted file has invalid key parameters: {}", e))?;
+
+    let salt_offset = params_offset + 12;
+    let salt = &header[salt_offset..salt_offset + SALT_LEN];
+    let nonce = &header[salt_offset + SALT_LEN..];
+
+    let key = derive_key(password, salt, params)?;
+    let cipher = XChaCha20Poly1305::new(&key.into());
+    cipher
+        .decrypt(XNonce::from_slice(nonce), Payload { msg: ciphertext, aad: header })
+        .map_err(|_| anyhow!("Incorrect password, or the file has been modified"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypted_bytes_round_trip() {
+        let bytes = encrypt(b"records", "secret").unwrap();
+        assert!(is_encrypted(&bytes));
+        assert_eq!(decrypt(&bytes, "secret").unwrap(), b"records");
+        assert!(decrypt(&bytes, "wrong").is_err());
+    }
+
+    #[test]
+    fn oversized_key_parameters_are_rejected() {
+        let bytes = encrypt(b"records", "secret").unwrap();
+        let offset = ENCRYPTED_MAGIC.len();
+        for (field, value) in [(0, MAX_MEMORY_KIB + 1), (1, MAX_ITERATIONS + 1), (2, MAX_PARALLELISM + 1)] {
+            let mut tampered = bytes.clone();
+            let start = offset + field * 4;
+            tampered[start..start + 4].copy_from_slice(&value.to_le_bytes());
+            let error = decrypt(&tampered, "secret").unwrap_err();
+            assert!(error.to_string().contains("unsupported key parameters"), "{}", error);
+        }
+    }
+
+    #[test]
+    fn write_atomic_replaces_the_file_and_leaves_no_temp_file() {
+        let dir = std::env::temp_dir().join(format!("desktop-app-storage-{}", fastrand::u64(..)));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("data.json");
+        fs::write(&path, b"old contents").unwrap();
+
+        write_atomic(&path, b"new").unwrap();
+
+        assert_eq!(fs::read(&path).unwrap(), b"new");
+        assert_eq!(fs::read_dir(&dir).unwrap().count(), 1);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn load_file_reports_whether_the_password_was_used() {
+        let dir = std::env::temp_dir().join(format!("desktop-app-storage-{}", fastrand::u64(..)));
+        let (plain, encrypted) = (dir.join("plain.json"), dir.join("encrypted.json"));
+        save_records(&plain, &[], None).unwrap();
+        save_records(&encrypted, &[], Some("secret")).unwrap();
+
+        assert!(!load_file(&plain, Some("typed anyway")).unwrap().1);
+        assert!(load_file(&encrypted, Some("secret")).unwrap().1);
+        assert!(load_file(&encrypted, None).is_err());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,423 @@
+use crate::data::TableData;
+use crate::locale::Locale;
+use crate::storage;
+use anyhow::Result;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::PathBuf;
+
+/// How many log entries are kept in memory for display.
+const LOG_HISTORY: usize = 200;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum SyncField {
+    Name,
+    Value,
+    Date,
+}
+
+impl SyncField {
+    pub const ALL: [SyncField; 3] = [SyncField::Name, SyncField::Value, SyncField::Date];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            SyncField::Name => "Name",
+            SyncField::Value => "Value",
+            SyncField::Date => "Date",
+        }
+    }
+
+    fn differs(&self, a: &TableData, b: &TableData) -> bool {
+        match self {
+            SyncField::Name => a.name != b.name,
+            SyncField::Value => a.value != b.value,
+            SyncField::Date => a.date != b.date,
+        }
+    }
+
+    pub fn display(&self, record: &TableData, locale: Locale) -> String {
+        match self {
+            SyncField::Name => record.name.clone(),
+            SyncField::Value => locale.format_number(record.value, 2),
+            SyncField::Date => locale.format_date_time(&record.date),
+        }
+    }
+
+    fn copy(&self, from: &TableData, to: &mut TableData) {
+        match self {
+            SyncField::Name => to.name = from.name.clone(),
+            SyncField::Value => to.value = from.value,
+            SyncField::Date => to.date = from.date,
+        }
+    }
+}
+
+/// Sync bookkeeping persisted between sessions so offline edits survive a restart.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SyncState {
+    /// Last version of each record known to be on the server.
+    base: BTreeMap<u32, TableData>,
+    /// Local edits waiting to be pushed.
+    queue: BTreeMap<u32, TableData>,
+}
+
+impl SyncState {
+    fn path() -> PathBuf {
+        storage::app_data_dir().join("sync_state.json")
+    }
+
+    pub fn load() -> Self {
+        match fs::read(Self::path()) {
+            Ok(bytes) => serde_json::from_slice(&bytes).unwrap_or_else(|e| {
+                log::warn!("Ignoring invalid sync state: {}", e);
+                Self::default()
+            }),
+            Err(_) => Self::default(),
+        }
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = Self::path();
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        storage::write_atomic(&path, &serde_json::to_vec_pretty(self)?)
+    }
+
+    pub fn enqueue(&mut self, records: impl IntoIterator<Item = TableData>) {
+        for record in records {
+            self.queue.insert(record.id, record);
+        }
+    }
+
+    pub fn queued(&self) -> Vec<TableData> {
+        self.queue.values().cloned().collect()
+    }
+
+    pub fn queue_len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Records that reached the server become the new base. They leave the queue
+    /// unless they were edited again while the push was in flight. Returns the IDs
+    /// that left the queue, i.e. whose sent version is the latest local one.
+    pub fn mark_pushed(&mut self, records: &[TableData]) -> Vec<u32> {
+        let mut synced = Vec::new();
+        for record in records {
+            if self.queue.get(&record.id).is_some_and(|queued| !SyncField::ALL.iter().any(|f| f.differs(queued, record))) {
+                self.queue.remove(&record.id);
+                synced.push(record.id);
+            }
+            self.base.insert(record.id, record.clone());
+        }
+        synced
+    }
+
+    /// Applies a conflict decision. The server version becomes the base, and the
+    /// resolved record is queued unless it matches the server exactly.
+    pub fn resolve(&mut self, conflict: &Conflict, resolved: &TableData) {
+        self.base.insert(conflict.id(), conflict.remote.clone());
+        if SyncField::ALL.iter().any(|field| field.differs(resolved, &conflict.remote)) {
+            self.queue.insert(conflict.id(), resolved.clone());
+        } else {
+            self.queue.remove(&conflict.id());
+        }
+    }
+
+    /// Three-way merges the queued edits with freshly pulled remote records, using
+    /// the last synced version of each record as the common ancestor.
+    pub fn reconcile(&mut self, remote: Vec<TableData>) -> Reconciliation {
+        let remote: BTreeMap<u32, TableData> = remote.into_iter().map(|item| (item.id, item)).collect();
+        let mut records = remote.clone();
+        let mut to_push = Vec::new();
+        let mut conflicts = Vec::new();
+        let mut deleted = Vec::new();
+
+        for (id, local) in &self.queue {
+            let Some(theirs) = remote.get(id) else {
+                if self.base.contains_key(id) {
+                    // Synced before, so it was deleted on the server. The delete wins.
+                    deleted.push(*id);
+                } else {
+                    // Created locally and never pushed.
+                    records.insert(*id, local.clone());
+                    to_push.push(local.clone());
+                }
+                continue;
+            };
+
+            let base = self.base.get(id);
+            let mut merged = local.clone();
+            let mut conflicting = Vec::new();
+
+            for field in SyncField::ALL {
+                let local_changed = base.is_none_or(|base| field.differs(base, local));
+                let remote_changed = base.is_none_or(|base| field.differs(base, theirs));
+
+                if local_changed && remote_changed && field.differs(local, theirs) {
+                    conflicting.push(field);
+                } else if remote_changed && !local_changed {
+                    field.copy(theirs, &mut merged);
+                }
+            }
+
+            records.insert(*id, merged.clone());
+            if conflicting.is_empty() {
+                to_push.push(merged);
+            } else {
+                conflicts.push(Conflict {
+                    local: local.clone(),
+                    remote: theirs.clone(),
+                    merged,
+                    fields: conflicting.into_iter().map(|field| (field, true)).collect(),
+                });
+            }
+        }
+
+        // Unresolved conflicts keep their old base so the next sync detects them again.
+        let mut base = remote;
+        for conflict in &conflicts {
+            match self.base.remove(&conflict.id()) {
+                Some(old) => base.insert(conflict.id(), old),
+                None => base.remove(&conflict.id()),
+            };
+        }
+        self.base = base;
+        for id in &deleted {
+            self.queue.remove(id);
+        }
+        self.enqueue(to_push.iter().cloned());
+
+        Reconciliation {
+            records: records.into_values().collect(),
+            to_push,
+            conflicts,
+            deleted,
+        }
+    }
+}
+
+pub struct Reconciliation {
+    /// The dataset to show locally after merging.
+    pub records: Vec<TableData>,
+    /// Merged records that can be pushed without asking.
+    pub to_push: Vec<TableData>,
+    /// Records changed on both sides in the same field.
+    pub conflicts: Vec<Conflict>,
+    /// Locally edited records that were deleted on the server, whose edits were dropped.
+    pub deleted: Vec<u32>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Conflict {
+    pub local: TableData,
+    pub remote: TableData,
+    /// Local record with non-conflicting remote changes applied.
+    pub merged: TableData,
+    /// Conflicting fields, each with whether the local value is chosen when merging.
+    pub fields: Vec<(SyncField, bool)>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Resolution {
+    KeepMine,
+    KeepTheirs,
+    Merge,
+}
+
+impl Resolution {
+    pub fn label(&self) -> &'static str {
+        match self {
+            Resolution::KeepMine => "Keep mine",
+            Resolution::KeepTheirs => "Keep theirs",
+            Resolution::Merge => "Merge",
+        }
+    }
+}
+
+impl Conflict {
+    pub fn id(&self) -> u32 {
+        self.local.id
+    }
+
+    pub fn resolve(&self, resolution: Resolution) -> TableData {
+        match resolution {
+            Resolution::KeepMine => self.local.clone(),
+            Resolution::KeepTheirs => self.remote.clone(),
+            Resolution::Merge => {
+                let mut record = self.merged.clone();
+                for (field, use_mine) in &self.fields {
+                    let source = if *use_mine { &self.local } else { &self.remote };
+                    field.copy(source, &mut record);
+                }
+                record
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LoggedField {
+    pub field: SyncField,
+    pub mine: String,
+    pub theirs: String,
+    pub chosen: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SyncLogEntry {
+    pub at: DateTime<Local>,
+    pub record_id: u32,
+    pub resolution: Resolution,
+    pub fields: Vec<LoggedField>,
+}
+
+/// Append-only record of conflict resolutions, stored as JSON lines.
+pub struct SyncLog {
+    path: PathBuf,
+    entries: Vec<SyncLogEntry>,
+}
+
+impl SyncLog {
+    pub fn load() -> Self {
+        let path = storage::app_data_dir().join("sync_log.jsonl");
+        let mut entries: Vec<SyncLogEntry> = fs::read_to_string(&path)
+            .map(|text| text.lines().filter_map(|line| serde_json::from_str(line).ok()).collect())
+            .unwrap_or_default();
+        let skip = entries.len().saturating_sub(LOG_HISTORY);
+        entries.drain(..skip);
+        Self { path, entries }
+    }
+
+    pub fn entries(&self) -> &[SyncLogEntry] {
+        &self.entries
+    }
+
+    pub fn record(&mut self, conflict: &Conflict, resolution: Resolution, resolved: &TableData, locale: Locale) -> Result<()> {
+        let entry = SyncLogEntry {
+            at: Local::now(),
+            record_id: conflict.id(),
+            resolution,
+            fields: conflict
+                .fields
+                .iter()
+                .map(|(field, _)| LoggedField {
+                    field: *field,
+                    mine: field.display(&conflict.local, locale),
+                    theirs: field.display(&conflict.remote, locale),
+                    chosen: field.display(resolved, locale),
+                })
+                .collect(),
+        };
+
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+
+        self.entries.push(entry);
+        if self.entries.len() > LOG_HISTORY {
+            self.entries.remove(0);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::TimeZone;
+
+    fn record(id: u32, name: &str, value: f64) -> TableData {
+        TableData {
+            id,
+            name: name.to_string(),
+            value,
+            date: Local.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap(),
+        }
+    }
+
+    /// State after `base` was synced and `edits` were made locally.
+    fn state(base: &[TableData], edits: &[TableData]) -> SyncState {
+        let mut state = SyncState::default();
+        state.mark_pushed(base);
+        state.enqueue(edits.iter().cloned());
+        state
+    }
+
+    #[test]
+    fn edits_to_different_fields_merge_without_conflict() {
+        let mut state = state(&[record(1, "Old", 1.0)], &[record(1, "Mine", 1.0)]);
+        let result = state.reconcile(vec![record(1, "Old", 2.0)]);
+
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.to_push.len(), 1);
+        assert_eq!(result.to_push[0].name, "Mine");
+        assert_eq!(result.to_push[0].value, 2.0);
+        assert_eq!(result.records[0].name, "Mine");
+    }
+
+    #[test]
+    fn edits_to_the_same_field_conflict() {
+        let mut state = state(&[record(1, "Old", 1.0)], &[record(1, "Mine", 1.0)]);
+        let result = state.reconcile(vec![record(1, "Theirs", 1.0)]);
+
+        assert!(result.to_push.is_empty());
+        assert_eq!(result.conflicts.len(), 1);
+        let conflict = &result.conflicts[0];
+        assert_eq!(conflict.fields, [(SyncField::Name, true)]);
+        assert_eq!(conflict.resolve(Resolution::KeepMine).name, "Mine");
+        assert_eq!(conflict.resolve(Resolution::KeepTheirs).name, "Theirs");
+
+        // The old base is kept, so the conflict is found again on the next sync.
+        let again = state.reconcile(vec![record(1, "Theirs", 1.0)]);
+        assert_eq!(again.conflicts.len(), 1);
+    }
+
+    #[test]
+    fn identical_edits_on_both_sides_do_not_conflict() {
+        let mut state = state(&[record(1, "Old", 1.0)], &[record(1, "Same", 1.0)]);
+        let result = state.reconcile(vec![record(1, "Same", 1.0)]);
+
+        assert!(result.conflicts.is_empty());
+        assert_eq!(result.records[0].name, "Same");
+    }
+
+    #[test]
+    fn new_local_records_are_pushed() {
+        let mut state = state(&[record(1, "Synced", 1.0)], &[record(2, "New", 2.0)]);
+        let result = state.reconcile(vec![record(1, "Synced", 1.0)]);
+
+        assert_eq!(result.to_push.iter().map(|item| item.id).collect::<Vec<_>>(), [2]);
+        assert_eq!(result.records.len(), 2);
+        assert!(result.deleted.is_empty());
+    }
+
+    #[test]
+    fn remote_deletes_are_not_resurrected() {
+        let mut state = state(&[record(1, "Old", 1.0), record(2, "Kept", 2.0)], &[record(1, "Mine", 1.0)]);
+        let result = state.reconcile(vec![record(2, "Kept", 2.0)]);
+
+        assert_eq!(result.deleted, [1]);
+        assert!(result.to_push.is_empty());
+        assert_eq!(result.records.iter().map(|item| item.id).collect::<Vec<_>>(), [2]);
+        assert_eq!(state.queue_len(), 0);
+    }
+
+    #[test]
+    fn edits_made_during_a_push_stay_queued() {
+        let mut state = state(&[record(1, "Old", 1.0)], &[record(1, "Sent", 1.0)]);
+        let sent = state.queued();
+        state.enqueue([record(1, "Edited again", 1.0)]);
+
+        assert!(state.mark_pushed(&sent).is_empty());
+        assert_eq!(state.queued()[0].name, "Edited again");
+        assert_eq!(state.mark_pushed(&state.queued()), [1]);
+        assert_eq!(state.queue_len(), 0);
+    }
+}
@@ -0,0 +1,191 @@
+use chrono::{Datelike, Duration, Months, NaiveDate};
+
+use crate::data::{DateRange, TableData};
+use crate::locale::Locale;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Granularity {
+    #[default]
+    Day,
+    Week,
+    Month,
+}
+
+impl Granularity {
+    pub const ALL: [Granularity; 3] = [Granularity::Day, Granularity::Week, Granularity::Month];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Granularity::Day => "Day",
+            Granularity::Week => "Week",
+            Granularity::Month => "Month",
+        }
+    }
+
+    /// First day of the bucket containing `date`. Weeks start on Monday.
+    pub fn bucket_start(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Granularity::Day => date,
+            Granularity::Week => date - Duration::days(date.weekday().num_days_from_monday() as i64),
+            Granularity::Month => date.with_day(1).unwrap_or(date),
+        }
+    }
+
+    /// Last day (inclusive) of the bucket starting at `start`.
+    pub fn bucket_end(&self, start: NaiveDate) -> NaiveDate {
+        match self {
+            Granularity::Day => start,
+            Granularity::Week => start + Duration::days(6),
+            Granularity::Month => {
+                start.checked_add_months(Months::new(1)).map(|next| next - Duration::days(1)).unwrap_or(start)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct TimelineBucket {
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+    pub count: usize,
+    pub total: f64,
+}
+
+impl TimelineBucket {
+    pub fn label(&self, granularity: Granularity, locale: Locale) -> String {
+        match granularity {
+            Granularity::Day => locale.format_day(self.start),
+            // First to last day, which reads the same in every regional format.
+            Granularity::Week => format!("{} – {}", locale.format_day(self.start), locale.format_day(self.end)),
+            Granularity::Month => locale.format_month(self.start),
+        }
+    }
+
+    pub fn range(&self) -> DateRange {
+        DateRange::new(Some(self.start), Some(self.end))
+    }
+}
+
+/// Groups records into buckets of the given granularity, sorted by date.
+/// Only buckets that contain at least one record are returned.
+pub fn build_timeline<'a>(
+    data: impl IntoIterator<Item = &'a TableData>,
+    granularity: Granularity,
+) -> Vec<TimelineBucket> {
+    let mut buckets: Vec<TimelineBucket> = Vec::new();
+
+    for item in data {
+        let start = granularity.bucket_start(item.date.date_naive());
+        match buckets.binary_search_by_key(&start, |bucket| bucket.start) {
+            Ok(index) => {
+                buckets[index].count += 1;
+                buckets[index].total += item.value;
+            }
+            Err(index) => buckets.insert(
+                index,
+                TimelineBucket {
+                    start,
+                    end: granularity.bucket_end(start),
+                    count: 1,
+                    total: item.value,
+                },
+            ),
+        }
+    }
+
+    buckets
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{Local, TimeZone};
+
+    fn record(id: u32, year: i32, month: u32, day: u32, value: f64) -> TableData {
+        TableData {
+            id,
+            name: format!("Record {}", id),
+            value,
+            date: Local.with_ymd_and_hms(year, month, day, 12, 0, 0).unwrap(),
+        }
+    }
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    #[test]
+    fn weeks_run_from_monday_to_sunday() {
+        let data = [
+            record(1, 2024, 6, 9, 1.0),  // Sunday
+            record(2, 2024, 6, 10, 2.0), // Monday
+            record(3, 2024, 6, 16, 4.0), // Sunday
+            record(4, 2024, 6, 17, 8.0), // Monday
+        ];
+        let buckets = build_timeline(&data, Granularity::Week);
+
+        let ranges: Vec<_> = buckets.iter().map(|bucket| (bucket.start, bucket.end, bucket.count)).collect();
+        assert_eq!(
+            ranges,
+            [
+                (date(2024, 6, 3), date(2024, 6, 9), 1),
+                (date(2024, 6, 10), date(2024, 6, 16), 2),
+                (date(2024, 6, 17), date(2024, 6, 23), 1),
+            ]
+        );
+        assert_eq!(buckets[1].total, 6.0);
+    }
+
+    #[test]
+    fn week_spanning_new_year_is_one_bucket() {
+        let data = [record(1, 2024, 12, 31, 1.0), record(2, 2025, 1, 5, 2.0)];
+        let buckets = build_timeline(&data, Granularity::Week);
+
+        assert_eq!(buckets.len(), 1);
+        assert_eq!(buckets[0].start, date(2024, 12, 30));
+        assert_eq!(buckets[0].end, date(2025, 1, 5));
+        assert_eq!(buckets[0].label(Granularity::Week, Locale::Standard), "2024-12-30 – 2025-01-05");
+        assert_eq!(buckets[0].label(Granularity::Week, Locale::German), "30.12.2024 – 05.01.2025");
+    }
+
+    #[test]
+    fn months_end_on_their_last_day() {
+        let data = [
+            record(1, 2024, 1, 31, 1.0),
+            record(2, 2024, 2, 1, 2.0),
+            record(3, 2024, 2, 29, 4.0),
+            record(4, 2023, 2, 28, 8.0),
+            record(5, 2024, 12, 31, 16.0),
+        ];
+        let buckets = build_timeline(&data, Granularity::Month);
+
+        let ranges: Vec<_> = buckets.iter().map(|bucket| (bucket.start, bucket.end, bucket.count)).collect();
+        assert_eq!(
+            ranges,
+            [
+                (date(2023, 2, 1), date(2023, 2, 28), 1),
+                (date(2024, 1, 1), date(2024, 1, 31), 1),
+                (date(2024, 2, 1), date(2024, 2, 29), 2),
+                (date(2024, 12, 1), date(2024, 12, 31), 1),
+            ]
+        );
+        assert_eq!(buckets[2].total, 6.0);
+        assert_eq!(buckets[2].label(Granularity::Month, Locale::Standard), "2024-02");
+        assert_eq!(buckets[2].label(Granularity::Month, Locale::EnglishUs), "02/2024");
+    }
+
+    #[test]
+    fn days_are_sorted_and_only_non_empty() {
+        let data = [record(1, 2024, 3, 2, 1.0), record(2, 2024, 3, 1, 2.0), record(3, 2024, 3, 2, 3.0)];
+        let buckets = build_timeline(&data, Granularity::Day);
+
+        let days: Vec<_> = buckets.iter().map(|bucket| (bucket.start, bucket.count, bucket.total)).collect();
+        assert_eq!(days, [(date(2024, 3, 1), 1, 2.0), (date(2024, 3, 2), 2, 4.0)]);
+        assert_eq!(buckets[1].range(), DateRange::new(Some(date(2024, 3, 2)), Some(date(2024, 3, 2))));
+    }
+
+    #[test]
+    fn no_records_give_no_buckets() {
+        assert!(build_timeline(&[], Granularity::Month).is_empty());
+    }
+}
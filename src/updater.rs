@@ -1,6 +1,58 @@
-use anyhow::Result;
+use anyhow::{Result, anyhow};
 use self_update::cargo_crate_version;
+use semver::Version;
+use serde::{Deserialize, Serialize};
 
+/// Which releases the updater offers. Each channel also includes the more
+/// stable ones, so beta testers move on to the final release.
+#[derive(Debug, Clone, Copy, Default, PartialEq, PartialOrd, Serialize, Deserialize)]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+    Nightly,
+}
+
+impl UpdateChannel {
+    pub const ALL: [UpdateChannel; 3] = [UpdateChannel::Stable, UpdateChannel::Beta, UpdateChannel::Nightly];
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => "Stable",
+            UpdateChannel::Beta => "Beta",
+            UpdateChannel::Nightly => "Nightly",
+        }
+    }
+
+    pub fn description(&self) -> &'static str {
+        match self {
+            UpdateChannel::Stable => "Tested releases only",
+            UpdateChannel::Beta => "Betas and release candidates ahead of each release",
+            UpdateChannel::Nightly => "Latest development builds, which may be unstable",
+        }
+    }
+
+    /// Channel a version is published on, from its pre-release suffix: none is
+    /// stable, `beta` and `rc` are beta, anything else such as `nightly` or
+    /// `alpha` is nightly.
+    pub fn of(version: &Version) -> Self {
+        let suffix = version.pre.as_str().to_ascii_lowercase();
+        if suffix.is_empty() {
+            UpdateChannel::Stable
+        } else if suffix.starts_with("beta") || suffix.starts_with("rc") {
+            UpdateChannel::Beta
+        } else {
+            UpdateChannel::Nightly
+        }
+    }
+
+    /// Whether releases published on `channel` are offered on this one.
+    pub fn includes(&self, channel: UpdateChannel) -> bool {
+        channel <= *self
+    }
+}
+
+#[derive(Clone)]
 pub struct AppUpdater {
     current_version: String,
 }
@@ -12,7 +64,7 @@ impl AppUpdater {
         }
     }
 
-    pub async fn check_for_updates(&self) -> Result<Option<String>> {
+    pub async fn check_for_updates(&self, channel: UpdateChannel) -> Result<Option<String>> {
         // Replace with your actual GitHub username and repository name
         let releases = self_update::backends::github::ReleaseList::configure()
             .repo_owner("mirekbohm") 
@@ -20,31 +72,150 @@ impl AppUpdater {
             .build()?
             .fetch()?;
 
-        if let Some(latest_release) = releases.first() {
-            let latest_version = &latest_release.version;
-            
-            // Simple version comparison
-            if latest_version != &self.current_version {
-                println!("New version available: {}", latest_version);
-                return Ok(Some(latest_version.clone()));
+        let current = self
+            .current_version()
+            .ok_or_else(|| anyhow!("Invalid current version {}", self.current_version))?;
+
+        let latest = newest_release(releases.iter().map(|release| release.version.as_str()), &current, channel);
+
+        if let Some(version) = &latest {
+            log::info!("New version available: {}", version);
+        }
+        Ok(latest.map(|version| version.to_string()))
+    }
+
+    /// Version of the running application.
+    pub fn current_version(&self) -> Option<Version> {
+        parse_version(&self.current_version)
+    }
+
+    /// Installs the release with the given version, which may be lower than the
+    /// running one when switching to a more stable channel.
+    pub async fn update_app(&self, version: &str) -> Result<()> {
+        // Release tags are usually `v`-prefixed, but a bare version is accepted too
+        let mut result = Err(anyhow!("No release found for version {}", version));
+        for tag in [format!("v{}", version), version.to_string()] {
+            result = self_update::backends::github::Update::configure()
+                .repo_owner("mirekbohm")  
+                .repo_name("rust-desktop-app")  
+                .bin_name("desktop-app") 
+                .target("x86_64-pc-windows-msvc") 
+                .show_download_progress(true)
+                .current_version(cargo_crate_version!())
+                .target_version_tag(&tag)
+                .build()
+                .and_then(|update| update.update())
+                .map_err(anyhow::Error::from);
+            if result.is_ok() {
+                break;
             }
         }
-        
-        Ok(None)
-    }
-
-    pub async fn update_app(&self) -> Result<()> {
-        let status = self_update::backends::github::Update::configure()
-            .repo_owner("mirekbohm")  
-            .repo_name("rust-desktop-app")  
-            .bin_name("desktop-app") 
-            .target("x86_64-pc-windows-msvc") 
-            .show_download_progress(true)
-            .current_version(cargo_crate_version!())
-            .build()?
-            .update()?;
 
-        println!("Update status: `{}`!", status.version());
+        let status = result?;
+        log::info!("Update status: {}", status.version());
         Ok(())
     }
 }
+
+/// Release to offer from the published versions, by semantic version order.
+///
+/// After switching to a more stable channel, e.g. from beta back to stable,
+/// its newest release is offered even though its version is lower than the
+/// running pre-release. Otherwise only newer releases are offered. Tags that
+/// are not semantic versions are ignored.
+pub fn newest_release<'a>(
+    versions: impl IntoIterator<Item = &'a str>,
+    current: &Version,
+    channel: UpdateChannel,
+) -> Option<Version> {
+    let leaving = !channel.includes(UpdateChannel::of(current));
+    versions
+        .into_iter()
+        .filter_map(parse_version)
+        .filter(|version| channel.includes(UpdateChannel::of(version)))
+        .filter(|version| leaving || version > current)
+        .max()
+}
+
+/// Parses a version such as `1.2.3`, `v1.2.3` or `1.3.0-beta.1`.
+pub fn parse_version(text: &str) -> Option<Version> {
+    let text = text.trim();
+    Version::parse(text.strip_prefix(['v', 'V']).unwrap_or(text)).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn version(text: &str) -> Version {
+        parse_version(text).unwrap()
+    }
+
+    fn newest(versions: &[&str], current: &str, channel: UpdateChannel) -> Option<String> {
+        newest_release(versions.iter().copied(), &version(current), channel).map(|version| version.to_string())
+    }
+
+    #[test]
+    fn versions_parse_with_or_without_prefix() {
+        assert_eq!(version("v1.2.3"), version("1.2.3"));
+        assert_eq!(version(" V1.3.0-beta.1 ").pre.as_str(), "beta.1");
+        assert!(parse_version("latest").is_none());
+    }
+
+    #[test]
+    fn versions_compare_numerically_not_as_text() {
+        assert_eq!(newest(&["0.9.0", "0.10.0", "0.4.5"], "0.4.4", UpdateChannel::Stable).as_deref(), Some("0.10.0"));
+        assert_eq!(newest(&["0.4.3", "0.4.4"], "0.4.4", UpdateChannel::Stable), None);
+        assert_eq!(newest(&["v0.5.0", "not-a-version"], "0.4.4", UpdateChannel::Stable).as_deref(), Some("0.5.0"));
+    }
+
+    #[test]
+    fn prereleases_order_before_their_release() {
+        assert!(version("1.0.0-beta.2") < version("1.0.0-beta.10"));
+        assert!(version("1.0.0-beta.10") < version("1.0.0-rc.1"));
+        assert!(version("1.0.0-rc.1") < version("1.0.0"));
+        assert_eq!(
+            newest(&["1.0.0-beta.2", "1.0.0-beta.10", "1.0.0-rc.1", "1.0.0"], "0.9.0", UpdateChannel::Nightly).as_deref(),
+            Some("1.0.0")
+        );
+        assert_eq!(
+            newest(&["1.0.0-beta.2", "1.0.0-beta.10"], "1.0.0-beta.2", UpdateChannel::Nightly).as_deref(),
+            Some("1.0.0-beta.10")
+        );
+    }
+
+    #[test]
+    fn prerelease_suffixes_map_to_channels() {
+        assert_eq!(UpdateChannel::of(&version("1.0.0")), UpdateChannel::Stable);
+        assert_eq!(UpdateChannel::of(&version("1.0.0-beta.1")), UpdateChannel::Beta);
+        assert_eq!(UpdateChannel::of(&version("1.0.0-RC.2")), UpdateChannel::Beta);
+        assert_eq!(UpdateChannel::of(&version("1.0.0-nightly.20240101")), UpdateChannel::Nightly);
+        assert_eq!(UpdateChannel::of(&version("1.0.0-alpha")), UpdateChannel::Nightly);
+    }
+
+    #[test]
+    fn channels_only_offer_their_own_and_more_stable_releases() {
+        let published = ["1.0.0", "1.1.0-beta.1", "1.1.0-nightly.5"];
+        assert_eq!(newest(&published, "1.0.0", UpdateChannel::Stable), None);
+        assert_eq!(newest(&published, "1.0.0", UpdateChannel::Beta).as_deref(), Some("1.1.0-beta.1"));
+        assert_eq!(newest(&published, "1.0.0", UpdateChannel::Nightly).as_deref(), Some("1.1.0-nightly.5"));
+        // Beta testers move on to the final release once it is out.
+        assert_eq!(
+            newest(&["1.1.0-beta.1", "1.1.0"], "1.1.0-beta.1", UpdateChannel::Beta).as_deref(),
+            Some("1.1.0")
+        );
+    }
+
+    #[test]
+    fn switching_back_to_stable_offers_its_newest_release() {
+        let published = ["1.0.0", "0.9.0", "1.1.0-beta.2"];
+        // The running beta is newer than any stable release, yet 1.0.0 is offered.
+        assert_eq!(newest(&published, "1.1.0-beta.2", UpdateChannel::Stable).as_deref(), Some("1.0.0"));
+        assert_eq!(newest(&published, "1.1.0-beta.2", UpdateChannel::Beta), None);
+        // Leaving nightly for beta offers the newest beta or stable release.
+        assert_eq!(
+            newest(&["1.0.0", "1.1.0-beta.2", "1.2.0-nightly.1"], "1.2.0-nightly.1", UpdateChannel::Beta).as_deref(),
+            Some("1.1.0-beta.2")
+        );
+    }
+}